@@ -0,0 +1,177 @@
+use postgres::GenericClient;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::Deck;
+use crate::i18n::Message;
+use crate::metrics;
+
+/// The prefix of the generated keys, making them easy to recognize in a configuration.
+pub const KEY_PREFIX: &str = "cg_";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AccessError {
+    /// The deck has an owner but the request has no API key.
+    Unauthorized,
+    /// The API key neither owns the deck nor was granted access to it.
+    Forbidden,
+    /// The token doesn't have one of the scopes needed on the deck or the game.
+    MissingScope,
+    /// Only the admin, with the `X-Admin-Token` header, is allowed.
+    AdminRequired,
+}
+
+impl AccessError {
+    pub fn message(&self) -> Message {
+        match self {
+            AccessError::Unauthorized => Message::Unauthorized,
+            AccessError::Forbidden => Message::Forbidden,
+            AccessError::MissingScope => Message::MissingScope,
+            AccessError::AdminRequired => Message::AdminRequired,
+        }
+    }
+}
+
+/// Tells if an API key may change a deck: a deck without owner can be changed by anybody,
+/// otherwise only by its owner or by the keys it granted.
+///
+/// # Arguments
+///
+/// * `api_key`: The id of the API key of the request, if any.
+/// * `owner`: The owner of the deck, if any.
+/// * `is_granted`: Tells if the owner granted access to the API key.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::api_key::{check_access, AccessError};
+/// use uuid::Uuid;
+///
+/// fn main() {
+///     let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+///     assert_eq!(check_access(None, None, |_| false), Ok(()));
+///     assert_eq!(check_access(Some(owner), Some(owner), |_| false), Ok(()));
+///     assert_eq!(check_access(None, Some(owner), |_| false), Err(AccessError::Unauthorized));
+///     assert_eq!(check_access(Some(other), Some(owner), |_| false), Err(AccessError::Forbidden));
+///     assert_eq!(check_access(Some(other), Some(owner), |_| true), Ok(()));
+/// }
+/// ```
+pub fn check_access<F: FnOnce(Uuid) -> bool>(api_key: Option<Uuid>, owner: Option<Uuid>, is_granted: F) -> Result<(), AccessError> {
+    match (api_key, owner) {
+        (_, None) => Ok(()),
+        (None, Some(_)) => Err(AccessError::Unauthorized),
+        (Some(api_key), Some(owner)) if api_key == owner => Ok(()),
+        (Some(api_key), Some(_)) if is_granted(api_key) => Ok(()),
+        _ => Err(AccessError::Forbidden)
+    }
+}
+
+/// The key authenticating the requests of a client, of which only the hash is stored.
+#[derive(Clone, Debug)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_hash: String,
+}
+
+impl ApiKey {
+    /// Generates a new API key.
+    ///
+    /// returns: (ApiKey, String), the API key to store and the clear key, which can't be found again.
+    pub fn generate(name: &str) -> (ApiKey, String) {
+        let secret: [u8; 32] = rand::thread_rng().gen();
+        let key = format!("{}{}", KEY_PREFIX, hex::encode(secret));
+        (ApiKey { id: Uuid::new_v4(), name: name.trim().to_string(), key_hash: ApiKey::hash(&key) }, key)
+    }
+
+    /// Hashes a clear key with SHA-256.
+    pub fn hash(key: &str) -> String {
+        hex::encode(Sha256::digest(key.as_bytes()))
+    }
+
+    pub fn insert_into_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "INSERT INTO api_keys (id, name, key_hash) VALUES ($1, $2, $3)"
+            ).unwrap();
+        metrics::time_query("insert_api_key", || connection.execute(&stmt, &[&self.id, &self.name, &self.key_hash])).expect("An error occurred.");
+    }
+
+    pub fn delete_from_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "DELETE FROM api_keys WHERE id = $1"
+            ).unwrap();
+
+        metrics::time_query("delete_api_key", || connection.execute(&stmt, &[&self.id])).expect("An error occurred.");
+    }
+
+    pub fn find_by_id<C: GenericClient>(id: Uuid, connection: &mut C) -> Option<ApiKey> {
+        ApiKey::find_with_query(&id, connection, "SELECT id, name, key_hash FROM api_keys WHERE id = $1")
+    }
+
+    /// Finds the API key matching a clear key.
+    pub fn find_by_key<C: GenericClient>(key: &str, connection: &mut C) -> Option<ApiKey> {
+        ApiKey::find_with_query(&ApiKey::hash(key), connection, "SELECT id, name, key_hash FROM api_keys WHERE key_hash = $1")
+    }
+
+    fn find_with_query<C: GenericClient>(parameter: &(dyn postgres::types::ToSql + Sync), connection: &mut C, query: &str) -> Option<ApiKey> {
+        let stmt = connection.prepare(query).unwrap();
+        let rows = metrics::time_query("find_api_key", || connection.query(&stmt, &[parameter]));
+
+        match rows {
+            Ok(rows) => {
+                let row = rows.first()?;
+                Some(ApiKey { id: row.get(0), name: row.get(1), key_hash: row.get(2) })
+            }
+            Err(_) => None
+        }
+    }
+
+    /// Tells if the API key may change a deck, see `check_access`.
+    pub fn authorize<C: GenericClient>(api_key: Option<&ApiKey>, deck: &Deck, connection: &mut C) -> Result<(), AccessError> {
+        check_access(api_key.map(|api_key| api_key.id), deck.owner, |api_key| is_granted(deck.id, api_key, connection))
+    }
+}
+
+/// Lets an API key change a deck it doesn't own.
+pub fn grant<C: GenericClient>(deck_id: Uuid, api_key: Uuid, connection: &mut C) {
+    let stmt = connection
+        .prepare(
+            "INSERT INTO deck_grants (deck_id, api_key_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        ).unwrap();
+    metrics::time_query("grant_deck", || connection.execute(&stmt, &[&deck_id, &api_key])).expect("An error occurred.");
+}
+
+pub fn revoke<C: GenericClient>(deck_id: Uuid, api_key: Uuid, connection: &mut C) {
+    let stmt = connection
+        .prepare(
+            "DELETE FROM deck_grants WHERE deck_id = $1 AND api_key_id = $2"
+        ).unwrap();
+    metrics::time_query("revoke_deck", || connection.execute(&stmt, &[&deck_id, &api_key])).expect("An error occurred.");
+}
+
+pub fn is_granted<C: GenericClient>(deck_id: Uuid, api_key: Uuid, connection: &mut C) -> bool {
+    let stmt = connection
+        .prepare(
+            "SELECT 1 FROM deck_grants WHERE deck_id = $1 AND api_key_id = $2"
+        ).unwrap();
+    metrics::time_query("find_grant", || connection.query(&stmt, &[&deck_id, &api_key])).map(|rows| !rows.is_empty()).unwrap_or(false)
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ApiKeyRepresentation {
+    pub id: Uuid,
+    pub name: String,
+    /// The clear key, only shown when the API key is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl ApiKeyRepresentation {
+    pub fn from(api_key: &ApiKey, key: Option<String>) -> ApiKeyRepresentation {
+        ApiKeyRepresentation { id: api_key.id, name: api_key.name.clone(), key }
+    }
+}
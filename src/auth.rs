@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use actix_web::{Error, HttpMessage};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::{header, StatusCode};
+use futures::future::{err, ok, Either, Ready};
+use jeu_de_carte::api_key::ApiKey;
+use jeu_de_carte::database::establish_connection;
+use jeu_de_carte::i18n::{Language, Message};
+use jeu_de_carte::jwt;
+use jeu_de_carte::rate_limit::{self, Limit, RateLimiter};
+use crate::controller::Failure;
+use crate::limiter;
+
+/// The header carrying the API key, when it is not sent as a bearer token.
+const API_KEY: &str = "X-Api-Key";
+
+/// Finds the API key or the token of a request, from the `Authorization: Bearer` header or the `X-Api-Key` header.
+fn credentials(req: &ServiceRequest) -> Option<String> {
+    let bearer = req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let api_key = req.headers()
+        .get(API_KEY)
+        .and_then(|value| value.to_str().ok());
+
+    bearer.or(api_key).map(|key| key.trim().to_string())
+}
+
+/// Authenticates the requests sending an API key or a token, the `ApiKey` or the token `Claims`
+/// being stored in the request extensions.
+///
+/// The requests without credentials go through anonymously, the handlers deciding what they may do.
+///
+/// The failed authentications are limited per address, an address over its limit being refused
+/// before its credentials are looked up.
+#[derive(Clone)]
+pub struct Authentication {
+    failures: Option<Arc<RateLimiter>>,
+}
+
+impl Authentication {
+    /// Reads the limit of the failed authentications from the `RATE_LIMIT_AUTHENTICATION` environment variable.
+    pub fn from_env() -> Authentication {
+        let limit = Limit::from_env("RATE_LIMIT_AUTHENTICATION", rate_limit::DEFAULT_AUTHENTICATION_LIMIT);
+        Authentication { failures: limit.map(|limit| Arc::new(RateLimiter::new(limit))) }
+    }
+}
+
+impl<S, B> Transform<S> for Authentication
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AuthenticationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AuthenticationMiddleware { service, failures: self.failures.clone() })
+    }
+}
+
+pub struct AuthenticationMiddleware<S> {
+    service: S,
+    failures: Option<Arc<RateLimiter>>,
+}
+
+impl<S> AuthenticationMiddleware<S> {
+    /// Refuses the credentials, counting the failure against the address of the request.
+    fn refuse(&self, req: &ServiceRequest, message: Message) -> Error {
+        if let Some(failures) = &self.failures {
+            failures.check(&limiter::address(req), Instant::now());
+        }
+        unauthorized(req, message)
+    }
+}
+
+impl<S, B> Service for AuthenticationMiddleware<S>
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let credential = match credentials(&req) {
+            Some(credential) => credential,
+            None => return Either::Left(self.service.call(req))
+        };
+        if let Some(failures) = &self.failures {
+            let decision = failures.peek(&limiter::address(&req), Instant::now());
+            if !decision.allowed {
+                return Either::Right(err(limiter::too_many_requests(&req, &decision)));
+            }
+        }
+
+        if jwt::is_token(&credential) {
+            return match jwt::verify(jwt::secret(), &credential) {
+                Some(claims) => {
+                    req.extensions_mut().insert(claims);
+                    Either::Left(self.service.call(req))
+                }
+                None => Either::Right(err(self.refuse(&req, Message::InvalidToken)))
+            };
+        }
+
+        let mut connection = establish_connection();
+        let api_key = ApiKey::find_by_key(&credential, &mut connection);
+        connection.close().ok();
+
+        match api_key {
+            Some(api_key) => {
+                req.extensions_mut().insert(api_key);
+                Either::Left(self.service.call(req))
+            }
+            None => Either::Right(err(self.refuse(&req, Message::Unauthorized)))
+        }
+    }
+}
+
+/// Picks the language of an error answered by a middleware from the `Accept-Language` header.
+pub(crate) fn service_language(req: &ServiceRequest) -> Language {
+    let accept_language = req.headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+
+    Language::negotiate(None, accept_language)
+}
+
+fn unauthorized(req: &ServiceRequest, message: Message) -> Error {
+    let language = service_language(req);
+    let response = Failure::new(StatusCode::UNAUTHORIZED, message).respond(language);
+    InternalError::from_response("", response).into()
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::{Card, CardRepresentation, Deck, DeckRepresentation};
+use crate::i18n::{Language, Message};
+use crate::jwt::Scope;
+use crate::theme::Theme;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Operation {
+    Shuffle {
+        shuffle_discarded: Option<bool>,
+    },
+    Take {
+        lifo: Option<bool>,
+        length: Option<usize>,
+        move_as_block: Option<bool>,
+    },
+    Put {
+        lifo: Option<bool>,
+        length: Option<usize>,
+        move_as_block: Option<bool>,
+    },
+    Move {
+        #[serde(deserialize_with = "crate::card::deserialize_cards")]
+        cards: Vec<Card>,
+        to_discarded: Option<bool>,
+    },
+    Deal {
+        hands: usize,
+        length: usize,
+    },
+}
+
+impl Operation {
+    /// The scope a token needs to apply the operation.
+    pub fn scope(&self) -> Scope {
+        match self {
+            Operation::Shuffle { .. } => Scope::Shuffle,
+            Operation::Take { .. } | Operation::Deal { .. } => Scope::Draw,
+            Operation::Put { .. } => Scope::Put,
+            Operation::Move { to_discarded, .. } if to_discarded.unwrap_or(true) => Scope::Draw,
+            Operation::Move { .. } => Scope::Put,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BatchRequest {
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StepResult {
+    Shuffled,
+    Moved(Vec<Card>),
+    Dealt(Vec<Vec<Card>>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BatchErrorKind {
+    CardNotFound(Card),
+    NotEnoughCards,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BatchError {
+    pub step: usize,
+    pub kind: BatchErrorKind,
+}
+
+impl BatchError {
+    pub fn message(&self) -> Message {
+        match self.kind {
+            BatchErrorKind::CardNotFound(card) => Message::BatchCardNotInStack(self.step, card),
+            BatchErrorKind::NotEnoughCards => Message::BatchNotEnoughCards(self.step),
+        }
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+/// Applies a list of operations to a deck, in order.
+///
+/// The deck is left untouched when one of the operations fails, so the batch can be applied
+/// all-or-nothing before persisting it.
+///
+/// # Arguments
+///
+/// * `deck`: The deck to update.
+/// * `operations`: The operations to apply.
+///
+/// returns: Result<Vec<StepResult>, BatchError>, the result of each operation or the first error.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::{Deck, DeckSize};
+/// use jeu_de_carte::batch::{apply, Operation};
+///
+/// fn main() {
+///     let mut deck = Deck::new(DeckSize::Normal);
+///     let operations = vec![
+///         Operation::Shuffle { shuffle_discarded: None },
+///         Operation::Take { lifo: Some(true), length: Some(2), move_as_block: None },
+///     ];
+///     let results = apply(&mut deck, &operations).unwrap();
+///     assert_eq!(results.len(), 2);
+///     assert_eq!(deck.discarded.len(), 2);
+/// }
+/// ```
+pub fn apply(deck: &mut Deck, operations: &[Operation]) -> Result<Vec<StepResult>, BatchError> {
+    let mut working = deck.clone();
+    let mut results = Vec::with_capacity(operations.len());
+
+    for (step, operation) in operations.iter().enumerate() {
+        let result = match operation {
+            Operation::Shuffle { shuffle_discarded } => {
+                working.shuffle(shuffle_discarded.unwrap_or(false));
+                StepResult::Shuffled
+            }
+            Operation::Take { lifo, length, move_as_block } => StepResult::Moved(working.take(
+                lifo.unwrap_or(false),
+                length.unwrap_or(1),
+                move_as_block.unwrap_or(false),
+            )),
+            Operation::Put { lifo, length, move_as_block } => StepResult::Moved(working.put(
+                lifo.unwrap_or(false),
+                length.unwrap_or(1),
+                move_as_block.unwrap_or(false),
+            )),
+            Operation::Move { cards, to_discarded } => {
+                working.move_cards(cards, to_discarded.unwrap_or(true))
+                    .map_err(|card| BatchError { step, kind: BatchErrorKind::CardNotFound(card) })?;
+                StepResult::Moved(cards.clone())
+            }
+            Operation::Deal { hands, length } => {
+                let dealt = working.deal(*hands, *length)
+                    .ok_or(BatchError { step, kind: BatchErrorKind::NotEnoughCards })?;
+                StepResult::Dealt(dealt)
+            }
+        };
+        results.push(result);
+    }
+
+    *deck = working;
+    Ok(results)
+}
+
+/// Counts the cards drawn by applied operations, the cards put back into the deck not counting.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::{Deck, DeckSize};
+/// use jeu_de_carte::batch::{apply, drawn, Operation};
+///
+/// fn main() {
+///     let mut deck = Deck::new(DeckSize::Normal);
+///     let operations = vec![
+///         Operation::Take { lifo: None, length: Some(3), move_as_block: None },
+///         Operation::Put { lifo: None, length: Some(1), move_as_block: None },
+///         Operation::Deal { hands: 2, length: 2 },
+///     ];
+///     let results = apply(&mut deck, &operations).unwrap();
+///     assert_eq!(drawn(&operations, &results), 7);
+/// }
+/// ```
+pub fn drawn(operations: &[Operation], results: &[StepResult]) -> usize {
+    operations.iter()
+        .zip(results.iter())
+        .map(|(operation, result)| match (operation, result) {
+            (Operation::Take { .. }, StepResult::Moved(cards)) => cards.len(),
+            (Operation::Move { to_discarded, .. }, StepResult::Moved(cards)) if to_discarded.unwrap_or(true) => cards.len(),
+            (_, StepResult::Dealt(hands)) => hands.iter().map(Vec::len).sum(),
+            _ => 0
+        })
+        .sum()
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StepRepresentation {
+    Shuffled,
+    Moved { cards: Vec<CardRepresentation> },
+    Dealt { hands: Vec<Vec<CardRepresentation>> },
+}
+
+impl StepRepresentation {
+    pub fn from(result: StepResult, language: Language, theme: &Theme) -> StepRepresentation {
+        match result {
+            StepResult::Shuffled => StepRepresentation::Shuffled,
+            StepResult::Moved(cards) => StepRepresentation::Moved { cards: DeckRepresentation::translate_cards(cards, language, theme) },
+            StepResult::Dealt(hands) => StepRepresentation::Dealt {
+                hands: hands.into_iter().map(|hand| DeckRepresentation::translate_cards(hand, language, theme)).collect()
+            },
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BatchRepresentation {
+    pub deck: DeckRepresentation,
+    pub results: Vec<StepRepresentation>,
+}
+
+impl BatchRepresentation {
+    pub fn from(deck: Deck, results: Vec<StepResult>) -> BatchRepresentation {
+        let theme = Theme::resolve(&[deck.theme.as_deref()]);
+        BatchRepresentation::translated(deck, results, Language::default(), &theme)
+    }
+
+    /// Represents the batch result with the card names in the given language and the images of the given theme.
+    pub fn translated(deck: Deck, results: Vec<StepResult>, language: Language, theme: &Theme) -> BatchRepresentation {
+        BatchRepresentation {
+            deck: DeckRepresentation::translated(deck, language, theme),
+            results: results.into_iter().map(|result| StepRepresentation::from(result, language, theme)).collect(),
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::fmt;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::{Card, CardRepresentation, Deck, DeckRepresentation, DeckSize};
+use crate::card::{PlayingCard, Rank, Suit};
+use crate::i18n::{Language, Message};
+use crate::theme::Theme;
+use crate::trick::{self, to_card, Played, Trick};
+
+pub const SESSION_KIND: &str = "belote";
+pub const PLAYERS: usize = 4;
+pub const DEFAULT_TARGET: i32 = 1000;
+/// The value of a capot contract, all the tricks being won by the taker's team.
+pub const CAPOT: u16 = 250;
+/// Total of the card points, plus the 10 points of the last trick.
+pub const ROUND_POINTS: i32 = 162;
+
+const LAST_TRICK_BONUS: i32 = 10;
+const BELOTE_BONUS: i32 = 20;
+/// Number of cards given to each player at each pass of the deal: 3, then 2, then 3.
+const PACKETS: [usize; 3] = [3, 2, 3];
+
+/// The rank of a card within its suit, from 1 for the seven to 8 for the master card.
+pub fn card_order(card: PlayingCard, trump: Suit) -> u8 {
+    let trumped = card.suit() == Some(trump);
+    match card.rank() {
+        Some(Rank::Jack) if trumped => 8,
+        Some(Rank::Nine) if trumped => 7,
+        Some(Rank::Ace) => if trumped { 6 } else { 8 },
+        Some(Rank::Ten) => if trumped { 5 } else { 7 },
+        Some(Rank::King) => if trumped { 4 } else { 6 },
+        Some(Rank::Queen) => if trumped { 3 } else { 5 },
+        Some(Rank::Jack) => 4,
+        Some(Rank::Nine) => 3,
+        Some(Rank::Eight) => 2,
+        _ => 1,
+    }
+}
+
+/// The points of a card, the jack and the nine of trump being worth 20 and 14 points.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::belote::card_points;
+/// use jeu_de_carte::card::Suit;
+///
+/// fn main() {
+///     assert_eq!(card_points("JH".parse().unwrap(), Suit::Hearts), 20);
+///     assert_eq!(card_points("JS".parse().unwrap(), Suit::Hearts), 2);
+///     assert_eq!(card_points("AS".parse().unwrap(), Suit::Hearts), 11);
+/// }
+/// ```
+pub fn card_points(card: PlayingCard, trump: Suit) -> i32 {
+    let trumped = card.suit() == Some(trump);
+    match card.rank() {
+        Some(Rank::Jack) => if trumped { 20 } else { 2 },
+        Some(Rank::Nine) if trumped => 14,
+        Some(Rank::Ace) => 11,
+        Some(Rank::Ten) => 10,
+        Some(Rank::King) => 4,
+        Some(Rank::Queen) => 3,
+        _ => 0,
+    }
+}
+
+pub fn team(seat: usize) -> usize {
+    seat % 2
+}
+
+/// Finds the seat winning a trick: the highest trump, or the highest card of the led suit.
+pub fn trick_winner(trick: &[Played], trump: Suit) -> Option<usize> {
+    trick::winner(trick, Some(trump), |card| card_order(card, trump))
+}
+
+/// Finds the cards a player may play on a trick.
+///
+/// The player must follow the led suit, going over the highest trump when trump is led.
+/// Without the led suit, the player must trump unless their partner is winning the trick,
+/// going over the highest trump already played when possible.
+pub fn legal_cards(hand: &[Card], trick: &[Played], seat: usize, trump: Suit) -> Vec<Card> {
+    let led = match trick.first() {
+        Some(played) => to_card(played.card).suit(),
+        None => return hand.to_vec()
+    };
+    let highest_trump = trick.iter()
+        .map(|played| to_card(played.card))
+        .filter(|card| card.suit() == Some(trump))
+        .map(|card| card_order(card, trump))
+        .max();
+    let over_trumps = |cards: &[Card]| -> Vec<Card> {
+        cards.iter()
+            .filter(|card| to_card(**card).suit() == Some(trump))
+            .filter(|card| highest_trump.is_none_or(|highest| card_order(to_card(**card), trump) > highest))
+            .copied()
+            .collect()
+    };
+
+    let following: Vec<Card> = hand.iter().filter(|card| to_card(**card).suit() == led).copied().collect();
+    if !following.is_empty() {
+        if led == Some(trump) {
+            let over = over_trumps(&following);
+            if !over.is_empty() { return over; }
+        }
+        return following;
+    }
+
+    let partner_wins = trick_winner(trick, trump).is_some_and(|winner| team(winner) == team(seat));
+    let trumps: Vec<Card> = hand.iter().filter(|card| to_card(**card).suit() == Some(trump)).copied().collect();
+    if partner_wins || trumps.is_empty() {
+        return hand.to_vec();
+    }
+
+    let over = over_trumps(&trumps);
+    if over.is_empty() { trumps } else { over }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BeloteError {
+    NotYourTurn,
+    IllegalBid,
+    IllegalAction,
+    CardNotInHand(PlayingCard),
+    IllegalCard(PlayingCard),
+}
+
+impl BeloteError {
+    pub fn message(&self) -> Message {
+        match self {
+            BeloteError::NotYourTurn => Message::NotYourTurn,
+            BeloteError::IllegalBid => Message::IllegalBid,
+            BeloteError::IllegalAction => Message::IllegalAction,
+            BeloteError::CardNotInHand(card) => Message::CardNotInHand(card.to_string()),
+            BeloteError::IllegalCard(card) => Message::IllegalCard(card.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for BeloteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Bid {
+    Pass,
+    /// Announces the points the team will make, from 80 to 160 by steps of 10, or 250 for a capot.
+    Contract { value: u16, trump: Suit },
+    /// Doubles the current contract of the opponents.
+    Coinche,
+    /// Doubles again a coinched contract.
+    Surcoinche,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BidRecord {
+    pub seat: usize,
+    pub bid: Bid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Contract {
+    pub seat: usize,
+    pub value: u16,
+    pub trump: Suit,
+    /// 1, then 2 when coinched and 4 when surcoinched.
+    pub multiplier: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(rename = "BelotePhase")]
+pub enum Phase {
+    Bidding,
+    Playing,
+    Finished,
+    /// A team reached the target score.
+    Over,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RoundResult {
+    pub made: bool,
+    /// The card points of each team, with the last trick and belote bonuses.
+    pub points: [i32; 2],
+    /// The score of each team for the round.
+    pub scores: [i32; 2],
+}
+
+/// A seat of a game, the player sitting there proving it with its token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Seat {
+    pub id: Uuid,
+    /// The secret of the seat, only given when the game is created.
+    pub token: String,
+}
+
+/// A belote game in its coinche variant, between the teams of seats 0 and 2 and of seats 1 and 3.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Game {
+    /// The seats of the players, the games created before the seats having none and being read-only.
+    #[serde(default)]
+    pub seats: Vec<Seat>,
+    pub target: i32,
+    pub phase: Phase,
+    pub round: u32,
+    pub dealer: usize,
+    pub turn: usize,
+    pub hands: Vec<Vec<Card>>,
+    pub bids: Vec<BidRecord>,
+    pub contract: Option<Contract>,
+    pub passes: usize,
+    pub trick: Vec<Played>,
+    pub tricks: Vec<Trick>,
+    /// The seat holding the king and the queen of trump.
+    pub belote: Option<usize>,
+    pub result: Option<RoundResult>,
+    pub scores: [i32; 2],
+}
+
+impl Game {
+    pub fn new(target: i32) -> Game {
+        Game {
+            seats: (0..PLAYERS).map(|_| Seat { id: Uuid::new_v4(), token: Uuid::new_v4().to_simple().to_string() }).collect(),
+            target,
+            phase: Phase::Finished,
+            round: 0,
+            dealer: 0,
+            turn: 0,
+            hands: vec![Vec::new(); PLAYERS],
+            bids: Vec::new(),
+            contract: None,
+            passes: 0,
+            trick: Vec::new(),
+            tricks: Vec::new(),
+            belote: None,
+            result: None,
+            scores: [0, 0],
+        }
+    }
+
+    /// Finds the seat of a token.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::belote::Game;
+    ///
+    /// fn main() {
+    ///     let game = Game::new(1000);
+    ///     assert_eq!(game.seat(&game.seats[2].token), Some(2));
+    ///     assert_eq!(game.seat("guess"), None);
+    /// }
+    /// ```
+    pub fn seat(&self, token: &str) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.token == token)
+    }
+
+    /// Finds a seat from its id, the subject of the tokens given to the players.
+    pub fn seat_by_id(&self, id: Uuid) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.id == id)
+    }
+
+    /// Creates the shuffled 32 cards deck of the game.
+    pub fn new_deck(&self) -> Deck {
+        let mut deck = Deck::new(DeckSize::Small);
+        deck.shuffle(false);
+        deck
+    }
+
+    /// Deals a new round by packets of 3, 2 and 3 cards, starting at the left of the dealer.
+    ///
+    /// The dealer moves to the left after each round.
+    pub fn deal(&mut self, deck: &mut Deck) -> Result<(), BeloteError> {
+        if self.phase != Phase::Finished { return Err(BeloteError::IllegalAction); }
+
+        if self.round > 0 { self.dealer = (self.dealer + 1) % PLAYERS; }
+        self.round += 1;
+        self.hands = vec![Vec::new(); PLAYERS];
+        for packet in PACKETS.iter() {
+            for offset in 1..=PLAYERS {
+                let seat = (self.dealer + offset) % PLAYERS;
+                let cards = deck.deal(1, *packet).expect("The deck has 32 cards.");
+                self.hands[seat].extend(cards.concat());
+            }
+        }
+
+        self.bids = Vec::new();
+        self.contract = None;
+        self.passes = 0;
+        self.trick = Vec::new();
+        self.tricks = Vec::new();
+        self.belote = None;
+        self.result = None;
+        self.turn = (self.dealer + 1) % PLAYERS;
+        self.phase = Phase::Bidding;
+        Ok(())
+    }
+
+    pub fn bid(&mut self, deck: &mut Deck, seat: usize, bid: Bid) -> Result<(), BeloteError> {
+        if self.phase != Phase::Bidding { return Err(BeloteError::IllegalAction); }
+        if seat != self.turn { return Err(BeloteError::NotYourTurn); }
+
+        let coinched = self.contract.is_some_and(|contract| contract.multiplier > 1);
+        match bid {
+            Bid::Pass => self.passes += 1,
+            Bid::Contract { value, trump } => {
+                let valid = value == CAPOT || ((80..=160).contains(&value) && value % 10 == 0);
+                let higher = self.contract.is_none_or(|contract| value > contract.value);
+                if !valid || !higher || coinched { return Err(BeloteError::IllegalBid); }
+
+                self.contract = Some(Contract { seat, value, trump, multiplier: 1 });
+                self.passes = 0;
+            }
+            Bid::Coinche => match self.contract.as_mut() {
+                Some(contract) if !coinched && team(contract.seat) != team(seat) => contract.multiplier = 2,
+                _ => return Err(BeloteError::IllegalBid)
+            },
+            Bid::Surcoinche => match self.contract.as_mut() {
+                Some(contract) if contract.multiplier == 2 && team(contract.seat) == team(seat) => contract.multiplier = 4,
+                _ => return Err(BeloteError::IllegalBid)
+            },
+        }
+        self.bids.push(BidRecord { seat, bid });
+
+        // After a coinche, the next player of the taker's team may only surcoinche or pass.
+        let ended = match (self.contract, bid) {
+            (None, _) => self.passes == PLAYERS,
+            (Some(_), Bid::Surcoinche) => true,
+            (Some(_), Bid::Pass) if coinched => true,
+            (Some(_), _) => self.passes == PLAYERS - 1,
+        };
+
+        if !ended {
+            self.turn = (self.turn + 1) % PLAYERS;
+        } else if self.contract.is_none() {
+            // Everybody passed: the cards are gathered and dealt again by the next dealer.
+            let cards: Vec<Card> = self.hands.concat();
+            self.gather(deck, cards);
+            self.phase = Phase::Finished;
+            self.deal(deck)?;
+        } else {
+            self.start_play();
+        }
+        Ok(())
+    }
+
+    fn start_play(&mut self) {
+        let trump = self.contract.expect("A contract.").trump;
+        let king = PlayingCard::new(Rank::King, trump).motif();
+        let queen = PlayingCard::new(Rank::Queen, trump).motif();
+        self.belote = (0..PLAYERS).find(|seat| self.hands[*seat].contains(&king) && self.hands[*seat].contains(&queen));
+        self.turn = (self.dealer + 1) % PLAYERS;
+        self.phase = Phase::Playing;
+    }
+
+    /// The cards the player to play may play on the current trick.
+    pub fn legal_cards(&self) -> Vec<Card> {
+        match (self.phase, self.contract) {
+            (Phase::Playing, Some(contract)) => legal_cards(&self.hands[self.turn], &self.trick, self.turn, contract.trump),
+            _ => Vec::new()
+        }
+    }
+
+    pub fn play(&mut self, deck: &mut Deck, seat: usize, card: PlayingCard) -> Result<(), BeloteError> {
+        if self.phase != Phase::Playing { return Err(BeloteError::IllegalAction); }
+        if seat != self.turn { return Err(BeloteError::NotYourTurn); }
+        if !self.hands[seat].contains(&card.motif()) { return Err(BeloteError::CardNotInHand(card)); }
+        if !self.legal_cards().contains(&card.motif()) { return Err(BeloteError::IllegalCard(card)); }
+
+        self.hands[seat].retain(|motif| *motif != card.motif());
+        self.trick.push(Played { seat, card: card.motif() });
+        if self.trick.len() < PLAYERS {
+            self.turn = (self.turn + 1) % PLAYERS;
+            return Ok(());
+        }
+
+        let trump = self.contract.expect("A contract.").trump;
+        let winner = trick_winner(&self.trick, trump).expect("A full trick.");
+        self.tricks.push(Trick { cards: std::mem::take(&mut self.trick), winner });
+        self.turn = winner;
+        if self.hands.iter().all(Vec::is_empty) {
+            self.score(deck);
+        }
+        Ok(())
+    }
+
+    /// The card points won by each team, with the last trick and belote bonuses.
+    pub fn points(&self) -> [i32; 2] {
+        let mut points = [0, 0];
+        let trump = match self.contract {
+            Some(contract) => contract.trump,
+            None => return points
+        };
+        for trick in self.tricks.iter() {
+            points[team(trick.winner)] += trick.cards.iter().map(|played| card_points(to_card(played.card), trump)).sum::<i32>();
+        }
+        if self.tricks.len() == 32 / PLAYERS {
+            points[team(self.tricks[self.tricks.len() - 1].winner)] += LAST_TRICK_BONUS;
+        }
+        if let Some(seat) = self.belote {
+            points[team(seat)] += BELOTE_BONUS;
+        }
+        points
+    }
+
+    /// Scores the round.
+    ///
+    /// When the contract is made, the taker's team scores the contract plus its points
+    /// and the defenders keep their points, unless they coinched.
+    /// Otherwise the defenders score 160 plus the contract, and the belote stays with its holder.
+    fn score(&mut self, deck: &mut Deck) {
+        let contract = self.contract.expect("A contract.");
+        let points = self.points();
+        let taker = team(contract.seat);
+        let defender = 1 - taker;
+        let multiplier = contract.multiplier as i32;
+        let value = contract.value as i32;
+
+        let made = if contract.value == CAPOT {
+            self.tricks.iter().all(|trick| team(trick.winner) == taker)
+        } else {
+            points[taker] >= value && points[taker] > points[defender]
+        };
+
+        let mut scores = [0, 0];
+        if made {
+            scores[taker] = value * multiplier + points[taker];
+            scores[defender] = if multiplier > 1 { 0 } else { points[defender] };
+        } else {
+            scores[defender] = (160 + value) * multiplier;
+            if let Some(seat) = self.belote {
+                scores[team(seat)] += BELOTE_BONUS;
+            }
+        }
+
+        self.scores[0] += scores[0];
+        self.scores[1] += scores[1];
+        self.result = Some(RoundResult { made, points, scores });
+        self.phase = if self.scores.iter().any(|score| *score >= self.target) { Phase::Over } else { Phase::Finished };
+
+        let cards: Vec<Card> = self.tricks.iter().flat_map(|trick| trick.cards.iter().map(|played| played.card)).collect();
+        self.gather(deck, cards);
+    }
+
+    /// Puts the cards back into the deck in the order they were gathered, and cuts it.
+    fn gather(&mut self, deck: &mut Deck, cards: Vec<Card>) {
+        deck.discarded.clear();
+        deck.cards = cards;
+        let cut = rand::thread_rng().gen_range(1..deck.cards.len());
+        deck.cards.rotate_left(cut);
+        self.hands = vec![Vec::new(); PLAYERS];
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PlayedRepresentation {
+    pub seat: usize,
+    pub card: CardRepresentation,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "BeloteGameRepresentation")]
+pub struct GameRepresentation {
+    pub id: uuid::Uuid,
+    pub phase: Phase,
+    pub target: i32,
+    pub round: u32,
+    pub dealer: usize,
+    pub turn: Option<usize>,
+    pub bids: Vec<BidRecord>,
+    pub contract: Option<Contract>,
+    pub belote: Option<usize>,
+    pub trick: Vec<PlayedRepresentation>,
+    pub last_trick: Option<Vec<PlayedRepresentation>>,
+    pub tricks_won: [usize; 2],
+    pub points: [i32; 2],
+    pub result: Option<RoundResult>,
+    pub scores: [i32; 2],
+    pub hand_sizes: Vec<usize>,
+    /// The seat of the player of the request, if any.
+    pub seat: Option<usize>,
+    /// The hand of the seat of the player only.
+    pub hand: Option<Vec<CardRepresentation>>,
+    /// The codes of the cards the player may play, when it is their turn.
+    pub legal: Option<Vec<String>>,
+    /// The tokens of the seats, by seat, only given when the game is created so that they can be handed to the players.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<String>>,
+}
+
+impl GameRepresentation {
+    pub fn translated(id: uuid::Uuid, game: &Game, seat: Option<usize>, language: Language, theme: &Theme) -> GameRepresentation {
+        let seat = seat.filter(|seat| *seat < PLAYERS);
+        let played = |cards: &[Played]| -> Vec<PlayedRepresentation> {
+            cards.iter()
+                .map(|played| PlayedRepresentation {
+                    seat: played.seat,
+                    card: DeckRepresentation::translate_cards(vec![played.card], language, theme).remove(0),
+                })
+                .collect()
+        };
+        let playing = game.phase == Phase::Bidding || game.phase == Phase::Playing;
+
+        GameRepresentation {
+            id,
+            phase: game.phase,
+            target: game.target,
+            round: game.round,
+            dealer: game.dealer,
+            turn: if playing { Some(game.turn) } else { None },
+            bids: game.bids.clone(),
+            contract: game.contract,
+            belote: game.belote,
+            trick: played(&game.trick),
+            last_trick: game.tricks.last().map(|trick| played(&trick.cards)),
+            tricks_won: [0, 1].map(|side| game.tricks.iter().filter(|trick| team(trick.winner) == side).count()),
+            points: game.points(),
+            result: game.result,
+            scores: game.scores,
+            hand_sizes: game.hands.iter().map(Vec::len).collect(),
+            seat,
+            hand: seat.map(|seat| DeckRepresentation::translate_cards(game.hands[seat].clone(), language, theme)),
+            legal: seat.filter(|seat| game.phase == Phase::Playing && *seat == game.turn)
+                .map(|_| game.legal_cards().iter().map(|card| to_card(*card).to_string()).collect()),
+            tokens: None,
+        }
+    }
+}
@@ -0,0 +1,451 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::{Card, CardRepresentation, Deck, DeckRepresentation};
+use crate::card::{PlayingCard, Rank};
+use crate::i18n::{Language, Message};
+use crate::theme::Theme;
+
+pub const SESSION_KIND: &str = "blackjack";
+pub const MAX_DECKS: usize = 8;
+pub const MAX_HANDS: usize = 4;
+/// The highest bet of a round, so that doubling and paying it can't overflow.
+pub const MAX_BET: u32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Rules {
+    /// Number of 52 cards decks in the shoe.
+    pub decks: usize,
+    /// If true, the dealer hits on a soft 17, otherwise the dealer stands on every 17.
+    pub dealer_hits_soft_17: bool,
+    /// The shoe is reshuffled before a round when fewer cards remain (the cut card).
+    pub reshuffle_at: usize,
+}
+
+impl Rules {
+    pub fn new(decks: usize, dealer_hits_soft_17: bool) -> Rules {
+        Rules { decks, dealer_hits_soft_17, reshuffle_at: decks * 52 / 4 }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules::new(6, false)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub total: u8,
+    /// True when an ace is counted as 11.
+    pub soft: bool,
+}
+
+fn card_value(card: Card) -> u8 {
+    match PlayingCard::try_from(card).ok().and_then(PlayingCard::rank) {
+        Some(Rank::Ace) => 1,
+        Some(Rank::Jack) | Some(Rank::Queen) | Some(Rank::King) => 10,
+        Some(rank) => rank.index() as u8 + 1,
+        None => panic!("Incorrect value.")
+    }
+}
+
+/// Scores a blackjack hand, counting one ace as 11 when it does not bust the hand.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::blackjack::{score, Score};
+///
+/// fn main() {
+///     // Ace of clubs and six of hearts.
+///     assert_eq!(score(&[0, 31]), Score { total: 17, soft: true });
+///     // Plus the king of spades.
+///     assert_eq!(score(&[0, 31, 51]), Score { total: 17, soft: false });
+/// }
+/// ```
+pub fn score(cards: &[Card]) -> Score {
+    let hard: u8 = cards.iter().map(|card| card_value(*card)).sum();
+    let has_ace = cards.iter().any(|card| card_value(*card) == 1);
+    if has_ace && hard + 10 <= 21 {
+        Score { total: hard + 10, soft: true }
+    } else {
+        Score { total: hard, soft: false }
+    }
+}
+
+pub fn is_blackjack(cards: &[Card]) -> bool {
+    cards.len() == 2 && score(cards).total == 21
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(rename = "BlackjackPhase")]
+pub enum Phase {
+    Betting,
+    Insurance,
+    Playing,
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+    Surrender,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl FromStr for Action {
+    type Err = BlackjackError;
+
+    fn from_str(value: &str) -> Result<Action, BlackjackError> {
+        match value {
+            "hit" => Ok(Action::Hit),
+            "stand" => Ok(Action::Stand),
+            "double" => Ok(Action::Double),
+            "split" => Ok(Action::Split),
+            "surrender" => Ok(Action::Surrender),
+            _ => Err(BlackjackError::IllegalAction)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlackjackError {
+    InvalidBet,
+    /// The bet is odd, while a blackjack, the insurance and a surrender pay half of it.
+    OddBet,
+    IllegalAction,
+}
+
+impl BlackjackError {
+    pub fn message(&self) -> Message {
+        match self {
+            BlackjackError::InvalidBet => Message::InvalidBet,
+            BlackjackError::OddBet => Message::OddBet,
+            BlackjackError::IllegalAction => Message::IllegalAction,
+        }
+    }
+}
+
+impl fmt::Display for BlackjackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hand {
+    pub cards: Vec<Card>,
+    pub bet: u32,
+    pub doubled: bool,
+    pub split: bool,
+    pub surrendered: bool,
+    pub done: bool,
+    pub outcome: Option<Outcome>,
+    pub payout: i64,
+}
+
+impl Hand {
+    fn new(cards: Vec<Card>, bet: u32, split: bool) -> Hand {
+        Hand { cards, bet, doubled: false, split, surrendered: false, done: false, outcome: None, payout: 0 }
+    }
+
+    fn busted(&self) -> bool {
+        score(&self.cards).total > 21
+    }
+
+    /// A natural blackjack, which cannot come from a split hand.
+    fn natural(&self) -> bool {
+        !self.split && is_blackjack(&self.cards)
+    }
+}
+
+/// A blackjack table with one player seat, dealing from a shoe.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Table {
+    pub rules: Rules,
+    pub phase: Phase,
+    pub hands: Vec<Hand>,
+    pub active: usize,
+    pub dealer: Vec<Card>,
+    pub insurance: u32,
+    /// Net winnings of the player since the table was opened.
+    pub balance: i64,
+}
+
+impl Table {
+    pub fn new(rules: Rules) -> Table {
+        Table { rules, phase: Phase::Betting, hands: Vec::new(), active: 0, dealer: Vec::new(), insurance: 0, balance: 0 }
+    }
+
+    /// Creates the shuffled shoe matching the rules of the table.
+    pub fn new_shoe(&self) -> Deck {
+        let mut shoe = Deck::shoe(self.rules.decks);
+        shoe.shuffle(false);
+        shoe
+    }
+
+    fn draw(&mut self, shoe: &mut Deck) -> Card {
+        if shoe.cards.is_empty() {
+            // Reshuffles the discard tray, keeping the cards which are still on the table.
+            let on_table: Vec<Card> = self.hands.iter().flat_map(|hand| hand.cards.clone()).chain(self.dealer.clone()).collect();
+            shoe.put(true, shoe.discarded.len(), true);
+            shoe.move_cards(&on_table, true).expect("Cards on the table come from the shoe.");
+            shoe.shuffle(false);
+        }
+
+        *shoe.take(true, 1, true).first().expect("The shoe is empty.")
+    }
+
+    /// Starts a new round with the given bet, which must be even so that every payout is a whole number of chips.
+    pub fn deal(&mut self, shoe: &mut Deck, bet: u32) -> Result<(), BlackjackError> {
+        if self.phase != Phase::Betting && self.phase != Phase::Finished { return Err(BlackjackError::IllegalAction); }
+        if bet == 0 || bet > MAX_BET { return Err(BlackjackError::InvalidBet); }
+        if !bet.is_multiple_of(2) { return Err(BlackjackError::OddBet); }
+
+        if shoe.cards.len() < self.rules.reshuffle_at {
+            shoe.put(true, shoe.discarded.len(), true);
+            shoe.shuffle(false);
+        }
+
+        self.hands = vec![Hand::new(Vec::new(), bet, false)];
+        self.active = 0;
+        self.dealer = Vec::new();
+        self.insurance = 0;
+        for _ in 0..2 {
+            let card = self.draw(shoe);
+            self.hands[0].cards.push(card);
+            let card = self.draw(shoe);
+            self.dealer.push(card);
+        }
+
+        if card_value(self.dealer[0]) == 1 {
+            self.phase = Phase::Insurance;
+        } else {
+            self.after_peek(shoe);
+        }
+        Ok(())
+    }
+
+    /// Takes or declines the insurance offered when the dealer shows an ace.
+    pub fn insure(&mut self, shoe: &mut Deck, take: bool) -> Result<(), BlackjackError> {
+        if self.phase != Phase::Insurance { return Err(BlackjackError::IllegalAction); }
+        if take { self.insurance = self.hands[0].bet / 2; }
+
+        self.after_peek(shoe);
+        Ok(())
+    }
+
+    fn after_peek(&mut self, shoe: &mut Deck) {
+        if is_blackjack(&self.dealer) || self.hands[0].natural() {
+            self.finish(shoe, false);
+        } else {
+            self.phase = Phase::Playing;
+        }
+    }
+
+    pub fn play(&mut self, shoe: &mut Deck, action: Action) -> Result<(), BlackjackError> {
+        if self.phase != Phase::Playing { return Err(BlackjackError::IllegalAction); }
+        let active = self.active;
+        let first_decision = self.hands[active].cards.len() == 2;
+
+        match action {
+            Action::Hit => {
+                let card = self.draw(shoe);
+                self.hands[active].cards.push(card);
+                if score(&self.hands[active].cards).total >= 21 { self.hands[active].done = true; }
+            }
+            Action::Stand => self.hands[active].done = true,
+            Action::Double => {
+                if !first_decision { return Err(BlackjackError::IllegalAction); }
+                let bet = self.hands[active].bet.checked_mul(2).ok_or(BlackjackError::InvalidBet)?;
+                let card = self.draw(shoe);
+                let hand = &mut self.hands[active];
+                hand.cards.push(card);
+                hand.bet = bet;
+                hand.doubled = true;
+                hand.done = true;
+            }
+            Action::Split => {
+                let hand = &self.hands[active];
+                let splittable = first_decision
+                    && card_value(hand.cards[0]) == card_value(hand.cards[1])
+                    && self.hands.len() < MAX_HANDS;
+                if !splittable { return Err(BlackjackError::IllegalAction); }
+
+                let aces = card_value(hand.cards[0]) == 1;
+                let moved = self.hands[active].cards.pop().expect("Two cards.");
+                self.hands[active].split = true;
+                self.hands.insert(active + 1, Hand::new(vec![moved], self.hands[active].bet, true));
+
+                let card = self.draw(shoe);
+                self.hands[active].cards.push(card);
+                if aces {
+                    // Split aces receive one card each.
+                    let card = self.draw(shoe);
+                    self.hands[active + 1].cards.push(card);
+                    self.hands[active].done = true;
+                    self.hands[active + 1].done = true;
+                }
+            }
+            Action::Surrender => {
+                if !first_decision || self.hands.len() > 1 { return Err(BlackjackError::IllegalAction); }
+                self.hands[active].surrendered = true;
+                self.hands[active].done = true;
+            }
+        }
+
+        self.advance(shoe);
+        Ok(())
+    }
+
+    fn advance(&mut self, shoe: &mut Deck) {
+        while self.hands[self.active].done {
+            if self.active + 1 == self.hands.len() {
+                let dealer_plays = self.hands.iter().any(|hand| !hand.busted() && !hand.surrendered);
+                self.finish(shoe, dealer_plays);
+                return;
+            }
+
+            self.active += 1;
+            if self.hands[self.active].cards.len() == 1 {
+                let card = self.draw(shoe);
+                self.hands[self.active].cards.push(card);
+                if score(&self.hands[self.active].cards).total == 21 { self.hands[self.active].done = true; }
+            }
+        }
+    }
+
+    fn finish(&mut self, shoe: &mut Deck, dealer_plays: bool) {
+        if dealer_plays {
+            loop {
+                let dealer = score(&self.dealer);
+                let hits = dealer.total < 17 || (dealer.total == 17 && dealer.soft && self.rules.dealer_hits_soft_17);
+                if !hits { break; }
+                let card = self.draw(shoe);
+                self.dealer.push(card);
+            }
+        }
+
+        let dealer_blackjack = is_blackjack(&self.dealer);
+        let dealer_total = score(&self.dealer).total;
+        for hand in self.hands.iter_mut() {
+            let bet = hand.bet as i64;
+            let total = score(&hand.cards).total;
+            let (outcome, payout) = if hand.surrendered {
+                (Outcome::Surrender, -bet / 2)
+            } else if total > 21 {
+                (Outcome::Lose, -bet)
+            } else if hand.natural() && !dealer_blackjack {
+                (Outcome::Blackjack, bet * 3 / 2)
+            } else if dealer_blackjack {
+                if hand.natural() { (Outcome::Push, 0) } else { (Outcome::Lose, -bet) }
+            } else if dealer_total > 21 || total > dealer_total {
+                (Outcome::Win, bet)
+            } else if total == dealer_total {
+                (Outcome::Push, 0)
+            } else {
+                (Outcome::Lose, -bet)
+            };
+
+            hand.done = true;
+            hand.outcome = Some(outcome);
+            hand.payout = payout;
+            self.balance += payout;
+        }
+
+        if self.insurance > 0 {
+            let insurance = self.insurance as i64;
+            self.balance += if dealer_blackjack { 2 * insurance } else { -insurance };
+        }
+
+        self.phase = Phase::Finished;
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "BlackjackHandRepresentation")]
+pub struct HandRepresentation {
+    pub cards: Vec<CardRepresentation>,
+    pub total: u8,
+    pub soft: bool,
+    pub bet: u32,
+    pub doubled: bool,
+    pub surrendered: bool,
+    pub outcome: Option<Outcome>,
+    pub payout: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DealerRepresentation {
+    pub cards: Vec<CardRepresentation>,
+    /// The total of the visible cards only.
+    pub total: u8,
+    pub hidden: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TableRepresentation {
+    pub id: uuid::Uuid,
+    pub rules: Rules,
+    pub phase: Phase,
+    pub active: Option<usize>,
+    pub hands: Vec<HandRepresentation>,
+    pub dealer: DealerRepresentation,
+    pub insurance: u32,
+    pub balance: i64,
+    pub shoe: usize,
+}
+
+impl TableRepresentation {
+    /// Represents a table, hiding the dealer hole card until the end of the round.
+    pub fn translated(id: uuid::Uuid, table: &Table, shoe: &Deck, language: Language, theme: &Theme) -> TableRepresentation {
+        let visible = match table.phase {
+            Phase::Insurance | Phase::Playing => &table.dealer[..1],
+            _ => &table.dealer[..],
+        };
+
+        TableRepresentation {
+            id,
+            rules: table.rules,
+            phase: table.phase,
+            active: if table.phase == Phase::Playing { Some(table.active) } else { None },
+            hands: table.hands.iter().map(|hand| {
+                let score = score(&hand.cards);
+                HandRepresentation {
+                    cards: DeckRepresentation::translate_cards(hand.cards.clone(), language, theme),
+                    total: score.total,
+                    soft: score.soft,
+                    bet: hand.bet,
+                    doubled: hand.doubled,
+                    surrendered: hand.surrendered,
+                    outcome: hand.outcome,
+                    payout: hand.payout,
+                }
+            }).collect(),
+            dealer: DealerRepresentation {
+                cards: DeckRepresentation::translate_cards(visible.to_vec(), language, theme),
+                total: score(visible).total,
+                hidden: table.dealer.len() - visible.len(),
+            },
+            insurance: table.insurance,
+            balance: table.balance,
+            shoe: shoe.cards.len(),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::Card;
+use crate::i18n::{Language, Message};
+
+/// Motif of the red joker, after the 52 standard cards.
+pub const RED_JOKER: Card = 52;
+/// Motif of the black joker.
+pub const BLACK_JOKER: Card = 53;
+/// Motif of the knight of clubs, the first of the four tarot knights.
+pub const KNIGHTS: Card = 54;
+/// Motif of the first tarot trump, the 21 trumps following each other.
+pub const TAROT_TRUMPS: Card = 58;
+/// Motif of the tarot Excuse, the last card.
+pub const EXCUSE: Card = 79;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    pub fn index(self) -> Card {
+        self as Card
+    }
+
+    pub fn letter(self) -> char {
+        match self {
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+            Suit::Hearts => 'H',
+            Suit::Spades => 'S',
+        }
+    }
+
+    pub fn is_red(self) -> bool {
+        self == Suit::Diamonds || self == Suit::Hearts
+    }
+
+    fn parse(value: &str) -> Option<Suit> {
+        match value {
+            "C" | "c" | "♣" | "♧" => Some(Suit::Clubs),
+            "D" | "d" | "♦" | "♢" => Some(Suit::Diamonds),
+            "H" | "h" | "♥" | "♡" => Some(Suit::Hearts),
+            "S" | "s" | "♠" | "♤" => Some(Suit::Spades),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Ace,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Ace, Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven,
+        Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King,
+    ];
+
+    pub fn index(self) -> Card {
+        self as Card
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Rank> {
+        match value.to_ascii_uppercase().as_str() {
+            "A" | "1" => Some(Rank::Ace),
+            "10" | "T" => Some(Rank::Ten),
+            "J" => Some(Rank::Jack),
+            "Q" => Some(Rank::Queen),
+            "K" => Some(Rank::King),
+            other => other.parse::<usize>().ok()
+                .filter(|value| (2..=9).contains(value))
+                .map(|value| Rank::ALL[value - 1])
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlayingCard {
+    Standard { rank: Rank, suit: Suit },
+    RedJoker,
+    BlackJoker,
+    /// The tarot knight, between the jack and the queen.
+    Knight(Suit),
+    /// A tarot trump, numbered from 1 to 21.
+    Trump(u8),
+    Excuse,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseCardError(pub String);
+
+impl ParseCardError {
+    pub fn message(&self) -> Message {
+        Message::InvalidCard(self.0.clone())
+    }
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+impl PlayingCard {
+    pub fn new(rank: Rank, suit: Suit) -> PlayingCard {
+        PlayingCard::Standard { rank, suit }
+    }
+
+    pub fn rank(self) -> Option<Rank> {
+        match self {
+            PlayingCard::Standard { rank, .. } => Some(rank),
+            _ => None
+        }
+    }
+
+    pub fn suit(self) -> Option<Suit> {
+        match self {
+            PlayingCard::Standard { suit, .. } | PlayingCard::Knight(suit) => Some(suit),
+            _ => None
+        }
+    }
+
+    /// The motif of the card, as stored in a deck.
+    pub fn motif(self) -> Card {
+        match self {
+            PlayingCard::Standard { rank, suit } => suit.index() * 13 + rank.index(),
+            PlayingCard::RedJoker => RED_JOKER,
+            PlayingCard::BlackJoker => BLACK_JOKER,
+            PlayingCard::Knight(suit) => KNIGHTS + suit.index(),
+            PlayingCard::Trump(number) => TAROT_TRUMPS + number as Card - 1,
+            PlayingCard::Excuse => EXCUSE,
+        }
+    }
+
+    /// The card as a character of the Unicode "Playing Cards" block, e.g. 🂡 for the ace of spades.
+    pub fn to_unicode(self) -> char {
+        let base = |suit: Suit| match suit {
+            Suit::Spades => 0x1F0A0,
+            Suit::Hearts => 0x1F0B0,
+            Suit::Diamonds => 0x1F0C0,
+            Suit::Clubs => 0x1F0D0,
+        };
+        let code = match self {
+            PlayingCard::Standard { rank, suit } => {
+                // The knight (0xC) sits between the jack and the queen.
+                let offset = match rank {
+                    Rank::Queen | Rank::King => rank.index() as u32 + 2,
+                    _ => rank.index() as u32 + 1,
+                };
+                base(suit) + offset
+            }
+            PlayingCard::RedJoker => 0x1F0BF,
+            PlayingCard::BlackJoker => 0x1F0CF,
+            PlayingCard::Knight(suit) => base(suit) + 0xC,
+            PlayingCard::Trump(number) => 0x1F0E0 + number as u32,
+            PlayingCard::Excuse => 0x1F0E0,
+        };
+
+        std::char::from_u32(code).unwrap()
+    }
+
+    fn from_unicode(value: char) -> Option<PlayingCard> {
+        let code = value as u32;
+        match code {
+            0x1F0BF => return Some(PlayingCard::RedJoker),
+            0x1F0CF | 0x1F0DF => return Some(PlayingCard::BlackJoker),
+            0x1F0E0 => return Some(PlayingCard::Excuse),
+            0x1F0E1..=0x1F0F5 => return Some(PlayingCard::Trump((code - 0x1F0E0) as u8)),
+            0x1F0A1..=0x1F0DE => {}
+            _ => return None
+        }
+
+        let suit = match code & 0xFFFF0 {
+            0x1F0A0 => Suit::Spades,
+            0x1F0B0 => Suit::Hearts,
+            0x1F0C0 => Suit::Diamonds,
+            _ => Suit::Clubs,
+        };
+        let rank = match code & 0xF {
+            offset @ 0x1..=0xB => Rank::ALL[offset as usize - 1],
+            0xC => return Some(PlayingCard::Knight(suit)),
+            0xD => Rank::Queen,
+            0xE => Rank::King,
+            _ => return None
+        };
+
+        Some(PlayingCard::new(rank, suit))
+    }
+}
+
+impl TryFrom<Card> for PlayingCard {
+    type Error = ParseCardError;
+
+    fn try_from(motif: Card) -> Result<PlayingCard, ParseCardError> {
+        match motif {
+            0..=51 => Ok(PlayingCard::new(Rank::ALL[(motif % 13) as usize], Suit::ALL[(motif / 13) as usize])),
+            RED_JOKER => Ok(PlayingCard::RedJoker),
+            BLACK_JOKER => Ok(PlayingCard::BlackJoker),
+            KNIGHTS..=57 => Ok(PlayingCard::Knight(Suit::ALL[(motif - KNIGHTS) as usize])),
+            TAROT_TRUMPS..=78 => Ok(PlayingCard::Trump((motif - TAROT_TRUMPS + 1) as u8)),
+            EXCUSE => Ok(PlayingCard::Excuse),
+            _ => Err(ParseCardError(motif.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for PlayingCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayingCard::Standard { rank, suit } => write!(f, "{}{}", rank.symbol(), suit.letter()),
+            PlayingCard::RedJoker => write!(f, "RJ"),
+            PlayingCard::BlackJoker => write!(f, "BJ"),
+            PlayingCard::Knight(suit) => write!(f, "C{}", suit.letter()),
+            PlayingCard::Trump(number) => write!(f, "T{}", number),
+            PlayingCard::Excuse => write!(f, "EX"),
+        }
+    }
+}
+
+impl FromStr for PlayingCard {
+    type Err = ParseCardError;
+
+    /// Parses a card from its short notation (`AS`, `10H`, `TH`, `QD`, `RJ`, `BJ`),
+    /// its suit symbol notation (`A♠`) or its Unicode character (🂡).
+    /// The tarot cards are written `CH` for a knight, `T1` to `T21` for the trumps and `EX` for the Excuse.
+    fn from_str(value: &str) -> Result<PlayingCard, ParseCardError> {
+        let trimmed = value.trim();
+        let error = || ParseCardError(value.to_string());
+
+        let mut chars = trimmed.chars();
+        if let (Some(character), None) = (chars.next(), chars.next()) {
+            return PlayingCard::from_unicode(character).ok_or_else(error);
+        }
+
+        let upper = trimmed.to_ascii_uppercase();
+        match upper.as_str() {
+            "RJ" | "JR" | "JOKER" => return Ok(PlayingCard::RedJoker),
+            "BJ" | "JB" => return Ok(PlayingCard::BlackJoker),
+            "EX" | "EXCUSE" => return Ok(PlayingCard::Excuse),
+            _ => {}
+        }
+        if let Some(number) = upper.strip_prefix('T').and_then(|number| number.parse::<u8>().ok()) {
+            return if (1..=21).contains(&number) { Ok(PlayingCard::Trump(number)) } else { Err(error()) };
+        }
+
+        let (last_index, _) = trimmed.char_indices().last().ok_or_else(error)?;
+        let suit = Suit::parse(&trimmed[last_index..]).ok_or_else(error)?;
+        if trimmed[..last_index].eq_ignore_ascii_case("C") {
+            return Ok(PlayingCard::Knight(suit));
+        }
+        let rank = Rank::parse(&trimmed[..last_index]).ok_or_else(error)?;
+
+        Ok(PlayingCard::new(rank, suit))
+    }
+}
+
+/// Deserializes a card motif either from its integer value or from any notation accepted by `PlayingCard::from_str`.
+pub fn deserialize_cards<'de, D>(deserializer: D) -> Result<Vec<Card>, D::Error>
+    where D: Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CardNotation {
+        Motif(Card),
+        Code(String),
+    }
+
+    Vec::<CardNotation>::deserialize(deserializer)?
+        .into_iter()
+        .map(|card| match card {
+            CardNotation::Motif(motif) => PlayingCard::try_from(motif).map(PlayingCard::motif),
+            CardNotation::Code(code) => code.parse::<PlayingCard>().map(PlayingCard::motif),
+        }.map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Parses a comma separated list of cards, e.g. `AS,10H,🂮`.
+pub fn parse_cards(value: &str) -> Result<Vec<Card>, ParseCardError> {
+    value.split(',')
+        .filter(|code| !code.trim().is_empty())
+        .map(|code| code.parse::<PlayingCard>().map(PlayingCard::motif))
+        .collect()
+}
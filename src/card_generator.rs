@@ -1,44 +1,28 @@
-use std::env;
+use std::convert::TryFrom;
 use crate::{Card, CardRepresentation};
+use crate::card::PlayingCard;
+use crate::i18n::{self, Language};
+use crate::theme::Theme;
 
-pub fn get_card(motif: &Card) -> CardRepresentation {
-    let card_color = match motif / 13 {
-        0 => "Trèfle",
-        1 => "Carreau",
-        2 => "Coeur",
-        3 => "Pique",
-        _ => panic!("Incorrect value.")
-    };
-
-    let card_value = match motif % 13 {
-        0 => "As",
-        1 => "2",
-        2 => "3",
-        3 => "4",
-        4 => "5",
-        5 => "6",
-        6 => "7",
-        7 => "8",
-        8 => "9",
-        9 => "10",
-        10 => "Valet",
-        11 => "Reine",
-        12 => "Roi",
-        _ => panic!("Incorrect value.")
-    };
-
-    let name = format!("{} de {}", card_value, card_color);
-    let image= format!("{}{}{}", get_asset_url(), motif, get_asset_extension());
+pub fn get_card(motif: &Card, language: Language, theme: &Theme) -> CardRepresentation {
+    let name = get_card_name(PlayingCard::try_from(*motif).expect("Incorrect value."), language);
+    let image = theme.image(motif);
 
     CardRepresentation { name, image, value: *motif }
 }
 
-fn get_asset_url() -> String {
-    return env::var("ASSET_URL")
-        .expect("ASSET_URL must be set");
+pub fn get_card_name(card: PlayingCard, language: Language) -> String {
+    match card {
+        PlayingCard::Standard { rank, suit } => i18n::card_name(rank, suit, language),
+        PlayingCard::Knight(suit) => i18n::knight_name(suit, language),
+        PlayingCard::Trump(number) => i18n::trump_name(number, language),
+        PlayingCard::Excuse => i18n::excuse_name(language).to_string(),
+        PlayingCard::RedJoker | PlayingCard::BlackJoker => i18n::joker_name(card.motif(), language).to_string()
+    }
 }
 
-fn get_asset_extension() -> String {
-    return env::var("ASSET_EXTENSION")
-        .expect("ASSET_EXTENSION must be set");
-}
\ No newline at end of file
+pub fn get_code(motif: &Card) -> String {
+    PlayingCard::try_from(*motif)
+        .expect("Incorrect value.")
+        .to_string()
+}
@@ -1,40 +1,465 @@
-use jeu_de_carte::{Deck, DeckRepresentation, DeckSize, JsonMessage};
-use actix_web::{HttpResponse, Result, HttpRequest, web};
+use jeu_de_carte::{CompactDeckRepresentation, Deck, DeckRepresentation, DeckSize, MovedCardsRepresentation, Problem, PROBLEM_CONTENT_TYPE};
+use actix_web::{HttpResponse, ResponseError, Result, HttpRequest, web};
+use actix_web::body::Body;
+use actix_web::dev::ServiceResponse;
+use actix_web::error::{BlockingError, InternalError, JsonPayloadError, QueryPayloadError};
+use actix_web::middleware::errhandlers::{ErrorHandlerResponse, ErrorHandlers};
+use actix_web::http::{header, StatusCode};
+use futures::StreamExt;
+use std::env;
 use uuid::Uuid;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use jeu_de_carte::api_key::{self, AccessError, ApiKey, ApiKeyRepresentation};
 use jeu_de_carte::database::establish_connection;
+use jeu_de_carte::events::{self, Audience, DeckChange, EventHub, EventKind, Notice};
+use jeu_de_carte::batch::{self, BatchError, BatchRepresentation, BatchRequest, Operation, StepResult};
+use jeu_de_carte::card::{parse_cards, PlayingCard};
+use jeu_de_carte::render::{render_back, render_card};
+use jeu_de_carte::i18n::{Language, Message};
+use jeu_de_carte::theme::Theme;
+use jeu_de_carte::poker::EvaluationRepresentation;
+use jeu_de_carte::odds::{self, DEFAULT_ITERATIONS};
+use jeu_de_carte::rate_limit;
+use jeu_de_carte::blackjack::{self, Action, BlackjackError, Rules, Table, TableRepresentation};
+use jeu_de_carte::belote::{self, Bid, BeloteError, Game, GameRepresentation};
+use jeu_de_carte::game::{self, Command, JoinRepresentation, PlayerRepresentation};
+use jeu_de_carte::jwt::{self, Claims, Scope, TokenRepresentation};
+use jeu_de_carte::metrics;
+use jeu_de_carte::session::Session;
+use jeu_de_carte::webhook::{self, DeadLetter, Delivery, RetryPolicy, UrlError, Webhook, WebhookEvent, WebhookRepresentation};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
+pub struct RepresentationRequest {
+    pub lang: Option<String>,
+    pub theme: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct DeckSizeRequest {
     pub size: Option<i32>,
+    pub theme: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct DeckShuffleRequest {
     pub shuffle_discarded: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct DeckSwitch {
     pub lifo: Option<bool>,
     pub length: Option<usize>,
     pub move_as_block: Option<bool>,
+    pub only_moved: Option<bool>,
+    pub compact: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeckMoveRequest {
+    pub cards: String,
+    pub to_discarded: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PokerEvaluationRequest {
+    pub hands: Vec<Vec<String>>,
+    pub board: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct HoldemOddsRequest {
+    pub hole: Vec<String>,
+    pub board: Option<Vec<String>>,
+    pub opponents: Option<usize>,
+    pub iterations: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BlackjackTableRequest {
+    pub decks: Option<usize>,
+    pub hit_soft_17: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BlackjackBetRequest {
+    pub bet: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BlackjackInsuranceRequest {
+    pub take: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BeloteGameRequest {
+    pub target: Option<i32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BeloteBidRequest {
+    #[serde(flatten)]
+    pub bid: Bid,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BelotePlayRequest {
+    pub card: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GameRequest {
+    pub size: Option<i32>,
+    pub players: Option<usize>,
+    pub theme: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct JoinRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GameTakeRequest {
+    pub length: Option<usize>,
 }
 
-pub async fn post_cards(query: web::Query<DeckSizeRequest>) -> Result<HttpResponse> {
-    let deck_size = match query.size {
+#[derive(Deserialize, JsonSchema)]
+pub struct WebhookRequest {
+    pub url: String,
+    /// The deck to follow, every deck when missing.
+    pub deck_id: Option<Uuid>,
+    /// The events to receive, all of them when missing.
+    pub events: Option<Vec<WebhookEvent>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApiKeyRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GrantRequest {
+    pub api_key_id: Uuid,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TokenRequest {
+    /// The id of the player, a new one when missing.
+    pub player: Option<Uuid>,
+    pub scopes: Vec<Scope>,
+    /// The lifetime of the token in seconds, an hour when missing.
+    pub expires_in: Option<i64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GameTokenRequest {
+    pub expires_in: Option<i64>,
+}
+
+/// The header carrying the token of a player, given when joining a game.
+const PLAYER_TOKEN: &str = "X-Player-Token";
+/// The header carrying the administration token, set in the `ADMIN_TOKEN` environment variable.
+const ADMIN_TOKEN: &str = "X-Admin-Token";
+/// The header sent by a reconnecting Server-Sent Events client, with the id of the last event it received.
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+/// Picks the response language from the `lang` query parameter or the `Accept-Language` header.
+pub(crate) fn request_language(req: &HttpRequest) -> Language {
+    let lang = web::Query::<RepresentationRequest>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.into_inner().lang);
+    let accept_language = req.headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+
+    Language::negotiate(lang.as_deref(), accept_language)
+}
+
+/// Picks the image theme from the `theme` query parameter, then the theme of the deck.
+pub(crate) fn request_theme(req: &HttpRequest, deck: &Deck) -> Theme {
+    let theme = web::Query::<RepresentationRequest>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.into_inner().theme);
+
+    Theme::resolve(&[theme.as_deref(), deck.theme.as_deref()])
+}
+
+pub(crate) fn represent(req: &HttpRequest, deck: Deck, language: Language) -> DeckRepresentation {
+    let theme = request_theme(req, &deck);
+    DeckRepresentation::translated(deck, language, &theme)
+}
+
+fn switch_response(req: &HttpRequest, deck: Deck, moved: Vec<i32>, query: &DeckSwitch, language: Language) -> HttpResponse {
+    match (query.only_moved.unwrap_or(false), query.compact.unwrap_or(false)) {
+        (true, true) => HttpResponse::Ok().json(MovedCardsRepresentation::compact(&deck, moved)),
+        (true, false) => HttpResponse::Ok().json(MovedCardsRepresentation::translated(&deck, moved, language, &request_theme(req, &deck))),
+        (false, true) => HttpResponse::Ok().json(CompactDeckRepresentation::from(deck)),
+        (false, false) => HttpResponse::Ok().json(represent(req, deck, language)),
+    }
+}
+
+fn deck_size(size: Option<i32>) -> DeckSize {
+    match size {
         Some(32) => DeckSize::Small,
+        Some(78) => DeckSize::Tarot,
         _ => DeckSize::Normal
-    };
-    let deck = Deck::new(deck_size);
+    }
+}
+
+pub(crate) fn player_token(req: &HttpRequest) -> Option<&str> {
+    req.headers().get(PLAYER_TOKEN).and_then(|value| value.to_str().ok())
+}
+
+/// The API key authenticating the request, if any.
+pub(crate) fn request_api_key(req: &HttpRequest) -> Option<ApiKey> {
+    req.extensions().get::<ApiKey>().cloned()
+}
+
+/// The credentials of a request, a token taking precedence over an API key.
+#[derive(Clone, Default)]
+pub(crate) struct Credentials {
+    pub api_key: Option<ApiKey>,
+    pub claims: Option<Claims>,
+}
+
+impl Credentials {
+    pub(crate) fn from_request(req: &HttpRequest) -> Credentials {
+        let extensions = req.extensions();
+        Credentials { api_key: extensions.get::<ApiKey>().cloned(), claims: extensions.get::<Claims>().cloned() }
+    }
+
+    /// Tells if the request may change a deck: a token must allow the scopes on this deck,
+    /// otherwise the API key must be allowed to change it.
+    pub(crate) fn authorize<C: postgres::GenericClient>(&self, deck: &Deck, scopes: &[Scope], connection: &mut C) -> std::result::Result<(), AccessError> {
+        match &self.claims {
+            Some(claims) => jwt::check_scopes(claims, deck.id, scopes),
+            None => ApiKey::authorize(self.api_key.as_ref(), deck, connection)
+        }
+    }
+
+    /// Tells if a token allows the scopes on a deck or a game, the requests without token being left to the handlers.
+    pub(crate) fn authorize_token(&self, target: Uuid, scopes: &[Scope]) -> std::result::Result<(), AccessError> {
+        match &self.claims {
+            Some(claims) => jwt::check_scopes(claims, target, scopes),
+            None => Ok(())
+        }
+    }
+
+    /// The token of the player of a game, from the `X-Player-Token` header or from the subject of the token,
+    /// the handlers checking that the token was given for this game.
+    pub(crate) fn player_token(&self, header: Option<&str>, game: &game::Game) -> Option<String> {
+        match header {
+            Some(token) => Some(token.to_string()),
+            None => self.claims.as_ref()
+                .filter(|claims| claims.game.is_some())
+                .and_then(|claims| game.player_by_id(claims.sub))
+                .map(|player| player.token.clone())
+        }
+    }
+}
+
+pub(crate) fn access_denied(error: AccessError, language: Language) -> HttpResponse {
+    Failure::from(error).respond(language)
+}
+
+/// A failure of a request, answered as problem details whatever the version of the API.
+pub(crate) struct Failure {
+    pub status: StatusCode,
+    pub message: Message,
+    /// The parameter of the request at fault, if any.
+    pub parameter: Option<String>,
+}
+
+impl Failure {
+    pub(crate) fn new(status: StatusCode, message: Message) -> Failure {
+        Failure { status, message, parameter: None }
+    }
+
+    /// A parameter missing from the request.
+    pub(crate) fn missing(parameter: &str) -> Failure {
+        Failure { status: StatusCode::BAD_REQUEST, message: Message::MissingParameter, parameter: Some(parameter.to_string()) }
+    }
+
+    /// A parameter of the request which isn't valid, such as an id which isn't a UUID.
+    pub(crate) fn incorrect(parameter: &str) -> Failure {
+        Failure { status: StatusCode::BAD_REQUEST, message: Message::IncorrectParameter, parameter: Some(parameter.to_string()) }
+    }
+
+    /// Names the parameter of the request at fault.
+    pub(crate) fn parameter(mut self, parameter: &str) -> Failure {
+        self.parameter = Some(parameter.to_string());
+        self
+    }
+
+    /// Answers the failure with a `Problem`, in the `application/problem+json` content type.
+    pub(crate) fn respond(&self, language: Language) -> HttpResponse {
+        metrics::ERRORS.inc(&[self.message.code(), self.status.as_str()]);
+        HttpResponse::build(self.status)
+            .content_type(PROBLEM_CONTENT_TYPE)
+            .json(Problem::new(self.status, &self.message, self.parameter.as_deref(), language))
+    }
+}
+
+impl From<AccessError> for Failure {
+    fn from(error: AccessError) -> Failure {
+        let status = match error {
+            AccessError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AccessError::Forbidden | AccessError::MissingScope | AccessError::AdminRequired => StatusCode::FORBIDDEN,
+        };
+        Failure::new(status, error.message())
+    }
+}
+
+impl From<Message> for Failure {
+    /// A move refused by the rules of a game: 409 when the state of the game doesn't allow it,
+    /// 403 when the token isn't the one of a player, 422 otherwise.
+    fn from(message: Message) -> Failure {
+        let status = match message {
+            Message::IllegalAction | Message::NotYourTurn | Message::IllegalBid | Message::IllegalCard(_) | Message::GameFull => StatusCode::CONFLICT,
+            Message::InvalidToken => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNPROCESSABLE_ENTITY
+        };
+        Failure::new(status, message)
+    }
+}
+
+/// The failure of a payload which couldn't be read, naming the field when serde tells it.
+fn payload_failure(status: StatusCode, error: &str) -> Failure {
+    let missing = error.split("missing field `").nth(1).and_then(|rest| rest.split('`').next());
+    match missing {
+        Some(field) => Failure { status, message: Message::MissingParameter, parameter: Some(field.to_string()) },
+        None => Failure::new(status, Message::IncorrectParameter)
+    }
+}
+
+/// Answers the query strings which can't be read with a `Problem` rather than a plain text.
+pub(crate) fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|error, req| {
+        let QueryPayloadError::Deserialize(cause) = &error;
+        let response = payload_failure(StatusCode::BAD_REQUEST, &cause.to_string()).respond(request_language(req));
+        InternalError::from_response(error, response).into()
+    })
+}
+
+/// Answers the JSON bodies which can't be read with a `Problem` rather than a plain text,
+/// a well-formed body with wrong fields being unprocessable.
+pub(crate) fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|error, req| {
+        let failure = match &error {
+            JsonPayloadError::Deserialize(cause) if cause.is_data() => payload_failure(StatusCode::UNPROCESSABLE_ENTITY, &cause.to_string()),
+            JsonPayloadError::Deserialize(cause) => payload_failure(StatusCode::BAD_REQUEST, &cause.to_string()),
+            _ => Failure::new(error.status_code(), Message::IncorrectParameter)
+        };
+        let response = failure.respond(request_language(req));
+        InternalError::from_response(error, response).into()
+    })
+}
+
+/// Answers the requests matching no route, or no method of their route, with a `Problem` rather than an empty body.
+pub(crate) fn error_handlers() -> ErrorHandlers<Body> {
+    ErrorHandlers::new()
+        .handler(StatusCode::NOT_FOUND, |res| empty_error(res, Message::RouteNotFound))
+        .handler(StatusCode::METHOD_NOT_ALLOWED, |res| empty_error(res, Message::MethodNotAllowed))
+}
+
+fn empty_error(res: ServiceResponse<Body>, message: Message) -> Result<ErrorHandlerResponse<Body>> {
+    if res.headers().contains_key(header::CONTENT_TYPE) {
+        return Ok(ErrorHandlerResponse::Response(res));
+    }
+
+    let response = Failure::new(res.status(), message).respond(request_language(res.request()));
+    Ok(ErrorHandlerResponse::Response(res.into_response(response)))
+}
+
+/// Tells if the request sends the administration token, the administration being disabled when it is not set.
+fn is_admin(req: &HttpRequest) -> bool {
+    let token = req.headers().get(ADMIN_TOKEN).and_then(|value| value.to_str().ok());
+    match (env::var("ADMIN_TOKEN"), token) {
+        (Ok(expected), Some(token)) if !expected.is_empty() => ApiKey::hash(&expected) == ApiKey::hash(token),
+        _ => false
+    }
+}
+
+pub(crate) fn event_hub(req: &HttpRequest) -> Option<&EventHub> {
+    req.app_data::<web::Data<EventHub>>().map(|hub| hub.get_ref())
+}
+
+/// Sends the events of a deck to the webhooks subscribed to them, in the background.
+fn trigger_webhooks<C: postgres::GenericClient>(connection: &mut C, deck: &Deck, events: &[WebhookEvent]) {
+    webhook::dispatch(Delivery::prepare(connection, deck, events), RetryPolicy::default());
+}
+
+/// Tells the clients listening to a deck how it changed.
+fn publish_deck_change(req: &HttpRequest, deck: &Deck, kind: EventKind, results: &[StepResult]) {
+    if let Some(hub) = event_hub(req) {
+        hub.publish(deck.id, kind, Audience::Everyone, DeckChange::new(deck, results));
+    }
+}
+
+/// Checks that an API key may own new decks, the decks created without API key not being counted.
+fn check_deck_quota<C: postgres::GenericClient>(owner: Option<Uuid>, new_decks: usize, connection: &mut C) -> std::result::Result<(), Failure> {
+    let max_decks = rate_limit::max_decks();
+    match owner {
+        Some(owner) if Deck::count_by_owner(owner, connection) + new_decks > max_decks => Err(Failure::new(StatusCode::TOO_MANY_REQUESTS, Message::TooManyDecks(max_decks))),
+        _ => Ok(())
+    }
+}
+
+/// Stores a new game session with its decks, which are owned by the API key of the request if any
+/// and are only changed through the session.
+fn insert_session<T: Serialize + DeserializeOwned>(req: &HttpRequest, session: &Session<T>, decks: &mut [Deck]) -> std::result::Result<(), Failure> {
+    let owner = request_api_key(req).map(|api_key| api_key.id);
+    let mut connection = establish_connection();
+    if let Err(failure) = check_deck_quota(owner, decks.len(), &mut connection) {
+        connection.close().ok();
+        return Err(failure);
+    }
+    for deck in decks.iter_mut() {
+        deck.owner = owner;
+        deck.session = Some(session.id);
+        deck.insert_into_db(&mut connection);
+    }
+    session.insert_into_db(&mut connection);
+    connection.close().ok();
+
+    Ok(())
+}
+
+/// Creates a deck owned by the API key of the request, if any.
+pub(crate) fn create_deck(req: &HttpRequest, size: Option<i32>, theme: Option<String>) -> std::result::Result<Deck, Failure> {
+    if let Some(theme) = &theme {
+        if Theme::find(theme).is_none() {
+            return Err(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::UnknownTheme(theme.clone())).parameter("theme"));
+        }
+    }
+
+    let mut deck = Deck::new(deck_size(size));
+    deck.theme = theme;
+    deck.owner = request_api_key(req).map(|api_key| api_key.id);
     let mut connection = establish_connection();
+    if let Err(failure) = check_deck_quota(deck.owner, 1, &mut connection) {
+        connection.close().ok();
+        return Err(failure);
+    }
     deck.insert_into_db(&mut connection);
+    trigger_webhooks(&mut connection, &deck, &[WebhookEvent::Created]);
     connection.close().ok();
 
-    Ok(HttpResponse::Created().json(DeckRepresentation::from(deck)))
+    Ok(deck)
+}
+
+pub async fn post_cards(req: HttpRequest, query: web::Query<DeckSizeRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    match create_deck(&req, query.size, query.theme.clone()) {
+        Ok(deck) => Ok(HttpResponse::Created().json(represent(&req, deck, language))),
+        Err(failure) => Ok(failure.respond(language))
+    }
 }
 
 pub async fn shuffle_cards(req: HttpRequest, query: web::Query<DeckShuffleRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
     let deck_id = req.match_info().get("id");
     match deck_id {
         Some(deck_id) => {
@@ -45,26 +470,34 @@ pub async fn shuffle_cards(req: HttpRequest, query: web::Query<DeckShuffleReques
                     let deck = Deck::find_by_id(deck_id, &mut connection);
                     match deck {
                         Some(mut deck) => {
+                            if let Err(error) = Credentials::from_request(&req).authorize(&deck, &[Scope::Shuffle], &mut connection) {
+                                connection.close().ok();
+                                return Ok(access_denied(error, language));
+                            }
                             match query.shuffle_discarded {
                                 Some(true) => deck.shuffle(true),
                                 _ => deck.shuffle(false)
                             }
                             deck.update_db(&mut connection);
+                            metrics::SHUFFLES.inc(&[]);
+                            trigger_webhooks(&mut connection, &deck, &[WebhookEvent::Shuffled]);
                             connection.close().ok();
+                            publish_deck_change(&req, &deck, EventKind::Shuffled, &[StepResult::Shuffled]);
 
-                            Ok(HttpResponse::Ok().json(DeckRepresentation::from(deck)))
+                            Ok(HttpResponse::Ok().json(represent(&req, deck, language)))
                         }
-                        None => Ok(HttpResponse::NotFound().json(JsonMessage { message: "Impossible de trouver ce paquet de carte." }))
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
                     }
                 }
-                Err(_) => Ok(HttpResponse::UnprocessableEntity().json(JsonMessage { message: "Paramètre incorrect." }))
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
             }
         }
-        None => Ok(HttpResponse::MethodNotAllowed().json(JsonMessage { message: "Paramètre manquant." }))
+        None => Ok(Failure::missing("id").respond(language))
     }
 }
 
 pub async fn take_cards(req: HttpRequest, query: web::Query<DeckSwitch>) -> Result<HttpResponse> {
+    let language = request_language(&req);
     let deck_id = req.match_info().get("id");
     match deck_id {
         Some(deck_id) => {
@@ -75,27 +508,36 @@ pub async fn take_cards(req: HttpRequest, query: web::Query<DeckSwitch>) -> Resu
                     let deck = Deck::find_by_id(deck_id, &mut connection);
                     match deck {
                         Some(mut deck) => {
-                            deck.take(
+                            if let Err(error) = Credentials::from_request(&req).authorize(&deck, &[Scope::Draw], &mut connection) {
+                                connection.close().ok();
+                                return Ok(access_denied(error, language));
+                            }
+                            let before = deck.cards.len();
+                            let moved = deck.take(
                                 query.lifo.unwrap_or(false),
                                 query.length.unwrap_or(1),
                                 query.move_as_block.unwrap_or(false)
                             );
                             deck.update_db(&mut connection);
+                            metrics::CARDS_DRAWN.inc_by(&[], moved.len() as u64);
+                            trigger_webhooks(&mut connection, &deck, &webhook::drawn(before, &deck));
                             connection.close().ok();
+                            publish_deck_change(&req, &deck, EventKind::Taken, &[StepResult::Moved(moved.clone())]);
 
-                            Ok(HttpResponse::Ok().json(DeckRepresentation::from(deck)))
+                            Ok(switch_response(&req, deck, moved, &query, language))
                         }
-                        None => Ok(HttpResponse::NotFound().json(JsonMessage { message: "Impossible de trouver ce paquet de carte." }))
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
                     }
                 }
-                Err(_) => Ok(HttpResponse::UnprocessableEntity().json(JsonMessage { message: "Paramètre incorrect." }))
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
             }
         }
-        None => Ok(HttpResponse::MethodNotAllowed().json(JsonMessage { message: "Paramètre manquant." }))
+        None => Ok(Failure::missing("id").respond(language))
     }
 }
 
 pub async fn put_cards(req: HttpRequest, query: web::Query<DeckSwitch>) -> Result<HttpResponse> {
+    let language = request_language(&req);
     let deck_id = req.match_info().get("id");
     match deck_id {
         Some(deck_id) => {
@@ -106,50 +548,296 @@ pub async fn put_cards(req: HttpRequest, query: web::Query<DeckSwitch>) -> Resul
                     let deck = Deck::find_by_id(deck_id, &mut connection);
                     match deck {
                         Some(mut deck) => {
-                            deck.put(
+                            if let Err(error) = Credentials::from_request(&req).authorize(&deck, &[Scope::Put], &mut connection) {
+                                connection.close().ok();
+                                return Ok(access_denied(error, language));
+                            }
+                            let moved = deck.put(
                                 query.lifo.unwrap_or(false),
                                 query.length.unwrap_or(1),
                                 query.move_as_block.unwrap_or(false)
                             );
                             deck.update_db(&mut connection);
                             connection.close().ok();
+                            publish_deck_change(&req, &deck, EventKind::Put, &[StepResult::Moved(moved.clone())]);
+
+                            Ok(switch_response(&req, deck, moved, &query, language))
+                        }
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn move_cards(req: HttpRequest, query: web::Query<DeckMoveRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let to_discarded = query.to_discarded.unwrap_or(true);
+    let scope = if to_discarded { Scope::Draw } else { Scope::Put };
+    let deck_id = req.match_info().get("id");
+    match deck_id {
+        Some(deck_id) => {
+            let deck_id = Uuid::parse_str(deck_id);
+            match (deck_id, parse_cards(&query.cards)) {
+                (Ok(deck_id), Ok(cards)) => {
+                    let mut connection = establish_connection();
+                    let deck = Deck::find_by_id(deck_id, &mut connection);
+                    match deck {
+                        Some(mut deck) => {
+                            if let Err(error) = Credentials::from_request(&req).authorize(&deck, &[scope], &mut connection) {
+                                connection.close().ok();
+                                return Ok(access_denied(error, language));
+                            }
+                            let before = deck.cards.len();
+                            match deck.move_cards(&cards, to_discarded) {
+                                Ok(()) => {
+                                    deck.update_db(&mut connection);
+                                    if to_discarded {
+                                        metrics::CARDS_DRAWN.inc_by(&[], cards.len() as u64);
+                                    }
+                                    trigger_webhooks(&mut connection, &deck, &webhook::drawn(before, &deck));
+                                    connection.close().ok();
+                                    publish_deck_change(&req, &deck, EventKind::Moved, &[StepResult::Moved(cards)]);
 
-                            Ok(HttpResponse::Ok().json(DeckRepresentation::from(deck)))
+                                    Ok(HttpResponse::Ok().json(represent(&req, deck, language)))
+                                }
+                                Err(card) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::CardNotInStack(card)).respond(language))
+                            }
                         }
-                        None => Ok(HttpResponse::NotFound().json(JsonMessage { message: "Impossible de trouver ce paquet de carte." }))
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
                     }
                 }
-                Err(_) => Ok(HttpResponse::UnprocessableEntity().json(JsonMessage { message: "Paramètre incorrect." }))
+                (Ok(_), Err(error)) => Ok(Failure::new(StatusCode::BAD_REQUEST, error.message()).parameter("cards").respond(language)),
+                (Err(_), _) => Ok(Failure::incorrect("id").respond(language))
             }
         }
-        None => Ok(HttpResponse::MethodNotAllowed().json(JsonMessage { message: "Paramètre manquant." }))
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+/// Why operations could not be applied to a deck.
+pub(crate) enum BatchFailure {
+    Access(AccessError),
+    Batch(BatchError),
+}
+
+impl BatchFailure {
+    pub(crate) fn message(&self) -> Message {
+        match self {
+            BatchFailure::Access(error) => error.message(),
+            BatchFailure::Batch(error) => error.message(),
+        }
     }
 }
 
+impl From<BatchFailure> for Failure {
+    fn from(failure: BatchFailure) -> Failure {
+        match failure {
+            BatchFailure::Access(error) => error.into(),
+            BatchFailure::Batch(error) => Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()),
+        }
+    }
+}
+
+/// Applies operations to a deck in one transaction, then tells the clients listening to the deck.
+///
+/// returns: None when the deck doesn't exist.
+pub(crate) fn apply_batch(hub: Option<&EventHub>, credentials: &Credentials, deck_id: Uuid, operations: &[Operation]) -> Option<std::result::Result<(Deck, Vec<StepResult>), BatchFailure>> {
+    let mut connection = establish_connection();
+    let mut transaction = connection.transaction().expect("An error occurred.");
+    let deck = Deck::find_by_id_for_update(deck_id, &mut transaction);
+    match deck {
+        Some(mut deck) => {
+            let scopes: Vec<Scope> = operations.iter().map(Operation::scope).collect();
+            if let Err(error) = credentials.authorize(&deck, &scopes, &mut transaction) {
+                transaction.rollback().ok();
+                return Some(Err(BatchFailure::Access(error)));
+            }
+            let before = deck.cards.len();
+            match batch::apply(&mut deck, operations) {
+                Ok(results) => {
+                    deck.update_db(&mut transaction);
+                    transaction.commit().expect("An error occurred.");
+                    metrics::CARDS_DRAWN.inc_by(&[], batch::drawn(operations, &results) as u64);
+                    metrics::SHUFFLES.inc_by(&[], results.iter().filter(|result| **result == StepResult::Shuffled).count() as u64);
+                    let mut events = webhook::drawn(before, &deck);
+                    if operations.iter().any(|operation| matches!(operation, Operation::Shuffle { .. })) {
+                        events.insert(0, WebhookEvent::Shuffled);
+                    }
+                    trigger_webhooks(&mut connection, &deck, &events);
+                    connection.close().ok();
+                    if let Some(hub) = hub {
+                        hub.publish(deck.id, EventKind::Batch, Audience::Everyone, DeckChange::new(&deck, &results));
+                    }
+
+                    Some(Ok((deck, results)))
+                }
+                Err(error) => {
+                    transaction.rollback().ok();
+                    Some(Err(BatchFailure::Batch(error)))
+                }
+            }
+        }
+        None => None
+    }
+}
+
+pub async fn batch_cards(req: HttpRequest, body: web::Json<BatchRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let deck_id = req.match_info().get("id");
+    match deck_id {
+        Some(deck_id) => {
+            let deck_id = Uuid::parse_str(deck_id);
+            match deck_id {
+                Ok(deck_id) => {
+                    match apply_batch(event_hub(&req), &Credentials::from_request(&req), deck_id, &body.operations) {
+                        Some(Ok((deck, results))) => {
+                            let theme = request_theme(&req, &deck);
+                            Ok(HttpResponse::Ok().json(BatchRepresentation::translated(deck, results, language, &theme)))
+                        }
+                        Some(Err(BatchFailure::Access(error))) => Ok(access_denied(error, language)),
+                        Some(Err(error)) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).respond(language)),
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+/// Finds a deck the request may read.
+pub(crate) fn read_deck(req: &HttpRequest, deck_id: Uuid) -> std::result::Result<Deck, Failure> {
+    Credentials::from_request(req).authorize_token(deck_id, &[Scope::Read])?;
+    let mut connection = establish_connection();
+    let deck = Deck::find_by_id(deck_id, &mut connection);
+    connection.close().ok();
+
+    deck.ok_or_else(|| Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound))
+}
+
 pub async fn get_cards(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let deck_id = req.match_info().get("id");
+    match deck_id {
+        Some(deck_id) => {
+            let deck_id = Uuid::parse_str(deck_id);
+            match deck_id {
+                Ok(deck_id) => {
+                    match read_deck(&req, deck_id) {
+                        Ok(deck) => Ok(HttpResponse::Ok().json(represent(&req, deck, language))),
+                        Err(failure) => Ok(failure.respond(language))
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+/// Deletes a deck, then tells its webhooks and the clients listening to it.
+pub(crate) fn remove_deck(req: &HttpRequest, deck_id: Uuid) -> std::result::Result<(), Failure> {
+    let mut connection = establish_connection();
+    let deck = match Deck::find_by_id(deck_id, &mut connection) {
+        Some(deck) => deck,
+        None => {
+            connection.close().ok();
+            return Err(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound));
+        }
+    };
+    if let Err(error) = Credentials::from_request(req).authorize(&deck, &[Scope::Admin], &mut connection) {
+        connection.close().ok();
+        return Err(error.into());
+    }
+    let deliveries = Delivery::prepare(&mut connection, &deck, &[WebhookEvent::Deleted]);
+    deck.delete_from_db(&mut connection);
+    connection.close().ok();
+    webhook::dispatch(deliveries, RetryPolicy::default());
+    if let Some(hub) = event_hub(req) {
+        hub.publish(deck.id, EventKind::Deleted, Audience::Everyone, ());
+        hub.close(deck.id);
+    }
+
+    Ok(())
+}
+
+pub async fn delete_cards(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let deck_id = req.match_info().get("id");
+    match deck_id {
+        Some(deck_id) => {
+            let deck_id = Uuid::parse_str(deck_id);
+            match deck_id {
+                Ok(deck_id) => {
+                    match remove_deck(&req, deck_id) {
+                        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+                        Err(failure) => Ok(failure.respond(language))
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn stream_deck_events(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
     let deck_id = req.match_info().get("id");
     match deck_id {
         Some(deck_id) => {
             let deck_id = Uuid::parse_str(deck_id);
             match deck_id {
                 Ok(deck_id) => {
+                    if let Err(error) = Credentials::from_request(&req).authorize_token(deck_id, &[Scope::Read]) {
+                        return Ok(access_denied(error, language));
+                    }
                     let mut connection = establish_connection();
                     let deck = Deck::find_by_id(deck_id, &mut connection);
                     connection.close().ok();
 
                     match deck {
-                        Some(deck) => Ok(HttpResponse::Ok().json(DeckRepresentation::from(deck))),
-                        None => Ok(HttpResponse::NotFound().json(JsonMessage { message: "Impossible de trouver ce paquet de carte." }))
+                        Some(deck) => {
+                            let last_event_id = req.headers()
+                                .get(LAST_EVENT_ID)
+                                .and_then(|value| value.to_str().ok())
+                                .and_then(|value| value.trim().parse::<u64>().ok());
+                            let hub = event_hub(&req).expect("The event hub must be registered.");
+                            let (missed, receiver) = hub.resume(deck.id, None, last_event_id);
+                            let stream = events::sse_stream(missed, receiver, events::KEEP_ALIVE)
+                                .map(Ok::<_, actix_web::Error>);
+
+                            Ok(HttpResponse::Ok()
+                                .content_type("text/event-stream")
+                                .header(header::CACHE_CONTROL, "no-cache")
+                                .streaming(stream))
+                        }
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
                     }
                 }
-                Err(_) => Ok(HttpResponse::UnprocessableEntity().json(JsonMessage { message: "Paramètre incorrect." }))
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
             }
         }
-        None => Ok(HttpResponse::MethodNotAllowed().json(JsonMessage { message: "Paramètre manquant." }))
+        None => Ok(Failure::missing("id").respond(language))
     }
 }
 
-pub async fn delete_cards(req: HttpRequest) -> Result<HttpResponse> {
+/// Issues a token for a deck, only the clients allowed to administrate the deck being able to.
+pub async fn post_deck_token(req: HttpRequest, body: web::Json<TokenRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    if body.scopes.is_empty() {
+        return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::MissingParameter).parameter("scopes").respond(language));
+    }
+    let lifetime = match jwt::lifetime(body.expires_in) {
+        Some(lifetime) => lifetime,
+        None => return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::IncorrectParameter).parameter("expires_in").respond(language))
+    };
+
     let deck_id = req.match_info().get("id");
     match deck_id {
         Some(deck_id) => {
@@ -160,17 +848,682 @@ pub async fn delete_cards(req: HttpRequest) -> Result<HttpResponse> {
                     let deck = Deck::find_by_id(deck_id, &mut connection);
                     match deck {
                         Some(deck) => {
-                            deck.delete_from_db(&mut connection);
+                            let access = Credentials::from_request(&req).authorize(&deck, &[Scope::Admin], &mut connection);
+                            connection.close().ok();
+                            if let Err(error) = access {
+                                return Ok(access_denied(error, language));
+                            }
+                            let player = body.player.unwrap_or_else(Uuid::new_v4);
+                            let claims = Claims::new(player, Some(deck.id), None, body.scopes.clone(), lifetime);
+
+                            Ok(HttpResponse::Created().json(TokenRepresentation::issue(claims)))
+                        }
+                        None => {
                             connection.close().ok();
+                            Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
+                        }
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+/// Lets another API key change a deck, only its owner being allowed to grant access to it.
+pub async fn grant_deck(req: HttpRequest, body: web::Json<GrantRequest>) -> Result<HttpResponse> {
+    Ok(manage_grant(&req, body.api_key_id, true))
+}
+
+pub async fn revoke_deck(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    match req.match_info().get("api_key_id").map(Uuid::parse_str) {
+        Some(Ok(api_key_id)) => Ok(manage_grant(&req, api_key_id, false)),
+        Some(Err(_)) => Ok(Failure::incorrect("api_key_id").respond(language)),
+        None => Ok(Failure::missing("api_key_id").respond(language))
+    }
+}
+
+fn manage_grant(req: &HttpRequest, api_key_id: Uuid, granted: bool) -> HttpResponse {
+    let language = request_language(req);
+    let deck_id = req.match_info().get("id").map(Uuid::parse_str);
+    match deck_id {
+        Some(Ok(deck_id)) => {
+            let mut connection = establish_connection();
+            let deck = Deck::find_by_id(deck_id, &mut connection);
+            let api_key = ApiKey::find_by_id(api_key_id, &mut connection);
+            let owner = request_api_key(req).map(|api_key| api_key.id);
+            let response = match (deck, api_key) {
+                (Some(deck), _) if deck.owner.is_none() || deck.owner != owner => {
+                    access_denied(if owner.is_none() { AccessError::Unauthorized } else { AccessError::Forbidden }, language)
+                }
+                (Some(deck), Some(api_key)) => {
+                    if granted {
+                        api_key::grant(deck.id, api_key.id, &mut connection);
+                    } else {
+                        api_key::revoke(deck.id, api_key.id, &mut connection);
+                    }
+                    HttpResponse::NoContent().finish()
+                }
+                (Some(_), None) => Failure::new(StatusCode::NOT_FOUND, Message::ApiKeyNotFound).respond(language),
+                (None, _) => Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language)
+            };
+            connection.close().ok();
+
+            response
+        }
+        Some(Err(_)) => Failure::incorrect("id").respond(language),
+        None => Failure::missing("id").respond(language)
+    }
+}
+
+pub async fn post_api_key(req: HttpRequest, body: web::Json<ApiKeyRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    if !is_admin(&req) {
+        return Ok(Failure::from(AccessError::AdminRequired).respond(language));
+    }
+    if body.name.trim().is_empty() {
+        return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::MissingParameter).parameter("name").respond(language));
+    }
+
+    let (api_key, key) = ApiKey::generate(&body.name);
+    let mut connection = establish_connection();
+    api_key.insert_into_db(&mut connection);
+    connection.close().ok();
+
+    Ok(HttpResponse::Created().json(ApiKeyRepresentation::from(&api_key, Some(key))))
+}
+
+pub async fn delete_api_key(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    if !is_admin(&req) {
+        return Ok(Failure::from(AccessError::AdminRequired).respond(language));
+    }
+
+    let api_key_id = req.match_info().get("id").map(Uuid::parse_str);
+    match api_key_id {
+        Some(Ok(api_key_id)) => {
+            let mut connection = establish_connection();
+            let api_key = ApiKey::find_by_id(api_key_id, &mut connection);
+            match api_key {
+                Some(api_key) => {
+                    api_key.delete_from_db(&mut connection);
+                    connection.close().ok();
+
+                    Ok(HttpResponse::NoContent().finish())
+                }
+                None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::ApiKeyNotFound).respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn post_webhook(req: HttpRequest, body: web::Json<WebhookRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let url = body.url.trim();
+    match webhook::check_url(url) {
+        Ok(()) => (),
+        Err(UrlError::Invalid) => return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::InvalidUrl(url.to_string())).parameter("url").respond(language)),
+        Err(UrlError::ForbiddenHost) => return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::ForbiddenHost(url.to_string())).parameter("url").respond(language)),
+    }
+
+    let admin = is_admin(&req);
+    let api_key = request_api_key(&req);
+    let owner = match (admin, &api_key, body.deck_id) {
+        (true, _, _) => None,
+        (false, _, None) => return Ok(Failure::from(AccessError::AdminRequired).respond(language)),
+        (false, None, Some(_)) => return Ok(Failure::from(AccessError::Unauthorized).respond(language)),
+        (false, Some(api_key), Some(_)) => Some(api_key.id),
+    };
+
+    let mut connection = establish_connection();
+    if let Some(deck_id) = body.deck_id {
+        let access = match Deck::find_by_id(deck_id, &mut connection) {
+            Some(_) if admin => Ok(()),
+            Some(deck) => ApiKey::authorize(api_key.as_ref(), &deck, &mut connection).map_err(Failure::from),
+            None => Err(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).parameter("deck_id"))
+        };
+        if let Err(failure) = access {
+            connection.close().ok();
+            return Ok(failure.respond(language));
+        }
+    }
+    let events = match &body.events {
+        Some(events) if !events.is_empty() => events.clone(),
+        _ => WebhookEvent::ALL.to_vec()
+    };
+    let mut webhook = Webhook::new(url, body.deck_id, events);
+    webhook.owner = owner;
+    webhook.insert_into_db(&mut connection);
+    connection.close().ok();
+
+    Ok(HttpResponse::Created().json(WebhookRepresentation::from(&webhook, true)))
+}
+
+/// Finds a webhook the request may see or delete, the admin being allowed to reach every webhook.
+fn find_webhook<C: postgres::GenericClient>(req: &HttpRequest, webhook_id: Uuid, connection: &mut C) -> std::result::Result<Webhook, Failure> {
+    match Webhook::find_by_id(webhook_id, connection) {
+        Some(webhook) if is_admin(req) => Ok(webhook),
+        Some(webhook) => webhook.authorize(request_api_key(req).map(|api_key| api_key.id)).map(|_| webhook).map_err(Failure::from),
+        None => Err(Failure::new(StatusCode::NOT_FOUND, Message::WebhookNotFound))
+    }
+}
+
+pub async fn get_webhook(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let webhook_id = req.match_info().get("id").map(Uuid::parse_str);
+    match webhook_id {
+        Some(Ok(webhook_id)) => {
+            let mut connection = establish_connection();
+            let webhook = find_webhook(&req, webhook_id, &mut connection);
+            connection.close().ok();
+
+            match webhook {
+                Ok(webhook) => Ok(HttpResponse::Ok().json(WebhookRepresentation::from(&webhook, false))),
+                Err(failure) => Ok(failure.respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn delete_webhook(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let webhook_id = req.match_info().get("id").map(Uuid::parse_str);
+    match webhook_id {
+        Some(Ok(webhook_id)) => {
+            let mut connection = establish_connection();
+            let webhook = find_webhook(&req, webhook_id, &mut connection);
+            if let Ok(webhook) = &webhook {
+                webhook.delete_from_db(&mut connection);
+            }
+            connection.close().ok();
+
+            match webhook {
+                Ok(_) => Ok(HttpResponse::NoContent().finish()),
+                Err(failure) => Ok(failure.respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+/// Lists the deliveries to a webhook which failed after every attempt.
+pub async fn get_webhook_dead_letters(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let webhook_id = req.match_info().get("id").map(Uuid::parse_str);
+    match webhook_id {
+        Some(Ok(webhook_id)) => {
+            let mut connection = establish_connection();
+            let webhook = find_webhook(&req, webhook_id, &mut connection);
+            let dead_letters = webhook.map(|webhook| DeadLetter::find_by_webhook(webhook.id, &mut connection));
+            connection.close().ok();
+
+            match dead_letters {
+                Ok(dead_letters) => Ok(HttpResponse::Ok().json(dead_letters)),
+                Err(failure) => Ok(failure.respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn get_card_image(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let code = req.match_info().get("code");
+    match code {
+        Some("back") => Ok(HttpResponse::Ok().content_type("image/svg+xml").body(render_back())),
+        Some(code) => {
+            match code.parse::<PlayingCard>() {
+                Ok(card) => Ok(HttpResponse::Ok().content_type("image/svg+xml").body(render_card(card))),
+                Err(error) => Ok(Failure::new(StatusCode::NOT_FOUND, error.message()).parameter("code").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("code").respond(language))
+    }
+}
+
+pub async fn evaluate_poker(req: HttpRequest, body: web::Json<PokerEvaluationRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let hands: std::result::Result<Vec<Vec<PlayingCard>>, _> = body.hands.iter()
+        .map(|hand| hand.iter().map(|code| code.parse::<PlayingCard>()).collect())
+        .collect();
+    let board: std::result::Result<Vec<PlayingCard>, _> = body.board.iter()
+        .flatten()
+        .map(|code| code.parse::<PlayingCard>())
+        .collect();
+
+    match (hands, board) {
+        (Ok(hands), Ok(board)) => {
+            match EvaluationRepresentation::evaluate(&hands, &board) {
+                Ok(evaluation) => Ok(HttpResponse::Ok().json(evaluation)),
+                Err(error) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).respond(language))
+            }
+        }
+        (Err(error), _) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).parameter("hands").respond(language)),
+        (_, Err(error)) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).parameter("board").respond(language))
+    }
+}
+
+pub async fn holdem_odds(req: HttpRequest, body: web::Json<HoldemOddsRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let hole: std::result::Result<Vec<PlayingCard>, _> = body.hole.iter()
+        .map(|code| code.parse::<PlayingCard>())
+        .collect();
+    let board: std::result::Result<Vec<PlayingCard>, _> = body.board.iter()
+        .flatten()
+        .map(|code| code.parse::<PlayingCard>())
+        .collect();
+
+    match (hole, board) {
+        (Ok(hole), Ok(board)) => {
+            let iterations = body.iterations.unwrap_or(DEFAULT_ITERATIONS);
+            let opponents = body.opponents.unwrap_or(1);
+            // The simulation may take a while, so it runs on the thread pool rather than on the worker.
+            match web::block(move || odds::holdem(&hole, &board, opponents, iterations)).await {
+                Ok(odds) => Ok(HttpResponse::Ok().json(odds)),
+                Err(BlockingError::Error(error)) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).respond(language)),
+                Err(error) => Err(error.into())
+            }
+        }
+        (Err(error), _) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).parameter("hole").respond(language)),
+        (_, Err(error)) => Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).parameter("board").respond(language))
+    }
+}
 
-                            Ok(HttpResponse::NoContent().finish())
+/// Loads a game session and its deck, applies a change to them and saves them in one transaction,
+/// then publishes the events of the change to the clients listening to the session.
+///
+/// returns: None when the session or its deck doesn't exist.
+pub(crate) fn transact_session<T, F>(hub: Option<&EventHub>, session_id: Uuid, kind: &str, apply: F) -> Option<std::result::Result<(Session<T>, Deck), Message>>
+    where T: Serialize + DeserializeOwned,
+          F: FnOnce(&mut T, &mut Deck) -> std::result::Result<Vec<Notice>, Message>
+{
+    let mut connection = establish_connection();
+    let mut transaction = connection.transaction().expect("An error occurred.");
+    let session = Session::<T>::find_by_id_for_update(session_id, kind, &mut transaction);
+    let deck = session.as_ref().and_then(|session| Deck::find_in_session_for_update(session.deck_id, session.id, &mut transaction));
+    match (session, deck) {
+        (Some(mut session), Some(mut deck)) => {
+            match apply(&mut session.state, &mut deck) {
+                Ok(notices) => {
+                    session.update_db(&mut transaction);
+                    deck.update_db(&mut transaction);
+                    transaction.commit().expect("An error occurred.");
+                    connection.close().ok();
+                    if let Some(hub) = hub {
+                        for notice in notices {
+                            hub.notify(session.id, notice);
                         }
-                        None => Ok(HttpResponse::NotFound().json(JsonMessage { message: "Impossible de trouver ce paquet de carte." }))
+                    }
+
+                    Some(Ok((session, deck)))
+                }
+                Err(message) => {
+                    transaction.rollback().ok();
+                    Some(Err(message))
+                }
+            }
+        }
+        _ => None
+    }
+}
+
+fn update_session<T, F, R, S>(req: &HttpRequest, kind: &str, apply: F, represent: R) -> HttpResponse
+    where T: Serialize + DeserializeOwned,
+          F: FnOnce(&mut T, &mut Deck) -> std::result::Result<Vec<Notice>, Message>,
+          R: FnOnce(&Session<T>, &Deck, Language) -> S,
+          S: Serialize
+{
+    let language = request_language(req);
+    let session_id = req.match_info().get("id");
+    match session_id {
+        Some(session_id) => {
+            let session_id = Uuid::parse_str(session_id);
+            match session_id {
+                Ok(session_id) => {
+                    match transact_session(event_hub(req), session_id, kind, apply) {
+                        Some(Ok((session, deck))) => HttpResponse::Ok().json(represent(&session, &deck, language)),
+                        Some(Err(message)) => Failure::from(message).respond(language),
+                        None => Failure::new(StatusCode::NOT_FOUND, Message::SessionNotFound).respond(language)
                     }
                 }
-                Err(_) => Ok(HttpResponse::UnprocessableEntity().json(JsonMessage { message: "Paramètre incorrect." }))
+                Err(_) => Failure::incorrect("id").respond(language)
+            }
+        }
+        None => Failure::missing("id").respond(language)
+    }
+}
+
+fn get_session<T, R, S>(req: &HttpRequest, kind: &str, represent: R) -> HttpResponse
+    where T: Serialize + DeserializeOwned,
+          R: FnOnce(&Session<T>, &Deck, Language) -> S,
+          S: Serialize
+{
+    let language = request_language(req);
+    let session_id = req.match_info().get("id").map(Uuid::parse_str);
+    match session_id {
+        Some(Ok(session_id)) => {
+            let mut connection = establish_connection();
+            let session = Session::<T>::find_by_id(session_id, kind, &mut connection);
+            let deck = session.as_ref().and_then(|session| Deck::find_in_session(session.deck_id, session.id, &mut connection));
+            connection.close().ok();
+
+            match (session, deck) {
+                (Some(session), Some(deck)) => HttpResponse::Ok().json(represent(&session, &deck, language)),
+                _ => Failure::new(StatusCode::NOT_FOUND, Message::SessionNotFound).respond(language)
             }
         }
-        None => Ok(HttpResponse::MethodNotAllowed().json(JsonMessage { message: "Paramètre manquant." }))
+        Some(Err(_)) => Failure::incorrect("id").respond(language),
+        None => Failure::missing("id").respond(language)
+    }
+}
+
+fn represent_table(req: &HttpRequest, session: &Session<Table>, shoe: &Deck, language: Language) -> TableRepresentation {
+    let theme = request_theme(req, shoe);
+    TableRepresentation::translated(session.id, &session.state, shoe, language, &theme)
+}
+
+fn update_blackjack_table<F>(req: &HttpRequest, apply: F) -> HttpResponse
+    where F: FnOnce(&mut Table, &mut Deck) -> std::result::Result<(), BlackjackError>
+{
+    update_session(
+        req,
+        blackjack::SESSION_KIND,
+        |table, shoe| apply(table, shoe).map(|()| Vec::new()).map_err(|error| error.message()),
+        |session, shoe, language| represent_table(req, session, shoe, language),
+    )
+}
+
+pub async fn post_blackjack_table(req: HttpRequest, query: web::Query<BlackjackTableRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let decks = query.decks.unwrap_or(6);
+    if decks == 0 || decks > blackjack::MAX_DECKS {
+        return Ok(Failure::incorrect("decks").respond(language));
+    }
+
+    let table = Table::new(Rules::new(decks, query.hit_soft_17.unwrap_or(false)));
+    let mut shoe = table.new_shoe();
+    let session = Session::new(blackjack::SESSION_KIND, shoe.id, table);
+    if let Err(failure) = insert_session(&req, &session, std::slice::from_mut(&mut shoe)) {
+        return Ok(failure.respond(language));
+    }
+
+    Ok(HttpResponse::Created().json(represent_table(&req, &session, &shoe, language)))
+}
+
+pub async fn get_blackjack_table(req: HttpRequest) -> Result<HttpResponse> {
+    Ok(get_session(&req, blackjack::SESSION_KIND, |session, shoe, language| represent_table(&req, session, shoe, language)))
+}
+
+pub async fn deal_blackjack(req: HttpRequest, query: web::Query<BlackjackBetRequest>) -> Result<HttpResponse> {
+    let bet = query.bet.unwrap_or(1);
+    Ok(update_blackjack_table(&req, |table, shoe| table.deal(shoe, bet)))
+}
+
+pub async fn insure_blackjack(req: HttpRequest, query: web::Query<BlackjackInsuranceRequest>) -> Result<HttpResponse> {
+    let take = query.take.unwrap_or(true);
+    Ok(update_blackjack_table(&req, |table, shoe| table.insure(shoe, take)))
+}
+
+pub async fn play_blackjack(req: HttpRequest) -> Result<HttpResponse> {
+    match req.match_info().get("action").unwrap_or("").parse::<Action>() {
+        Ok(action) => Ok(update_blackjack_table(&req, |table, shoe| table.play(shoe, action))),
+        Err(_) => Ok(Failure::incorrect("action").respond(request_language(&req)))
+    }
+}
+
+/// The seat of the player of a belote game, from the `X-Player-Token` header or from the subject of the token,
+/// `deny_game_access` checking that the token was given for this game.
+fn belote_seat(req: &HttpRequest, game: &Game) -> Option<usize> {
+    match player_token(req) {
+        Some(token) => game.seat(token),
+        None => Credentials::from_request(req).claims
+            .filter(|claims| claims.game.is_some())
+            .and_then(|claims| game.seat_by_id(claims.sub))
+    }
+}
+
+fn represent_belote(req: &HttpRequest, session: &Session<Game>, deck: &Deck, language: Language) -> GameRepresentation {
+    let theme = request_theme(req, deck);
+    GameRepresentation::translated(session.id, &session.state, belote_seat(req, &session.state), language, &theme)
+}
+
+/// Runs a change for the seat of the player of the request.
+fn update_belote_game<F>(req: &HttpRequest, apply: F) -> HttpResponse
+    where F: FnOnce(&mut Game, &mut Deck, usize) -> std::result::Result<(), BeloteError>
+{
+    if let Some(response) = deny_game_access(req, Scope::Draw) {
+        return response;
+    }
+    update_session(
+        req,
+        belote::SESSION_KIND,
+        |game, deck| {
+            let seat = belote_seat(req, game).ok_or(Message::InvalidToken)?;
+            apply(game, deck, seat).map(|()| Vec::new()).map_err(|error| error.message())
+        },
+        |session, deck, language| represent_belote(req, session, deck, language),
+    )
+}
+
+pub async fn post_belote_game(req: HttpRequest, query: web::Query<BeloteGameRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let target = query.target.unwrap_or(belote::DEFAULT_TARGET);
+    if target <= 0 {
+        return Ok(Failure::incorrect("target").respond(language));
+    }
+
+    let mut game = Game::new(target);
+    let mut deck = game.new_deck();
+    game.deal(&mut deck).expect("A new game can be dealt.");
+    let session = Session::new(belote::SESSION_KIND, deck.id, game);
+    if let Err(failure) = insert_session(&req, &session, std::slice::from_mut(&mut deck)) {
+        return Ok(failure.respond(language));
+    }
+
+    let mut representation = represent_belote(&req, &session, &deck, language);
+    representation.tokens = Some(session.state.seats.iter().map(|seat| seat.token.clone()).collect());
+
+    Ok(HttpResponse::Created().json(representation))
+}
+
+pub async fn get_belote_game(req: HttpRequest) -> Result<HttpResponse> {
+    if let Some(response) = deny_game_access(&req, Scope::Read) {
+        return Ok(response);
     }
-}
\ No newline at end of file
+    Ok(get_session(&req, belote::SESSION_KIND, |session, deck, language| represent_belote(&req, session, deck, language)))
+}
+
+pub async fn deal_belote(req: HttpRequest) -> Result<HttpResponse> {
+    Ok(update_belote_game(&req, |game, deck, _| game.deal(deck)))
+}
+
+pub async fn bid_belote(req: HttpRequest, body: web::Json<BeloteBidRequest>) -> Result<HttpResponse> {
+    let bid = body.bid;
+    Ok(update_belote_game(&req, |game, deck, seat| game.bid(deck, seat, bid)))
+}
+
+pub async fn play_belote(req: HttpRequest, body: web::Json<BelotePlayRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let card = match body.card.parse::<PlayingCard>() {
+        Ok(card) => card,
+        Err(error) => return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, error.message()).parameter("card").respond(language))
+    };
+    Ok(update_belote_game(&req, |game, deck, seat| game.play(deck, seat, card)))
+}
+
+/// Issues a token for a belote game: the player of a seat may read the game and play, anybody else may only read it.
+pub async fn post_belote_token(req: HttpRequest, query: web::Query<GameTokenRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let lifetime = match jwt::lifetime(query.expires_in) {
+        Some(lifetime) => lifetime,
+        None => return Ok(Failure::incorrect("expires_in").respond(language))
+    };
+
+    let session_id = req.match_info().get("id").map(Uuid::parse_str);
+    match session_id {
+        Some(Ok(session_id)) => {
+            let mut connection = establish_connection();
+            let session = Session::<Game>::find_by_id(session_id, belote::SESSION_KIND, &mut connection);
+            connection.close().ok();
+
+            match session {
+                Some(session) => {
+                    let claims = match player_token(&req) {
+                        Some(token) => match session.state.seat(token) {
+                            Some(seat) => Claims::new(session.state.seats[seat].id, None, Some(session.id), vec![Scope::Read, Scope::Draw], lifetime),
+                            None => return Ok(Failure::from(Message::InvalidToken).parameter(PLAYER_TOKEN).respond(language))
+                        },
+                        None => Claims::new(Uuid::new_v4(), None, Some(session.id), vec![Scope::Read], lifetime)
+                    };
+
+                    Ok(HttpResponse::Created().json(TokenRepresentation::issue(claims)))
+                }
+                None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::SessionNotFound).respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+fn represent_game(req: &HttpRequest, session: &Session<game::Game>, draw_pile: &Deck, language: Language) -> game::GameRepresentation {
+    let theme = request_theme(req, draw_pile);
+    let token = Credentials::from_request(req).player_token(player_token(req), &session.state);
+    game::GameRepresentation::translated(session.id, &session.state, draw_pile, token.as_deref(), language, &theme)
+}
+
+/// Checks that the token of a game request allows a scope on the game, an invalid id being left to the handler.
+fn deny_game_access(req: &HttpRequest, scope: Scope) -> Option<HttpResponse> {
+    let session_id = req.match_info().get("id").and_then(|id| Uuid::parse_str(id).ok())?;
+    Credentials::from_request(req)
+        .authorize_token(session_id, &[scope])
+        .err()
+        .map(|error| access_denied(error, request_language(req)))
+}
+
+/// Runs a command for the player of the request token.
+fn command_game(req: &HttpRequest, command: Command) -> HttpResponse {
+    if let Some(response) = deny_game_access(req, Scope::Draw) {
+        return response;
+    }
+    let credentials = Credentials::from_request(req);
+    update_session(
+        req,
+        game::SESSION_KIND,
+        |game: &mut game::Game, draw_pile| {
+            let token = credentials.player_token(player_token(req), game);
+            let before = draw_pile.cards.len();
+            let notices = game.run(draw_pile, token.as_deref(), &command).map_err(|error| error.message())?;
+            metrics::CARDS_DRAWN.inc_by(&[], (before - draw_pile.cards.len()) as u64);
+            Ok(notices)
+        },
+        |session, draw_pile, language| represent_game(req, session, draw_pile, language),
+    )
+}
+
+pub async fn post_game(req: HttpRequest, query: web::Query<GameRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let players = query.players.unwrap_or(game::MIN_PLAYERS);
+    if !(game::MIN_PLAYERS..=game::MAX_PLAYERS).contains(&players) {
+        return Ok(Failure::incorrect("players").respond(language));
+    }
+    if let Some(theme) = &query.theme {
+        if Theme::find(theme).is_none() {
+            return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::UnknownTheme(theme.clone())).parameter("theme").respond(language));
+        }
+    }
+
+    let mut draw_pile = Deck::new(deck_size(query.size));
+    draw_pile.theme = query.theme.clone();
+    draw_pile.shuffle(false);
+    let session = Session::new(game::SESSION_KIND, draw_pile.id, game::Game::new(draw_pile.id, players));
+    if let Err(failure) = insert_session(&req, &session, std::slice::from_mut(&mut draw_pile)) {
+        return Ok(failure.respond(language));
+    }
+
+    Ok(HttpResponse::Created().json(represent_game(&req, &session, &draw_pile, language)))
+}
+
+pub async fn get_game(req: HttpRequest) -> Result<HttpResponse> {
+    if let Some(response) = deny_game_access(&req, Scope::Read) {
+        return Ok(response);
+    }
+    Ok(get_session(&req, game::SESSION_KIND, |session, draw_pile, language| represent_game(&req, session, draw_pile, language)))
+}
+
+pub async fn join_game(req: HttpRequest, body: web::Json<JoinRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    if body.name.trim().is_empty() {
+        return Ok(Failure::new(StatusCode::UNPROCESSABLE_ENTITY, Message::MissingParameter).parameter("name").respond(language));
+    }
+
+    Ok(update_session(
+        &req,
+        game::SESSION_KIND,
+        |game: &mut game::Game, _| {
+            game.join(&body.name)
+                .map(|player| vec![Notice::new(EventKind::Joined, Audience::Everyone, PlayerRepresentation::from(player))])
+                .map_err(|error| error.message())
+        },
+        |session, draw_pile, language| {
+            let player = session.state.players.last().expect("The player who joined.");
+            let theme = request_theme(&req, draw_pile);
+            JoinRepresentation {
+                player: PlayerRepresentation::from(player),
+                token: player.token.clone(),
+                game: game::GameRepresentation::translated(session.id, &session.state, draw_pile, Some(&player.token), language, &theme),
+            }
+        },
+    ))
+}
+
+/// Issues a token for a game: a seated player may read the game and play, anybody else may only read it.
+pub async fn post_game_token(req: HttpRequest, query: web::Query<GameTokenRequest>) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let lifetime = match jwt::lifetime(query.expires_in) {
+        Some(lifetime) => lifetime,
+        None => return Ok(Failure::incorrect("expires_in").respond(language))
+    };
+
+    let session_id = req.match_info().get("id").map(Uuid::parse_str);
+    match session_id {
+        Some(Ok(session_id)) => {
+            let mut connection = establish_connection();
+            let session = Session::<game::Game>::find_by_id(session_id, game::SESSION_KIND, &mut connection);
+            connection.close().ok();
+
+            match session {
+                Some(session) => {
+                    let claims = match player_token(&req) {
+                        Some(token) => match session.state.player(token) {
+                            Some(player) => Claims::new(player.id, None, Some(session.id), vec![Scope::Read, Scope::Draw], lifetime),
+                            None => return Ok(Failure::from(Message::InvalidToken).parameter(PLAYER_TOKEN).respond(language))
+                        },
+                        None => Claims::new(Uuid::new_v4(), None, Some(session.id), vec![Scope::Read], lifetime)
+                    };
+
+                    Ok(HttpResponse::Created().json(TokenRepresentation::issue(claims)))
+                }
+                None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::SessionNotFound).respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn take_game_cards(req: HttpRequest, query: web::Query<GameTakeRequest>) -> Result<HttpResponse> {
+    Ok(command_game(&req, Command::Take { length: query.length }))
+}
+
+pub async fn end_game_turn(req: HttpRequest) -> Result<HttpResponse> {
+    Ok(command_game(&req, Command::EndTurn))
+}
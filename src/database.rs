@@ -1,10 +1,52 @@
 use std::env;
+use std::fmt;
 use postgres::{Client, NoTls};
+use crate::metrics;
+
+#[derive(Debug)]
+pub enum ConnectionError {
+    MissingUrl,
+    Unreachable(String, postgres::Error),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::MissingUrl => write!(f, "DATABASE_URL must be set"),
+            ConnectionError::Unreachable(database_url, _) => write!(f, "Error connecting to {}", database_url),
+        }
+    }
+}
 
 pub fn establish_connection() -> Client {
+    try_establish_connection().unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Connects to the database, telling why it failed rather than panicking.
+pub fn try_establish_connection() -> Result<Client, ConnectionError> {
     let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
+        .map_err(|_| ConnectionError::MissingUrl)?;
+
+    match Client::connect(&database_url, NoTls) {
+        Ok(client) => {
+            metrics::DB_CONNECTIONS_OPENED.inc(&[]);
+            tracing::debug!("database connection opened");
+            Ok(client)
+        }
+        Err(error) => {
+            metrics::DB_CONNECTION_ERRORS.inc(&[]);
+            tracing::error!(error = %error, "database connection failed");
+            Err(ConnectionError::Unreachable(database_url, error))
+        }
+    }
+}
+
+/// Reads how many connections are open on the database and how many it accepts, for the metrics.
+pub fn connection_usage(connection: &mut Client) -> Result<(i64, i64), postgres::Error> {
+    let open: i64 = metrics::time_query("count_connections", || {
+        connection.query_one("SELECT COUNT(*) FROM pg_stat_activity WHERE datname = current_database()", &[])
+    })?.get(0);
+    let max: String = connection.query_one("SHOW max_connections", &[])?.get(0);
 
-    Client::connect(&database_url, NoTls)
-        .expect(&format!("Error connecting to {}", database_url))
-}
\ No newline at end of file
+    Ok((open, max.parse().unwrap_or_default()))
+}
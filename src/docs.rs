@@ -0,0 +1,390 @@
+use std::sync::OnceLock;
+use actix_web::{HttpResponse, Result};
+use serde_json::Value;
+use jeu_de_carte::DeckRepresentation;
+use jeu_de_carte::api_key::ApiKeyRepresentation;
+use jeu_de_carte::batch::{BatchRepresentation, BatchRequest};
+use jeu_de_carte::belote;
+use jeu_de_carte::blackjack::TableRepresentation;
+use jeu_de_carte::game::{self, JoinRepresentation};
+use jeu_de_carte::health::{Liveness, Readiness};
+use jeu_de_carte::jwt::TokenRepresentation;
+use jeu_de_carte::odds::Odds;
+use jeu_de_carte::openapi::{ApiDoc, Operation};
+use jeu_de_carte::poker::EvaluationRepresentation;
+use jeu_de_carte::webhook::{DeadLetter, WebhookRepresentation};
+use crate::controller::*;
+use crate::socket::SocketRequest;
+use crate::v2::{DealBody, DeckBody, MoveBody, ShuffleBody, SwitchBody};
+
+/// A page showing the OpenAPI document with Redoc.
+const DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Card Game API</title>
+    <meta charset="utf-8"/>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+/// Describes every route of the API, the schemas being generated from the types of the handlers.
+pub fn document() -> &'static Value {
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    DOCUMENT.get_or_init(|| {
+        let mut doc = ApiDoc::new("Card Game API", env!("CARGO_PKG_VERSION"));
+
+        doc.add("post", "/v1/cards", Operation::new("post_cards", "Creates a deck, owned by the API key of the request if any.")
+            .query::<DeckSizeRequest>()
+            .response::<DeckRepresentation>(201, "The new deck.")
+            .error(422, "Unknown theme.")
+            .error(429, "Too many requests, or too many decks owned by the API key."));
+        doc.add("get", "/v1/cards/{id}", Operation::new("get_cards", "Gets a deck.")
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The deck.")
+            .error(403, "The token doesn't allow reading the deck.")
+            .error(404, "Deck not found."));
+        doc.add("delete", "/v1/cards/{id}", Operation::new("delete_cards", "Deletes a deck.")
+            .empty(204, "The deck was deleted.")
+            .error(401, "The deck has an owner and the request no credentials.")
+            .error(403, "The credentials don't allow deleting the deck.")
+            .error(404, "Deck not found."));
+        doc.add("post", "/v1/cards/{id}/shuffle", Operation::new("shuffle_cards", "Shuffles the cards of a deck.")
+            .query::<DeckShuffleRequest>()
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The shuffled deck.")
+            .error(403, "The credentials don't allow shuffling the deck.")
+            .error(404, "Deck not found."));
+        doc.add("post", "/v1/cards/{id}/take", Operation::new("take_cards", "Takes cards from the deck to the discarded cards.")
+            .query::<DeckSwitch>()
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The deck, or only the moved cards with `only_moved`, or the card codes with `compact`.")
+            .error(403, "The credentials don't allow drawing from the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Not enough cards."));
+        doc.add("post", "/v1/cards/{id}/put", Operation::new("put_cards", "Puts discarded cards back into the deck.")
+            .query::<DeckSwitch>()
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The deck, or only the moved cards with `only_moved`, or the card codes with `compact`.")
+            .error(403, "The credentials don't allow putting cards into the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Not enough cards."));
+        doc.add("post", "/v1/cards/{id}/move", Operation::new("move_cards", "Moves given cards between the deck and the discarded cards.")
+            .query::<DeckMoveRequest>()
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The deck.")
+            .error(403, "The credentials don't allow moving the cards.")
+            .error(404, "Deck not found.")
+            .error(422, "Card not in the stack."));
+        doc.add("post", "/v1/cards/{id}/batch", Operation::new("batch_cards", "Applies several operations to a deck at once, none being applied when one fails.")
+            .query::<RepresentationRequest>()
+            .body::<BatchRequest>()
+            .response::<BatchRepresentation>(200, "The deck and the result of each operation.")
+            .error(403, "The credentials don't allow one of the operations.")
+            .error(404, "Deck not found.")
+            .error(422, "An operation failed."));
+        doc.add("get", "/v1/cards/{id}/socket", Operation::new("deck_socket", "Opens a WebSocket pushing the events of a deck and running batch operations.")
+            .query::<SocketRequest>()
+            .empty(101, "The socket is open.")
+            .error(404, "Deck not found."));
+        doc.add("get", "/v1/cards/{id}/events/stream", Operation::new("stream_deck_events", "Streams the events of a deck as Server-Sent Events, resuming after the `Last-Event-ID` header.")
+            .media(200, "The stream of events.", "text/event-stream")
+            .error(404, "Deck not found."));
+        doc.add("post", "/v1/cards/{id}/tokens", Operation::new("post_deck_token", "Issues a signed token with scopes for a deck.")
+            .body::<TokenRequest>()
+            .response::<TokenRepresentation>(201, "The token and its claims.")
+            .error(403, "The credentials don't allow administrating the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "No scope or incorrect lifetime."));
+        doc.add("post", "/v1/cards/{id}/grants", Operation::new("grant_deck", "Lets another API key change a deck.")
+            .body::<GrantRequest>()
+            .empty(204, "The access was granted.")
+            .error(403, "Only the owner of the deck may grant access to it.")
+            .error(404, "Deck or API key not found."));
+        doc.add("delete", "/v1/cards/{id}/grants/{api_key_id}", Operation::new("revoke_deck", "Revokes the access of an API key to a deck.")
+            .empty(204, "The access was revoked.")
+            .error(403, "Only the owner of the deck may revoke access to it.")
+            .error(404, "Deck or API key not found."));
+        doc.add("post", "/v1/admin/api_keys", Operation::new("post_api_key", "Creates an API key, with the `X-Admin-Token` header.")
+            .body::<ApiKeyRequest>()
+            .response::<ApiKeyRepresentation>(201, "The API key, along with the clear key shown only once.")
+            .error(403, "Missing or wrong administration token."));
+        doc.add("delete", "/v1/admin/api_keys/{id}", Operation::new("delete_api_key", "Revokes an API key, with the `X-Admin-Token` header.")
+            .empty(204, "The API key was revoked.")
+            .error(403, "Missing or wrong administration token.")
+            .error(404, "API key not found."));
+        doc.add("post", "/v1/webhooks", Operation::new("post_webhook", "Subscribes a URL to the events of a deck or of every deck.")
+            .body::<WebhookRequest>()
+            .response::<WebhookRepresentation>(201, "The webhook, along with the secret signing its payloads.")
+            .error(401, "Missing API key.")
+            .error(403, "The API key has no access to the deck, or the webhook follows every deck without the `X-Admin-Token` header.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid URL, or its host isn't public."));
+        doc.add("get", "/v1/webhooks/{id}", Operation::new("get_webhook", "Gets a webhook.")
+            .response::<WebhookRepresentation>(200, "The webhook.")
+            .error(401, "Missing API key.")
+            .error(403, "Only the API key which created the webhook, or the admin, may see it.")
+            .error(404, "Webhook not found."));
+        doc.add("delete", "/v1/webhooks/{id}", Operation::new("delete_webhook", "Deletes a webhook.")
+            .empty(204, "The webhook was deleted.")
+            .error(401, "Missing API key.")
+            .error(403, "Only the API key which created the webhook, or the admin, may delete it.")
+            .error(404, "Webhook not found."));
+        doc.add("get", "/v1/webhooks/{id}/dead_letters", Operation::new("get_webhook_dead_letters", "Lists the deliveries of a webhook which failed after every retry.")
+            .response::<Vec<DeadLetter>>(200, "The failed deliveries.")
+            .error(401, "Missing API key.")
+            .error(403, "Only the API key which created the webhook, or the admin, may see them.")
+            .error(404, "Webhook not found."));
+        doc.add("get", "/v1/assets/cards/{code}.svg", Operation::new("get_card_image", "Renders a card, or its back with `back`.")
+            .media(200, "The image of the card.", "image/svg+xml")
+            .error(404, "Unknown card."));
+        doc.add("post", "/v1/evaluate/poker", Operation::new("evaluate_poker", "Ranks poker hands.")
+            .query::<RepresentationRequest>()
+            .body::<PokerEvaluationRequest>()
+            .response::<EvaluationRepresentation>(200, "The hands from the best to the worst.")
+            .error(422, "Invalid hand."));
+        doc.add("post", "/v1/odds/holdem", Operation::new("holdem_odds", "Computes the odds of Texas hold'em hole cards.")
+            .body::<HoldemOddsRequest>()
+            .response::<Odds>(200, "The odds of winning, splitting and losing.")
+            .error(422, "Invalid cards or number of opponents."));
+        doc.add("post", "/v1/blackjack/tables", Operation::new("post_blackjack_table", "Opens a blackjack table.")
+            .query::<BlackjackTableRequest>()
+            .query::<RepresentationRequest>()
+            .response::<TableRepresentation>(201, "The table."));
+        doc.add("get", "/v1/blackjack/tables/{id}", Operation::new("get_blackjack_table", "Gets a blackjack table.")
+            .query::<RepresentationRequest>()
+            .response::<TableRepresentation>(200, "The table.")
+            .error(404, "Table not found."));
+        doc.add("post", "/v1/blackjack/tables/{id}/deal", Operation::new("deal_blackjack", "Bets and deals a round.")
+            .query::<BlackjackBetRequest>()
+            .query::<RepresentationRequest>()
+            .response::<TableRepresentation>(200, "The table.")
+            .error(404, "Table not found.")
+            .error(409, "A round is already being played.")
+            .error(422, "Invalid or odd bet."));
+        doc.add("post", "/v1/blackjack/tables/{id}/insurance", Operation::new("insure_blackjack", "Takes or declines the insurance.")
+            .query::<BlackjackInsuranceRequest>()
+            .query::<RepresentationRequest>()
+            .response::<TableRepresentation>(200, "The table.")
+            .error(404, "Table not found.")
+            .error(409, "Illegal action."));
+        doc.add("post", "/v1/blackjack/tables/{id}/{action}", Operation::new("play_blackjack", "Plays `hit`, `stand`, `double`, `split` or `surrender`.")
+            .query::<RepresentationRequest>()
+            .response::<TableRepresentation>(200, "The table.")
+            .error(404, "Table not found.")
+            .error(409, "Illegal action."));
+        doc.add("post", "/v1/belote/games", Operation::new("post_belote_game", "Starts a belote game, answering the tokens of the four seats.")
+            .query::<BeloteGameRequest>()
+            .query::<RepresentationRequest>()
+            .response::<belote::GameRepresentation>(201, "The game, with the tokens of the seats."));
+        doc.add("get", "/v1/belote/games/{id}", Operation::new("get_belote_game", "Gets a belote game, with the hand of the seat of the `X-Player-Token` header.")
+            .query::<RepresentationRequest>()
+            .response::<belote::GameRepresentation>(200, "The game.")
+            .error(403, "The token isn't allowed to read this game.")
+            .error(404, "Game not found."));
+        doc.add("post", "/v1/belote/games/{id}/deal", Operation::new("deal_belote", "Deals a round, for a seated player.")
+            .query::<RepresentationRequest>()
+            .response::<belote::GameRepresentation>(200, "The game.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found.")
+            .error(409, "Illegal action."));
+        doc.add("post", "/v1/belote/games/{id}/bid", Operation::new("bid_belote", "Bids for the seat of the `X-Player-Token` header.")
+            .query::<RepresentationRequest>()
+            .body::<BeloteBidRequest>()
+            .response::<belote::GameRepresentation>(200, "The game.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found.")
+            .error(409, "Illegal bid."));
+        doc.add("post", "/v1/belote/games/{id}/play", Operation::new("play_belote", "Plays a card for the seat of the `X-Player-Token` header.")
+            .query::<RepresentationRequest>()
+            .body::<BelotePlayRequest>()
+            .response::<belote::GameRepresentation>(200, "The game.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found.")
+            .error(409, "Illegal card.")
+            .error(422, "Invalid card or card not in the hand."));
+        doc.add("post", "/v1/belote/games/{id}/tokens", Operation::new("post_belote_token", "Issues a signed token for a belote game, to a seated player with `X-Player-Token` or to a spectator.")
+            .query::<GameTokenRequest>()
+            .response::<TokenRepresentation>(201, "The token and its claims.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found."));
+        doc.add("post", "/v1/games", Operation::new("post_game", "Creates a game with a draw pile and seats for the players.")
+            .query::<GameRequest>()
+            .query::<RepresentationRequest>()
+            .response::<game::GameRepresentation>(201, "The game.")
+            .error(422, "Unknown theme."));
+        doc.add("get", "/v1/games/{id}", Operation::new("get_game", "Gets a game, with the hand of the player of the request.")
+            .query::<RepresentationRequest>()
+            .response::<game::GameRepresentation>(200, "The game.")
+            .error(403, "The token doesn't allow reading the game.")
+            .error(404, "Game not found."));
+        doc.add("post", "/v1/games/{id}/join", Operation::new("join_game", "Takes a seat in a game.")
+            .query::<RepresentationRequest>()
+            .body::<JoinRequest>()
+            .response::<JoinRepresentation>(200, "The player, their token and the game.")
+            .error(404, "Game not found.")
+            .error(409, "The game is full."));
+        doc.add("post", "/v1/games/{id}/tokens", Operation::new("post_game_token", "Issues a signed token for a game, to a seated player with `X-Player-Token` or to a spectator.")
+            .query::<GameTokenRequest>()
+            .response::<TokenRepresentation>(201, "The token and its claims.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found."));
+        doc.add("post", "/v1/games/{id}/take", Operation::new("take_game_cards", "Takes cards from the draw pile into the hand of the current player.")
+            .query::<GameTakeRequest>()
+            .query::<RepresentationRequest>()
+            .response::<game::GameRepresentation>(200, "The game.")
+            .error(403, "The token doesn't allow drawing or isn't the one of a player.")
+            .error(404, "Game not found.")
+            .error(409, "Not your turn."));
+        doc.add("post", "/v1/games/{id}/end_turn", Operation::new("end_game_turn", "Ends the turn of the current player.")
+            .query::<RepresentationRequest>()
+            .response::<game::GameRepresentation>(200, "The game.")
+            .error(403, "The token doesn't allow playing or isn't the one of a player.")
+            .error(404, "Game not found.")
+            .error(409, "Not your turn."));
+        doc.add("get", "/v1/games/{id}/socket", Operation::new("game_socket", "Opens a WebSocket pushing the events of a game and running the commands of its player.")
+            .query::<SocketRequest>()
+            .empty(101, "The socket is open.")
+            .error(401, "Invalid signed token.")
+            .error(403, "Invalid player token.")
+            .error(404, "Game not found."));
+        doc.add("post", "/v2/decks", Operation::new("v2_post_deck", "Creates a deck, owned by the API key of the request if any.")
+            .body::<DeckBody>()
+            .response::<DeckRepresentation>(201, "The new deck.")
+            .error(422, "Invalid body or unknown theme.")
+            .error(429, "Too many requests, or too many decks owned by the API key."));
+        doc.add("get", "/v2/decks/{id}", Operation::new("v2_get_deck", "Gets a deck.")
+            .query::<RepresentationRequest>()
+            .response::<DeckRepresentation>(200, "The deck.")
+            .error(403, "The token doesn't allow reading the deck.")
+            .error(404, "Deck not found."));
+        doc.add("delete", "/v2/decks/{id}", Operation::new("v2_delete_deck", "Deletes a deck.")
+            .empty(204, "The deck was deleted.")
+            .error(401, "The deck has an owner and the request no credentials.")
+            .error(403, "The credentials don't allow deleting the deck.")
+            .error(404, "Deck not found."));
+        doc.add("post", "/v2/decks/{id}/shuffle", Operation::new("v2_shuffle_deck", "Shuffles the cards of a deck.")
+            .query::<RepresentationRequest>()
+            .body::<ShuffleBody>()
+            .response::<BatchRepresentation>(200, "The shuffled deck.")
+            .error(403, "The credentials don't allow shuffling the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body."));
+        doc.add("post", "/v2/decks/{id}/take", Operation::new("v2_take_cards", "Takes cards from the deck to the discarded cards.")
+            .query::<RepresentationRequest>()
+            .body::<SwitchBody>()
+            .response::<BatchRepresentation>(200, "The deck and the cards taken.")
+            .error(403, "The credentials don't allow drawing from the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body or not enough cards."));
+        doc.add("post", "/v2/decks/{id}/put", Operation::new("v2_put_cards", "Puts discarded cards back into the deck.")
+            .query::<RepresentationRequest>()
+            .body::<SwitchBody>()
+            .response::<BatchRepresentation>(200, "The deck and the cards put back.")
+            .error(403, "The credentials don't allow putting cards into the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body or not enough cards."));
+        doc.add("post", "/v2/decks/{id}/move", Operation::new("v2_move_cards", "Moves given cards between the deck and the discarded cards.")
+            .query::<RepresentationRequest>()
+            .body::<MoveBody>()
+            .response::<BatchRepresentation>(200, "The deck and the cards moved.")
+            .error(403, "The credentials don't allow moving the cards.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body or card not in the stack."));
+        doc.add("post", "/v2/decks/{id}/deal", Operation::new("v2_deal_cards", "Deals hands from the deck to the discarded cards.")
+            .query::<RepresentationRequest>()
+            .body::<DealBody>()
+            .response::<BatchRepresentation>(200, "The deck and the hands dealt.")
+            .error(403, "The credentials don't allow drawing from the deck.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body or not enough cards."));
+        doc.add("post", "/v2/decks/{id}/operations", Operation::new("v2_apply_operations", "Applies several operations to a deck at once, none being applied when one fails.")
+            .query::<RepresentationRequest>()
+            .body::<BatchRequest>()
+            .response::<BatchRepresentation>(200, "The deck and the result of each operation.")
+            .error(403, "The credentials don't allow one of the operations.")
+            .error(404, "Deck not found.")
+            .error(422, "Invalid body or an operation failed."));
+        doc.add("get", "/openapi.json", Operation::new("get_openapi", "Gets this document.")
+            .media(200, "The OpenAPI document.", "application/json"));
+        doc.add("get", "/docs", Operation::new("get_docs", "Shows this document.")
+            .media(200, "The documentation page.", "text/html"));
+        doc.add("get", "/metrics", Operation::new("get_metrics", "Gets the metrics of the API in the text format of Prometheus.")
+            .media(200, "The metrics.", "text/plain"));
+        doc.add("get", "/health/live", Operation::new("get_liveness", "Tells that the process is up.")
+            .response::<Liveness>(200, "The process is up."));
+        doc.add("get", "/health/ready", Operation::new("get_readiness", "Tells if the database is reachable and its schema up to date.")
+            .response::<Readiness>(200, "The API is ready.")
+            .response::<Readiness>(503, "The database is down or its schema needs to be upgraded."));
+
+        doc.to_json()
+    })
+}
+
+pub async fn get_openapi() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(document()))
+}
+
+pub async fn get_docs() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().content_type("text/html; charset=utf-8").body(DOCS_PAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use super::document;
+
+    /// Lists the routes registered by `main.rs`, as (method, path), the routes of each version being under its prefix.
+    fn declared_routes() -> BTreeSet<(String, String)> {
+        let versioned = |prefix: &'static str, routes: &'static [(&str, &str)]| {
+            routes.iter().map(move |(method, path)| (method.to_string(), format!("{}{}", prefix, path)))
+        };
+        versioned("/v1", crate::V1_ROUTES)
+            .chain(versioned("/v2", crate::V2_ROUTES))
+            .chain(versioned("", crate::ROOT_ROUTES))
+            .collect()
+    }
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        document()["paths"]
+            .as_object()
+            .expect("The paths of the document.")
+            .iter()
+            .flat_map(|(path, item)| item.as_object().expect("A path item.").keys().map(move |method| (method.clone(), path.clone())))
+            .collect()
+    }
+
+    #[test]
+    fn test_openapi_matches_routes() {
+        let declared = declared_routes();
+        assert!(!declared.is_empty());
+        assert_eq!(declared, documented_routes());
+    }
+
+    #[test]
+    fn test_openapi_errors_are_problems() {
+        for (path, item) in document()["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                for (status, response) in operation["responses"].as_object().unwrap() {
+                    if status.starts_with('4') {
+                        assert!(response["content"]["application/problem+json"].is_object(), "{} {} {}", method, path, status);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_openapi_references() {
+        let document = serde_json::to_string(document()).unwrap();
+        let schemas = &super::document()["components"]["schemas"];
+        for reference in document.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = reference.split('"').next().unwrap();
+            assert!(schemas.get(name).is_some(), "Missing schema {}", name);
+        }
+    }
+}
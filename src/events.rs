@@ -0,0 +1,323 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::rt::time::timeout;
+use actix_web::web::Bytes;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::stream::{LocalBoxStream, StreamExt};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::{CompactDeckRepresentation, Deck};
+use crate::batch::StepResult;
+
+/// Number of events kept for each topic, to send the missed ones to the clients reconnecting.
+pub const HISTORY_SIZE: usize = 100;
+/// Number of topics whose history is kept, the least recently updated one being forgotten beyond it.
+pub const MAX_TOPICS: usize = 1000;
+/// How long a stream of Server-Sent Events stays silent before a comment is sent, so that the proxies keep it open.
+pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// How long the clients wait before reconnecting to a closed stream, in milliseconds.
+pub const RETRY: u64 = 3000;
+/// The comment sent on a silent stream.
+pub const PING: &str = ": ping\n\n";
+
+/// The id of the first event published since the process started, growing from one start to the next
+/// so that a client resuming after a restart never skips the new events.
+///
+/// It is the number of milliseconds since the epoch, shifted to leave room for 1024 events per millisecond,
+/// and stays below 2^53 for the JavaScript clients.
+fn first_event_id() -> u64 {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since_epoch.as_millis() as u64) << 10
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Shuffled,
+    Taken,
+    Put,
+    Moved,
+    Batch,
+    Deleted,
+    Joined,
+    /// The cards taken by a player, only sent to them.
+    Hand,
+    TurnEnded,
+}
+
+/// Who may receive an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Audience {
+    Everyone,
+    Player(Uuid),
+}
+
+/// A change of a deck or a game, pushed to the clients listening to it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    /// Increasing id of the event, unique within the hub.
+    pub id: u64,
+    /// The id of the deck or of the game the event is about.
+    pub topic: Uuid,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    #[serde(skip)]
+    pub audience: Audience,
+    pub data: serde_json::Value,
+}
+
+impl Event {
+    /// Tells if an event can be seen by a player, or by a spectator when None.
+    pub fn is_visible_to(&self, player: Option<Uuid>) -> bool {
+        match self.audience {
+            Audience::Everyone => true,
+            Audience::Player(id) => player == Some(id),
+        }
+    }
+
+    /// Formats the event as a Server-Sent Event, the client sending back its id as `Last-Event-ID`.
+    pub fn to_sse(&self) -> String {
+        let kind = serde_json::to_value(self.kind).expect("An error occurred.");
+        let data = serde_json::to_string(self).expect("An error occurred.");
+        format!("id: {}\nevent: {}\ndata: {}\n\n", self.id, kind.as_str().unwrap_or_default(), data)
+    }
+}
+
+/// An event to publish once the change is saved, the hub giving it its topic and its id.
+#[derive(Debug)]
+pub struct Notice {
+    pub kind: EventKind,
+    pub audience: Audience,
+    pub data: serde_json::Value,
+}
+
+impl Notice {
+    pub fn new<D: Serialize>(kind: EventKind, audience: Audience, data: D) -> Notice {
+        Notice { kind, audience, data: serde_json::to_value(data).expect("An error occurred.") }
+    }
+}
+
+struct Subscriber {
+    topic: Uuid,
+    player: Option<Uuid>,
+    sender: UnboundedSender<Event>,
+}
+
+struct HubState {
+    next_id: u64,
+    subscribers: Vec<Subscriber>,
+    history: HashMap<Uuid, VecDeque<Event>>,
+    max_topics: usize,
+}
+
+impl HubState {
+    /// Forgets the history of the least recently updated topics, until at most `max_topics` are kept.
+    fn evict_topics(&mut self) {
+        while self.history.len() > self.max_topics {
+            let oldest = self.history.iter()
+                .min_by_key(|(_, events)| events.back().map(|event| event.id).unwrap_or_default())
+                .map(|(topic, _)| *topic);
+            match oldest {
+                Some(topic) => self.history.remove(&topic),
+                None => break
+            };
+        }
+    }
+}
+
+/// Dispatches the events of the decks and games to their subscribers.
+pub struct EventHub {
+    state: Mutex<HubState>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        EventHub::new()
+    }
+}
+
+impl EventHub {
+    pub fn new() -> EventHub {
+        EventHub::with_max_topics(MAX_TOPICS)
+    }
+
+    /// Creates a hub keeping the history of at most `max_topics` topics, the least recently updated ones being forgotten.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::events::{Audience, EventHub, EventKind};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let hub = EventHub::with_max_topics(1);
+    ///     let (first_deck, second_deck) = (Uuid::new_v4(), Uuid::new_v4());
+    ///     hub.publish(first_deck, EventKind::Shuffled, Audience::Everyone, ());
+    ///     hub.publish(second_deck, EventKind::Shuffled, Audience::Everyone, ());
+    ///     assert!(hub.resume(first_deck, None, Some(0)).0.is_empty());
+    ///     assert_eq!(hub.resume(second_deck, None, Some(0)).0.len(), 1);
+    /// }
+    /// ```
+    pub fn with_max_topics(max_topics: usize) -> EventHub {
+        EventHub { state: Mutex::new(HubState { next_id: first_event_id(), subscribers: Vec::new(), history: HashMap::new(), max_topics }) }
+    }
+
+    /// Listens to the events of a topic visible to a player, or to a spectator when None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::events::{Audience, EventHub, EventKind};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let hub = EventHub::new();
+    ///     let deck_id = Uuid::new_v4();
+    ///     let mut events = hub.subscribe(deck_id, None);
+    ///     hub.publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+    ///     let event = events.try_recv().unwrap();
+    ///     assert_eq!(event.kind, EventKind::Shuffled);
+    /// }
+    /// ```
+    pub fn subscribe(&self, topic: Uuid, player: Option<Uuid>) -> UnboundedReceiver<Event> {
+        self.resume(topic, player, None).1
+    }
+
+    /// Listens to the events of a topic, after the events published since the last event received by the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic`: The id of the deck or of the game.
+    /// * `player`: The player allowed to see their own events, None for a spectator.
+    /// * `last_event_id`: The id of the last event received, None for a new client.
+    ///
+    /// returns: (Vec<Event>, UnboundedReceiver<Event>), the missed events still kept, then the next ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::events::{Audience, EventHub, EventKind};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let hub = EventHub::new();
+    ///     let deck_id = Uuid::new_v4();
+    ///     let first = hub.publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+    ///     let second = hub.publish(deck_id, EventKind::Taken, Audience::Everyone, ());
+    ///     let (missed, _) = hub.resume(deck_id, None, Some(first.id));
+    ///     assert_eq!(missed.len(), 1);
+    ///     assert_eq!(missed[0].id, second.id);
+    /// }
+    /// ```
+    pub fn resume(&self, topic: Uuid, player: Option<Uuid>, last_event_id: Option<u64>) -> (Vec<Event>, UnboundedReceiver<Event>) {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut state = self.state.lock().expect("An error occurred.");
+        let missed = match (last_event_id, state.history.get(&topic)) {
+            (Some(last_event_id), Some(history)) => history.iter()
+                .filter(|event| event.id > last_event_id && event.is_visible_to(player))
+                .cloned()
+                .collect(),
+            _ => Vec::new()
+        };
+        state.subscribers.push(Subscriber { topic, player, sender });
+        (missed, receiver)
+    }
+
+    /// Sends an event to the subscribers of its topic allowed to see it, forgetting the disconnected ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic`: The id of the deck or of the game.
+    /// * `kind`: The kind of change.
+    /// * `audience`: Who may receive the event.
+    /// * `data`: The content of the event.
+    ///
+    /// returns: Event, the published event along with its id.
+    pub fn publish<D: Serialize>(&self, topic: Uuid, kind: EventKind, audience: Audience, data: D) -> Event {
+        self.notify(topic, Notice::new(kind, audience, data))
+    }
+
+    /// Publishes a notice on a topic.
+    pub fn notify(&self, topic: Uuid, notice: Notice) -> Event {
+        let mut state = self.state.lock().expect("An error occurred.");
+        let event = Event {
+            id: state.next_id,
+            topic,
+            kind: notice.kind,
+            audience: notice.audience,
+            data: notice.data,
+        };
+        state.next_id += 1;
+        let history = state.history.entry(topic).or_default();
+        if history.len() == HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(event.clone());
+        state.evict_topics();
+        state.subscribers.retain(|subscriber| {
+            if subscriber.topic != event.topic || !event.is_visible_to(subscriber.player) {
+                return !subscriber.sender.is_closed();
+            }
+            subscriber.sender.unbounded_send(event.clone()).is_ok()
+        });
+        event
+    }
+
+    /// Ends the streams of every subscriber of a topic, once the deck or the game is deleted.
+    pub fn close(&self, topic: Uuid) {
+        let mut state = self.state.lock().expect("An error occurred.");
+        state.subscribers.retain(|subscriber| subscriber.topic != topic);
+        state.history.remove(&topic);
+    }
+}
+
+/// Formats the events of a topic as Server-Sent Events: the reconnection delay, the missed events, then the next ones,
+/// a `: ping` comment being sent whenever no event comes for `keep_alive`.
+///
+/// The stream ends once the topic is closed.
+pub fn sse_stream(missed: Vec<Event>, receiver: UnboundedReceiver<Event>, keep_alive: Duration) -> LocalBoxStream<'static, Bytes> {
+    let live = futures::stream::unfold(receiver, move |mut receiver| async move {
+        match timeout(keep_alive, receiver.next()).await {
+            Ok(Some(event)) => Some((Bytes::from(event.to_sse()), receiver)),
+            Ok(None) => None,
+            Err(_) => Some((Bytes::from_static(PING.as_bytes()), receiver)),
+        }
+    });
+
+    futures::stream::once(async { Bytes::from(format!("retry: {}\n\n", RETRY)) })
+        .chain(futures::stream::iter(missed).map(|event| Bytes::from(event.to_sse())))
+        .chain(live)
+        .boxed_local()
+}
+
+/// The new size of both stacks of a deck, along with the cards moved or dealt by the change.
+///
+/// The order of the cards left in the deck is never sent, so a shuffle reveals nothing.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct DeckChange {
+    pub cards_count: usize,
+    pub discarded_count: usize,
+    pub moved: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dealt: Vec<Vec<String>>,
+}
+
+impl DeckChange {
+    pub fn new(deck: &Deck, results: &[StepResult]) -> DeckChange {
+        let mut change = DeckChange {
+            cards_count: deck.cards.len(),
+            discarded_count: deck.discarded.len(),
+            moved: Vec::new(),
+            dealt: Vec::new(),
+        };
+        for result in results {
+            match result {
+                StepResult::Shuffled => (),
+                StepResult::Moved(cards) => change.moved.extend(CompactDeckRepresentation::translate_cards(cards)),
+                StepResult::Dealt(hands) => change.dealt.extend(hands.iter().map(|hand| CompactDeckRepresentation::translate_cards(hand))),
+            }
+        }
+        change
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::{Card, CardRepresentation, Deck, DeckRepresentation, MovedCardsRepresentation};
+use crate::events::{Audience, EventKind, Notice};
+use crate::i18n::{Language, Message};
+use crate::theme::Theme;
+
+pub const SESSION_KIND: &str = "game";
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GameError {
+    GameFull,
+    InvalidToken,
+    NotYourTurn,
+    IllegalAction,
+}
+
+impl GameError {
+    pub fn message(&self) -> Message {
+        match self {
+            GameError::GameFull => Message::GameFull,
+            GameError::InvalidToken => Message::InvalidToken,
+            GameError::NotYourTurn => Message::NotYourTurn,
+            GameError::IllegalAction => Message::IllegalAction,
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Players can join until every seat is taken.
+    Waiting,
+    Playing,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Player {
+    pub id: Uuid,
+    pub name: String,
+    pub seat: usize,
+    /// The secret identifying the player, only given to them when joining.
+    pub token: String,
+    pub hand: Vec<Card>,
+}
+
+/// A command sent by a player, over the game socket or the HTTP endpoints.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    Take {
+        length: Option<usize>,
+    },
+    EndTurn,
+}
+
+/// A game played by seated players taking turns, around a draw pile.
+///
+/// Only the player whose turn it is may take from the draw pile.
+/// The cards taken are put into the discarded stack of the deck and into the hand of the player.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Game {
+    /// The decks of the game, only holding the draw pile.
+    pub decks: Vec<Uuid>,
+    pub seats: usize,
+    pub status: Status,
+    pub players: Vec<Player>,
+    pub turn: usize,
+}
+
+impl Game {
+    pub fn new(draw_pile: Uuid, seats: usize) -> Game {
+        Game { decks: vec![draw_pile], seats, status: Status::Waiting, players: Vec::new(), turn: 0 }
+    }
+
+    /// Seats a new player, the game starting once every seat is taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::game::{Game, Status};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let mut game = Game::new(Uuid::new_v4(), 2);
+    ///     game.join("Alice").unwrap();
+    ///     let bob = game.join("Bob").unwrap();
+    ///     assert_eq!(bob.seat, 1);
+    ///     assert_eq!(game.status, Status::Playing);
+    ///     assert!(game.join("Carol").is_err());
+    /// }
+    /// ```
+    pub fn join(&mut self, name: &str) -> Result<&Player, GameError> {
+        if self.status != Status::Waiting || self.players.len() >= self.seats { return Err(GameError::GameFull); }
+
+        let seat = self.players.len();
+        self.players.push(Player {
+            id: Uuid::new_v4(),
+            name: name.trim().to_string(),
+            seat,
+            token: Uuid::new_v4().to_simple().to_string(),
+            hand: Vec::new(),
+        });
+        if self.players.len() == self.seats {
+            self.status = Status::Playing;
+        }
+
+        Ok(&self.players[seat])
+    }
+
+    pub fn player(&self, token: &str) -> Option<&Player> {
+        self.players.iter().find(|player| player.token == token)
+    }
+
+    /// Finds a player from their id, the subject of the tokens given to the players.
+    pub fn player_by_id(&self, id: Uuid) -> Option<&Player> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    /// Finds the player of a token, who must be the player whose turn it is.
+    fn current_player(&self, token: Option<&str>) -> Result<usize, GameError> {
+        if self.status != Status::Playing { return Err(GameError::IllegalAction); }
+        let player = token.and_then(|token| self.player(token)).ok_or(GameError::InvalidToken)?;
+        if player.seat != self.turn { return Err(GameError::NotYourTurn); }
+
+        Ok(player.seat)
+    }
+
+    /// Takes cards from the top of the draw pile into the hand of the current player.
+    pub fn take(&mut self, draw_pile: &mut Deck, token: Option<&str>, length: usize) -> Result<Vec<Card>, GameError> {
+        let seat = self.current_player(token)?;
+        let cards = draw_pile.take(true, length, false);
+        self.players[seat].hand.extend(cards.iter());
+
+        Ok(cards)
+    }
+
+    /// Ends the turn of the current player, the next seat playing.
+    pub fn end_turn(&mut self, token: Option<&str>) -> Result<(), GameError> {
+        self.current_player(token)?;
+        self.turn = (self.turn + 1) % self.players.len();
+
+        Ok(())
+    }
+
+    /// Runs a command for the player of a token.
+    ///
+    /// returns: Result<Vec<Notice>, GameError>, the events telling the change to the clients of the game.
+    /// The cards taken are only told to the player taking them, the others only learning how many.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::{Deck, DeckSize};
+    /// use jeu_de_carte::events::{Audience, EventKind};
+    /// use jeu_de_carte::game::{Command, Game};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let mut draw_pile = Deck::new(DeckSize::Normal);
+    ///     let mut game = Game::new(draw_pile.id, 2);
+    ///     let token = game.join("Alice").unwrap().token.clone();
+    ///     game.join("Bob").unwrap();
+    ///     let notices = game.run(&mut draw_pile, Some(&token), &Command::Take { length: Some(2) }).unwrap();
+    ///     assert_eq!(notices[0].kind, EventKind::Taken);
+    ///     assert_eq!(notices[1].audience, Audience::Player(game.players[0].id));
+    /// }
+    /// ```
+    pub fn run(&mut self, draw_pile: &mut Deck, token: Option<&str>, command: &Command) -> Result<Vec<Notice>, GameError> {
+        match command {
+            Command::Take { length } => {
+                let seat = self.turn;
+                let cards = self.take(draw_pile, token, length.unwrap_or(1))?;
+                Ok(vec![
+                    Notice::new(EventKind::Taken, Audience::Everyone, TakeNotice { seat, count: cards.len(), draw_pile: PileRepresentation::from(draw_pile) }),
+                    Notice::new(EventKind::Hand, Audience::Player(self.players[seat].id), MovedCardsRepresentation::compact(draw_pile, cards)),
+                ])
+            }
+            Command::EndTurn => {
+                self.end_turn(token)?;
+                Ok(vec![Notice::new(EventKind::TurnEnded, Audience::Everyone, TurnNotice { turn: self.turn })])
+            }
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PlayerRepresentation {
+    pub id: Uuid,
+    pub name: String,
+    pub seat: usize,
+    pub cards_count: usize,
+}
+
+impl PlayerRepresentation {
+    pub fn from(player: &Player) -> PlayerRepresentation {
+        PlayerRepresentation { id: player.id, name: player.name.clone(), seat: player.seat, cards_count: player.hand.len() }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PileRepresentation {
+    pub cards_count: usize,
+    pub discarded_count: usize,
+}
+
+impl PileRepresentation {
+    pub fn from(deck: &Deck) -> PileRepresentation {
+        PileRepresentation { cards_count: deck.cards.len(), discarded_count: deck.discarded.len() }
+    }
+}
+
+/// What every client of the game is told when a player takes cards.
+#[derive(Serialize)]
+pub struct TakeNotice {
+    pub seat: usize,
+    pub count: usize,
+    pub draw_pile: PileRepresentation,
+}
+
+#[derive(Serialize)]
+pub struct TurnNotice {
+    pub turn: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct GameRepresentation {
+    pub id: Uuid,
+    pub status: Status,
+    pub seats: usize,
+    pub turn: Option<usize>,
+    pub players: Vec<PlayerRepresentation>,
+    pub draw_pile: PileRepresentation,
+    /// The hand of the player identified by the request token only.
+    pub hand: Option<Vec<CardRepresentation>>,
+}
+
+impl GameRepresentation {
+    pub fn translated(id: Uuid, game: &Game, draw_pile: &Deck, token: Option<&str>, language: Language, theme: &Theme) -> GameRepresentation {
+        GameRepresentation {
+            id,
+            status: game.status,
+            seats: game.seats,
+            turn: if game.status == Status::Playing { Some(game.turn) } else { None },
+            players: game.players.iter().map(PlayerRepresentation::from).collect(),
+            draw_pile: PileRepresentation::from(draw_pile),
+            hand: token
+                .and_then(|token| game.player(token))
+                .map(|player| DeckRepresentation::translate_cards(player.hand.clone(), language, theme)),
+        }
+    }
+}
+
+/// The player who just joined, with the token they must send to play.
+#[derive(Serialize, JsonSchema)]
+pub struct JoinRepresentation {
+    pub player: PlayerRepresentation,
+    pub token: String,
+    pub game: GameRepresentation,
+}
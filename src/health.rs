@@ -0,0 +1,165 @@
+use std::time::Instant;
+use postgres::Client;
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::database::{self, ConnectionError};
+use crate::metrics;
+
+/// The tables of the database and their columns used by the API, including the ones added by the upgrades.
+pub const SCHEMA: &[(&str, &[&str])] = &[
+    ("decks", &["id", "cards", "discarded", "theme", "owner", "session"]),
+    ("sessions", &["id", "kind", "deck_id", "state", "updated_at"]),
+    ("webhooks", &["id", "deck_id", "url", "secret", "events", "owner"]),
+    ("webhook_dead_letters", &["id", "webhook_id", "event", "payload", "attempts", "error"]),
+    ("api_keys", &["id", "name", "key_hash"]),
+    ("deck_grants", &["deck_id", "api_key_id"]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaStatus {
+    UpToDate,
+    /// Some tables or columns are missing, the database needing to be upgraded.
+    Pending,
+    /// The schema couldn't be read, the database being down.
+    Unknown,
+}
+
+/// Tells that the process is up, whatever the state of the database.
+#[derive(Serialize, JsonSchema)]
+pub struct Liveness {
+    pub status: Status,
+    pub version: String,
+}
+
+impl Liveness {
+    pub fn new() -> Liveness {
+        Liveness { status: Status::Up, version: env!("CARGO_PKG_VERSION").to_string() }
+    }
+}
+
+impl Default for Liveness {
+    fn default() -> Liveness {
+        Liveness::new()
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DatabaseCheck {
+    pub status: Status,
+    /// The time taken to connect and to answer a query, in milliseconds.
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SchemaCheck {
+    pub status: SchemaStatus,
+    /// The missing tables and columns, as `table` or `table.column`.
+    pub missing: Vec<String>,
+}
+
+/// Tells if the API can serve requests, the database being reachable and its schema up to date.
+#[derive(Serialize, JsonSchema)]
+pub struct Readiness {
+    pub status: Status,
+    pub database: DatabaseCheck,
+    pub schema: SchemaCheck,
+}
+
+impl Readiness {
+    /// Connects to the database the way the handlers do, then checks its schema.
+    pub fn check() -> Readiness {
+        let start = Instant::now();
+        let mut connection = match database::try_establish_connection() {
+            Ok(connection) => connection,
+            Err(error) => return Readiness::down(connection_error(&error))
+        };
+        let columns = schema_columns(&mut connection);
+        let latency_ms = start.elapsed().as_millis() as u64;
+        connection.close().ok();
+
+        match columns {
+            Ok(columns) => {
+                let missing = missing_columns(&columns);
+                let status = if missing.is_empty() { SchemaStatus::UpToDate } else { SchemaStatus::Pending };
+                Readiness {
+                    status: if missing.is_empty() { Status::Up } else { Status::Down },
+                    database: DatabaseCheck { status: Status::Up, latency_ms: Some(latency_ms), error: None },
+                    schema: SchemaCheck { status, missing },
+                }
+            }
+            Err(error) => Readiness::down(error.to_string())
+        }
+    }
+
+    fn down(error: String) -> Readiness {
+        Readiness {
+            status: Status::Down,
+            database: DatabaseCheck { status: Status::Down, latency_ms: None, error: Some(error) },
+            schema: SchemaCheck { status: SchemaStatus::Unknown, missing: Vec::new() },
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == Status::Up
+    }
+}
+
+/// Tells why the connection failed, without the address of the database which may hold its password.
+fn connection_error(error: &ConnectionError) -> String {
+    match error {
+        ConnectionError::MissingUrl => error.to_string(),
+        ConnectionError::Unreachable(_, cause) => cause.to_string(),
+    }
+}
+
+/// Reads the columns of the tables of the current schema, as `(table, column)`.
+fn schema_columns(connection: &mut Client) -> Result<Vec<(String, String)>, postgres::Error> {
+    let rows = metrics::time_query("read_schema", || {
+        connection.query("SELECT table_name::text, column_name::text FROM information_schema.columns WHERE table_schema = current_schema()", &[])
+    })?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Lists the tables and columns of the `SCHEMA` missing from the columns of a database.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::health::{missing_columns, SCHEMA};
+///
+/// fn main() {
+///     let mut columns: Vec<(String, String)> = SCHEMA.iter()
+///         .flat_map(|(table, names)| names.iter().map(move |name| (table.to_string(), name.to_string())))
+///         .collect();
+///     assert!(missing_columns(&columns).is_empty());
+///
+///     columns.retain(|(table, column)| (table.as_str(), column.as_str()) != ("decks", "owner") && table != "deck_grants");
+///     assert_eq!(missing_columns(&columns), vec!["decks.owner", "deck_grants"]);
+/// }
+/// ```
+pub fn missing_columns(columns: &[(String, String)]) -> Vec<String> {
+    let mut missing = Vec::new();
+    for (table, names) in SCHEMA {
+        if !columns.iter().any(|(present, _)| present == table) {
+            missing.push(table.to_string());
+            continue;
+        }
+        for name in names.iter() {
+            if !columns.iter().any(|(present, column)| present == table && column == name) {
+                missing.push(format!("{}.{}", table, name));
+            }
+        }
+    }
+
+    missing
+}
@@ -0,0 +1,74 @@
+use crate::Card;
+use crate::card::{PlayingCard, Rank, Suit};
+use crate::trick::{follow_suit, to_card, Played, Round, TrickRules};
+
+/// Points needed to shoot the moon: every heart and the queen of spades.
+pub const MOON: i32 = 26;
+
+/// Hearts for 4 players, without passing: every heart costs 1 point and the queen of spades 13.
+pub struct Hearts;
+
+fn is_penalty(card: PlayingCard) -> bool {
+    card.suit() == Some(Suit::Hearts) || card == PlayingCard::new(Rank::Queen, Suit::Spades)
+}
+
+impl TrickRules for Hearts {
+    fn players(&self) -> usize {
+        4
+    }
+
+    fn hand_size(&self) -> usize {
+        13
+    }
+
+    /// The two of clubs leads the first trick.
+    fn first_leader(&self, round: &Round) -> usize {
+        let two = PlayingCard::new(Rank::Two, Suit::Clubs).motif();
+        (0..self.players()).find(|seat| round.hands[*seat].contains(&two)).unwrap_or((round.dealer + 1) % self.players())
+    }
+
+    /// Hearts cannot be led until one was played, and the first trick cannot carry points when avoidable.
+    fn legal_cards(&self, round: &Round, seat: usize) -> Vec<Card> {
+        let hand = &round.hands[seat];
+        let first_trick = round.tricks.is_empty();
+        let without_penalties = |cards: Vec<Card>| -> Vec<Card> {
+            let safe: Vec<Card> = cards.iter().filter(|card| !is_penalty(to_card(**card))).copied().collect();
+            if safe.is_empty() { cards } else { safe }
+        };
+
+        if round.trick.is_empty() {
+            let two = PlayingCard::new(Rank::Two, Suit::Clubs).motif();
+            if first_trick && hand.contains(&two) { return vec![two]; }
+
+            let broken = round.tricks.iter()
+                .flat_map(|trick| trick.cards.iter())
+                .any(|played| to_card(played.card).suit() == Some(Suit::Hearts));
+            let others: Vec<Card> = hand.iter().filter(|card| to_card(**card).suit() != Some(Suit::Hearts)).copied().collect();
+            return if broken || others.is_empty() { hand.clone() } else { others };
+        }
+
+        let cards = follow_suit(hand, &round.trick);
+        if first_trick { without_penalties(cards) } else { cards }
+    }
+
+    fn trick_points(&self, trick: &[Played]) -> i32 {
+        trick.iter()
+            .map(|played| to_card(played.card))
+            .map(|card| if card.suit() == Some(Suit::Hearts) { 1 } else if is_penalty(card) { 13 } else { 0 })
+            .sum()
+    }
+
+    /// A seat taking every penalty card shoots the moon: the other seats get 26 points instead.
+    fn scores(&self, round: &Round) -> Vec<i32> {
+        let mut scores = vec![0; self.players()];
+        for trick in round.tricks.iter() {
+            scores[trick.winner] += self.trick_points(&trick.cards);
+        }
+
+        if scores.contains(&MOON) {
+            scores.iter().map(|score| if *score == MOON { 0 } else { MOON }).collect()
+        } else {
+            scores
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use crate::Card;
+use crate::card::{Rank, Suit, BLACK_JOKER, RED_JOKER};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    French,
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    /// Finds a supported language from a language tag such as `en`, `en-US` or `de_DE`.
+    pub fn from_tag(tag: &str) -> Option<Language> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        match primary.to_ascii_lowercase().as_str() {
+            "fr" => Some(Language::French),
+            "en" => Some(Language::English),
+            "de" => Some(Language::German),
+            "es" => Some(Language::Spanish),
+            _ => None
+        }
+    }
+
+    /// Picks the response language, the `lang` parameter taking precedence over the `Accept-Language` header.
+    ///
+    /// # Arguments
+    ///
+    /// * `lang`: The value of the `lang` query parameter, if any.
+    /// * `accept_language`: The value of the `Accept-Language` header, if any.
+    ///
+    /// returns: Language, French when nothing supported is requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::i18n::Language;
+    ///
+    /// fn main() {
+    ///     assert_eq!(Language::negotiate(None, Some("it;q=0.9, de;q=0.8, en;q=0.5")), Language::German);
+    ///     assert_eq!(Language::negotiate(Some("es"), Some("en")), Language::Spanish);
+    ///     assert_eq!(Language::negotiate(None, None), Language::French);
+    /// }
+    /// ```
+    pub fn negotiate(lang: Option<&str>, accept_language: Option<&str>) -> Language {
+        if let Some(language) = lang.and_then(Language::from_tag) {
+            return language;
+        }
+
+        let mut ranges: Vec<(Language, f32)> = accept_language
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let language = Language::from_tag(parts.next()?)?;
+                let quality = parts
+                    .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                    .filter_map(|quality| quality.parse::<f32>().ok())
+                    .next()
+                    .unwrap_or(1.0);
+                Some((language, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+
+        ranges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranges.first().map(|(language, _)| *language).unwrap_or_default()
+    }
+}
+
+pub enum Message {
+    DeckNotFound,
+    IncorrectParameter,
+    MissingParameter,
+    InvalidCard(String),
+    CardNotInStack(Card),
+    BatchCardNotInStack(usize, Card),
+    BatchNotEnoughCards(usize),
+    UnknownTheme(String),
+    InvalidHandSize(usize),
+    DuplicateCard(String),
+    JokerNotAllowed,
+    InvalidHoleCards(usize),
+    InvalidBoard(usize),
+    InvalidOpponents(usize),
+    SessionNotFound,
+    InvalidBet,
+    OddBet,
+    IllegalAction,
+    NotYourTurn,
+    IllegalBid,
+    CardNotInHand(String),
+    IllegalCard(String),
+    InvalidPlayers(usize),
+    InvalidDiscard,
+    GameFull,
+    InvalidToken,
+    WebhookNotFound,
+    InvalidUrl(String),
+    ForbiddenHost(String),
+    Unauthorized,
+    Forbidden,
+    MissingScope,
+    AdminRequired,
+    ApiKeyNotFound,
+    TooManyRequests,
+    TooManyDecks(usize),
+    RouteNotFound,
+    MethodNotAllowed,
+}
+
+impl Message {
+    /// A code identifying the message whatever the language, for the clients to rely on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::i18n::Message;
+    ///
+    /// fn main() {
+    ///     assert_eq!(Message::DeckNotFound.code(), "deck_not_found");
+    ///     assert_eq!(Message::InvalidCard("ZZ".to_string()).code(), "invalid_card");
+    /// }
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Message::DeckNotFound => "deck_not_found",
+            Message::IncorrectParameter => "incorrect_parameter",
+            Message::MissingParameter => "missing_parameter",
+            Message::InvalidCard(_) => "invalid_card",
+            Message::CardNotInStack(_) => "card_not_in_stack",
+            Message::BatchCardNotInStack(..) => "batch_card_not_in_stack",
+            Message::BatchNotEnoughCards(_) => "batch_not_enough_cards",
+            Message::UnknownTheme(_) => "unknown_theme",
+            Message::InvalidHandSize(_) => "invalid_hand_size",
+            Message::DuplicateCard(_) => "duplicate_card",
+            Message::JokerNotAllowed => "joker_not_allowed",
+            Message::InvalidHoleCards(_) => "invalid_hole_cards",
+            Message::InvalidBoard(_) => "invalid_board",
+            Message::InvalidOpponents(_) => "invalid_opponents",
+            Message::SessionNotFound => "session_not_found",
+            Message::InvalidBet => "invalid_bet",
+            Message::OddBet => "odd_bet",
+            Message::IllegalAction => "illegal_action",
+            Message::NotYourTurn => "not_your_turn",
+            Message::IllegalBid => "illegal_bid",
+            Message::CardNotInHand(_) => "card_not_in_hand",
+            Message::IllegalCard(_) => "illegal_card",
+            Message::InvalidPlayers(_) => "invalid_players",
+            Message::InvalidDiscard => "invalid_discard",
+            Message::GameFull => "game_full",
+            Message::InvalidToken => "invalid_token",
+            Message::WebhookNotFound => "webhook_not_found",
+            Message::InvalidUrl(_) => "invalid_url",
+            Message::ForbiddenHost(_) => "forbidden_host",
+            Message::Unauthorized => "unauthorized",
+            Message::Forbidden => "forbidden",
+            Message::MissingScope => "missing_scope",
+            Message::AdminRequired => "admin_required",
+            Message::ApiKeyNotFound => "api_key_not_found",
+            Message::TooManyRequests => "too_many_requests",
+            Message::TooManyDecks(_) => "too_many_decks",
+            Message::RouteNotFound => "route_not_found",
+            Message::MethodNotAllowed => "method_not_allowed",
+        }
+    }
+
+    pub fn translate(&self, language: Language) -> String {
+        match (self, language) {
+            (Message::DeckNotFound, Language::French) => "Impossible de trouver ce paquet de carte.".to_string(),
+            (Message::DeckNotFound, Language::English) => "Unable to find this card deck.".to_string(),
+            (Message::DeckNotFound, Language::German) => "Dieses Kartenspiel wurde nicht gefunden.".to_string(),
+            (Message::DeckNotFound, Language::Spanish) => "No se encuentra esta baraja.".to_string(),
+
+            (Message::IncorrectParameter, Language::French) => "Paramètre incorrect.".to_string(),
+            (Message::IncorrectParameter, Language::English) => "Incorrect parameter.".to_string(),
+            (Message::IncorrectParameter, Language::German) => "Ungültiger Parameter.".to_string(),
+            (Message::IncorrectParameter, Language::Spanish) => "Parámetro incorrecto.".to_string(),
+
+            (Message::MissingParameter, Language::French) => "Paramètre manquant.".to_string(),
+            (Message::MissingParameter, Language::English) => "Missing parameter.".to_string(),
+            (Message::MissingParameter, Language::German) => "Fehlender Parameter.".to_string(),
+            (Message::MissingParameter, Language::Spanish) => "Falta un parámetro.".to_string(),
+
+            (Message::InvalidCard(card), Language::French) => format!("Carte invalide : {}", card),
+            (Message::InvalidCard(card), Language::English) => format!("Invalid card: {}", card),
+            (Message::InvalidCard(card), Language::German) => format!("Ungültige Karte: {}", card),
+            (Message::InvalidCard(card), Language::Spanish) => format!("Carta no válida: {}", card),
+
+            (Message::CardNotInStack(card), Language::French) => format!("La carte {} n'est pas dans la pile.", card),
+            (Message::CardNotInStack(card), Language::English) => format!("The card {} is not in the stack.", card),
+            (Message::CardNotInStack(card), Language::German) => format!("Die Karte {} ist nicht im Stapel.", card),
+            (Message::CardNotInStack(card), Language::Spanish) => format!("La carta {} no está en el montón.", card),
+
+            (Message::BatchCardNotInStack(step, card), _) => format!("{} : {}", Message::step(*step, language), Message::CardNotInStack(*card).translate(language)),
+
+            (Message::BatchNotEnoughCards(step), Language::French) => format!("{} : pas assez de cartes pour distribuer.", Message::step(*step, language)),
+            (Message::BatchNotEnoughCards(step), Language::English) => format!("{}: not enough cards to deal.", Message::step(*step, language)),
+            (Message::BatchNotEnoughCards(step), Language::German) => format!("{}: nicht genug Karten zum Austeilen.", Message::step(*step, language)),
+            (Message::BatchNotEnoughCards(step), Language::Spanish) => format!("{}: no hay suficientes cartas para repartir.", Message::step(*step, language)),
+
+            (Message::UnknownTheme(theme), Language::French) => format!("Thème inconnu : {}", theme),
+            (Message::UnknownTheme(theme), Language::English) => format!("Unknown theme: {}", theme),
+            (Message::UnknownTheme(theme), Language::German) => format!("Unbekanntes Design: {}", theme),
+            (Message::UnknownTheme(theme), Language::Spanish) => format!("Tema desconocido: {}", theme),
+
+            (Message::InvalidHandSize(size), Language::French) => format!("Une main doit contenir entre 5 et 7 cartes, {} reçues.", size),
+            (Message::InvalidHandSize(size), Language::English) => format!("A hand must have between 5 and 7 cards, {} given.", size),
+            (Message::InvalidHandSize(size), Language::German) => format!("Eine Hand muss 5 bis 7 Karten haben, {} erhalten.", size),
+            (Message::InvalidHandSize(size), Language::Spanish) => format!("Una mano debe tener entre 5 y 7 cartas, {} recibidas.", size),
+
+            (Message::DuplicateCard(card), Language::French) => format!("La carte {} est présente plusieurs fois.", card),
+            (Message::DuplicateCard(card), Language::English) => format!("The card {} appears more than once.", card),
+            (Message::DuplicateCard(card), Language::German) => format!("Die Karte {} kommt mehrfach vor.", card),
+            (Message::DuplicateCard(card), Language::Spanish) => format!("La carta {} aparece más de una vez.", card),
+
+            (Message::JokerNotAllowed, Language::French) => "Les jokers ne sont pas autorisés.".to_string(),
+            (Message::JokerNotAllowed, Language::English) => "Jokers are not allowed.".to_string(),
+            (Message::JokerNotAllowed, Language::German) => "Joker sind nicht erlaubt.".to_string(),
+            (Message::JokerNotAllowed, Language::Spanish) => "Los comodines no están permitidos.".to_string(),
+
+            (Message::InvalidHoleCards(size), Language::French) => format!("Il faut 2 cartes privatives, {} reçues.", size),
+            (Message::InvalidHoleCards(size), Language::English) => format!("2 hole cards are required, {} given.", size),
+            (Message::InvalidHoleCards(size), Language::German) => format!("2 Handkarten sind erforderlich, {} erhalten.", size),
+            (Message::InvalidHoleCards(size), Language::Spanish) => format!("Se requieren 2 cartas propias, {} recibidas.", size),
+
+            (Message::InvalidBoard(size), Language::French) => format!("Le tableau contient au plus 5 cartes, {} reçues.", size),
+            (Message::InvalidBoard(size), Language::English) => format!("The board has at most 5 cards, {} given.", size),
+            (Message::InvalidBoard(size), Language::German) => format!("Das Board hat höchstens 5 Karten, {} erhalten.", size),
+            (Message::InvalidBoard(size), Language::Spanish) => format!("La mesa tiene como máximo 5 cartas, {} recibidas.", size),
+
+            (Message::InvalidOpponents(opponents), Language::French) => format!("Le nombre d'adversaires doit être compris entre 1 et 9, {} reçu.", opponents),
+            (Message::InvalidOpponents(opponents), Language::English) => format!("The number of opponents must be between 1 and 9, {} given.", opponents),
+            (Message::InvalidOpponents(opponents), Language::German) => format!("Die Anzahl der Gegner muss zwischen 1 und 9 liegen, {} erhalten.", opponents),
+            (Message::InvalidOpponents(opponents), Language::Spanish) => format!("El número de rivales debe estar entre 1 y 9, {} recibido.", opponents),
+
+            (Message::SessionNotFound, Language::French) => "Impossible de trouver cette partie.".to_string(),
+            (Message::SessionNotFound, Language::English) => "Unable to find this game.".to_string(),
+            (Message::SessionNotFound, Language::German) => "Dieses Spiel wurde nicht gefunden.".to_string(),
+            (Message::SessionNotFound, Language::Spanish) => "No se encuentra esta partida.".to_string(),
+
+            (Message::InvalidBet, Language::French) => "Mise incorrecte.".to_string(),
+            (Message::InvalidBet, Language::English) => "Invalid bet.".to_string(),
+            (Message::InvalidBet, Language::German) => "Ungültiger Einsatz.".to_string(),
+            (Message::InvalidBet, Language::Spanish) => "Apuesta no válida.".to_string(),
+
+            (Message::OddBet, Language::French) => "La mise doit être paire, un blackjack, l'assurance et l'abandon en payant la moitié.".to_string(),
+            (Message::OddBet, Language::English) => "The bet must be even, a blackjack, the insurance and a surrender paying half of it.".to_string(),
+            (Message::OddBet, Language::German) => "Der Einsatz muss gerade sein, da ein Blackjack, die Versicherung und die Aufgabe die Hälfte davon auszahlen.".to_string(),
+            (Message::OddBet, Language::Spanish) => "La apuesta debe ser par, ya que un blackjack, el seguro y la rendición pagan la mitad.".to_string(),
+
+            (Message::IllegalAction, Language::French) => "Action impossible à ce stade de la partie.".to_string(),
+            (Message::IllegalAction, Language::English) => "This action is not allowed at this stage of the game.".to_string(),
+            (Message::IllegalAction, Language::German) => "Diese Aktion ist in dieser Spielphase nicht erlaubt.".to_string(),
+            (Message::IllegalAction, Language::Spanish) => "Esta acción no está permitida en esta fase de la partida.".to_string(),
+
+            (Message::NotYourTurn, Language::French) => "Ce n'est pas à ce joueur de jouer.".to_string(),
+            (Message::NotYourTurn, Language::English) => "It is not this player's turn.".to_string(),
+            (Message::NotYourTurn, Language::German) => "Dieser Spieler ist nicht am Zug.".to_string(),
+            (Message::NotYourTurn, Language::Spanish) => "No es el turno de este jugador.".to_string(),
+
+            (Message::IllegalBid, Language::French) => "Enchère impossible.".to_string(),
+            (Message::IllegalBid, Language::English) => "This bid is not allowed.".to_string(),
+            (Message::IllegalBid, Language::German) => "Dieses Gebot ist nicht erlaubt.".to_string(),
+            (Message::IllegalBid, Language::Spanish) => "Esta apuesta no está permitida.".to_string(),
+
+            (Message::CardNotInHand(card), Language::French) => format!("La carte {} n'est pas dans la main du joueur.", card),
+            (Message::CardNotInHand(card), Language::English) => format!("The card {} is not in the player's hand.", card),
+            (Message::CardNotInHand(card), Language::German) => format!("Die Karte {} ist nicht in der Hand des Spielers.", card),
+            (Message::CardNotInHand(card), Language::Spanish) => format!("La carta {} no está en la mano del jugador.", card),
+
+            (Message::IllegalCard(card), Language::French) => format!("La carte {} ne peut pas être jouée.", card),
+            (Message::IllegalCard(card), Language::English) => format!("The card {} cannot be played.", card),
+            (Message::IllegalCard(card), Language::German) => format!("Die Karte {} darf nicht gespielt werden.", card),
+            (Message::IllegalCard(card), Language::Spanish) => format!("La carta {} no se puede jugar.", card),
+
+            (Message::InvalidPlayers(players), Language::French) => format!("Nombre de joueurs incorrect : {}.", players),
+            (Message::InvalidPlayers(players), Language::English) => format!("Invalid number of players: {}.", players),
+            (Message::InvalidPlayers(players), Language::German) => format!("Ungültige Spielerzahl: {}.", players),
+            (Message::InvalidPlayers(players), Language::Spanish) => format!("Número de jugadores no válido: {}.", players),
+
+            (Message::InvalidDiscard, Language::French) => "Écart incorrect.".to_string(),
+            (Message::InvalidDiscard, Language::English) => "Invalid discard.".to_string(),
+            (Message::InvalidDiscard, Language::German) => "Ungültiges Ablegen.".to_string(),
+            (Message::InvalidDiscard, Language::Spanish) => "Descarte no válido.".to_string(),
+
+            (Message::GameFull, Language::French) => "Cette partie est complète.".to_string(),
+            (Message::GameFull, Language::English) => "This game is full.".to_string(),
+            (Message::GameFull, Language::German) => "Dieses Spiel ist voll.".to_string(),
+            (Message::GameFull, Language::Spanish) => "Esta partida está completa.".to_string(),
+
+            (Message::InvalidToken, Language::French) => "Jeton de joueur manquant ou invalide.".to_string(),
+            (Message::InvalidToken, Language::English) => "Missing or invalid player token.".to_string(),
+            (Message::InvalidToken, Language::German) => "Fehlendes oder ungültiges Spieler-Token.".to_string(),
+            (Message::InvalidToken, Language::Spanish) => "Token de jugador ausente o no válido.".to_string(),
+
+            (Message::WebhookNotFound, Language::French) => "Impossible de trouver ce webhook.".to_string(),
+            (Message::WebhookNotFound, Language::English) => "Unable to find this webhook.".to_string(),
+            (Message::WebhookNotFound, Language::German) => "Dieser Webhook wurde nicht gefunden.".to_string(),
+            (Message::WebhookNotFound, Language::Spanish) => "No se encuentra este webhook.".to_string(),
+
+            (Message::InvalidUrl(url), Language::French) => format!("L'URL {} doit commencer par http:// ou https://.", url),
+            (Message::InvalidUrl(url), Language::English) => format!("The URL {} must start with http:// or https://.", url),
+            (Message::InvalidUrl(url), Language::German) => format!("Die URL {} muss mit http:// oder https:// beginnen.", url),
+            (Message::InvalidUrl(url), Language::Spanish) => format!("La URL {} debe empezar por http:// o https://.", url),
+
+            (Message::ForbiddenHost(url), Language::French) => format!("L'hôte de l'URL {} est introuvable ou n'est pas public.", url),
+            (Message::ForbiddenHost(url), Language::English) => format!("The host of the URL {} can't be found or isn't public.", url),
+            (Message::ForbiddenHost(url), Language::German) => format!("Der Host der URL {} wurde nicht gefunden oder ist nicht öffentlich.", url),
+            (Message::ForbiddenHost(url), Language::Spanish) => format!("El host de la URL {} no se encuentra o no es público.", url),
+
+            (Message::Unauthorized, Language::French) => "Clé d'API manquante ou invalide.".to_string(),
+            (Message::Unauthorized, Language::English) => "Missing or invalid API key.".to_string(),
+            (Message::Unauthorized, Language::German) => "Fehlender oder ungültiger API-Schlüssel.".to_string(),
+            (Message::Unauthorized, Language::Spanish) => "Clave de API ausente o no válida.".to_string(),
+
+            (Message::Forbidden, Language::French) => "Cette clé d'API n'a pas accès à ce paquet.".to_string(),
+            (Message::Forbidden, Language::English) => "This API key has no access to this deck.".to_string(),
+            (Message::Forbidden, Language::German) => "Dieser API-Schlüssel hat keinen Zugriff auf dieses Kartenspiel.".to_string(),
+            (Message::Forbidden, Language::Spanish) => "Esta clave de API no tiene acceso a esta baraja.".to_string(),
+
+            (Message::MissingScope, Language::French) => "Ce jeton n'a pas les droits nécessaires pour cette action.".to_string(),
+            (Message::MissingScope, Language::English) => "This token doesn't have the scope needed for this action.".to_string(),
+            (Message::MissingScope, Language::German) => "Dieses Token hat nicht die für diese Aktion nötige Berechtigung.".to_string(),
+            (Message::MissingScope, Language::Spanish) => "Este token no tiene el permiso necesario para esta acción.".to_string(),
+
+            (Message::AdminRequired, Language::French) => "Cette action est réservée à l'administrateur.".to_string(),
+            (Message::AdminRequired, Language::English) => "This action is reserved for the administrator.".to_string(),
+            (Message::AdminRequired, Language::German) => "Diese Aktion ist dem Administrator vorbehalten.".to_string(),
+            (Message::AdminRequired, Language::Spanish) => "Esta acción está reservada al administrador.".to_string(),
+
+            (Message::ApiKeyNotFound, Language::French) => "Impossible de trouver cette clé d'API.".to_string(),
+            (Message::ApiKeyNotFound, Language::English) => "Unable to find this API key.".to_string(),
+            (Message::ApiKeyNotFound, Language::German) => "Dieser API-Schlüssel wurde nicht gefunden.".to_string(),
+            (Message::ApiKeyNotFound, Language::Spanish) => "No se encuentra esta clave de API.".to_string(),
+
+            (Message::TooManyRequests, Language::French) => "Trop de requêtes, veuillez réessayer plus tard.".to_string(),
+            (Message::TooManyRequests, Language::English) => "Too many requests, please try again later.".to_string(),
+            (Message::TooManyRequests, Language::German) => "Zu viele Anfragen, bitte versuchen Sie es später erneut.".to_string(),
+            (Message::TooManyRequests, Language::Spanish) => "Demasiadas solicitudes, inténtelo de nuevo más tarde.".to_string(),
+
+            (Message::TooManyDecks(max), Language::French) => format!("Cette clé d'API possède déjà {} paquets.", max),
+            (Message::TooManyDecks(max), Language::English) => format!("This API key already owns {} decks.", max),
+            (Message::TooManyDecks(max), Language::German) => format!("Dieser API-Schlüssel besitzt bereits {} Kartenspiele.", max),
+            (Message::TooManyDecks(max), Language::Spanish) => format!("Esta clave de API ya posee {} barajas.", max),
+
+            (Message::RouteNotFound, Language::French) => "Cette route n'existe pas.".to_string(),
+            (Message::RouteNotFound, Language::English) => "This route doesn't exist.".to_string(),
+            (Message::RouteNotFound, Language::German) => "Diese Route existiert nicht.".to_string(),
+            (Message::RouteNotFound, Language::Spanish) => "Esta ruta no existe.".to_string(),
+
+            (Message::MethodNotAllowed, Language::French) => "Cette méthode n'est pas permise sur cette route.".to_string(),
+            (Message::MethodNotAllowed, Language::English) => "This method isn't allowed on this route.".to_string(),
+            (Message::MethodNotAllowed, Language::German) => "Diese Methode ist auf dieser Route nicht erlaubt.".to_string(),
+            (Message::MethodNotAllowed, Language::Spanish) => "Este método no está permitido en esta ruta.".to_string(),
+        }
+    }
+
+    fn step(step: usize, language: Language) -> String {
+        match language {
+            Language::French => format!("Opération {}", step),
+            Language::English => format!("Operation {}", step),
+            Language::German => format!("Operation {}", step),
+            Language::Spanish => format!("Operación {}", step),
+        }
+    }
+}
+
+pub fn suit_name(suit: Suit, language: Language) -> &'static str {
+    match (suit, language) {
+        (Suit::Clubs, Language::French) => "Trèfle",
+        (Suit::Diamonds, Language::French) => "Carreau",
+        (Suit::Hearts, Language::French) => "Coeur",
+        (Suit::Spades, Language::French) => "Pique",
+        (Suit::Clubs, Language::English) => "Clubs",
+        (Suit::Diamonds, Language::English) => "Diamonds",
+        (Suit::Hearts, Language::English) => "Hearts",
+        (Suit::Spades, Language::English) => "Spades",
+        (Suit::Clubs, Language::German) => "Kreuz",
+        (Suit::Diamonds, Language::German) => "Karo",
+        (Suit::Hearts, Language::German) => "Herz",
+        (Suit::Spades, Language::German) => "Pik",
+        (Suit::Clubs, Language::Spanish) => "Tréboles",
+        (Suit::Diamonds, Language::Spanish) => "Diamantes",
+        (Suit::Hearts, Language::Spanish) => "Corazones",
+        (Suit::Spades, Language::Spanish) => "Picas",
+    }
+}
+
+pub fn rank_name(rank: Rank, language: Language) -> &'static str {
+    match (rank, language) {
+        (Rank::Ace, Language::French) => "As",
+        (Rank::Jack, Language::French) => "Valet",
+        (Rank::Queen, Language::French) => "Reine",
+        (Rank::King, Language::French) => "Roi",
+        (Rank::Ace, Language::English) => "Ace",
+        (Rank::Jack, Language::English) => "Jack",
+        (Rank::Queen, Language::English) => "Queen",
+        (Rank::King, Language::English) => "King",
+        (Rank::Ace, Language::German) => "Ass",
+        (Rank::Jack, Language::German) => "Bube",
+        (Rank::Queen, Language::German) => "Dame",
+        (Rank::King, Language::German) => "König",
+        (Rank::Ace, Language::Spanish) => "As",
+        (Rank::Jack, Language::Spanish) => "Jota",
+        (Rank::Queen, Language::Spanish) => "Reina",
+        (Rank::King, Language::Spanish) => "Rey",
+        (rank, _) => rank.symbol(),
+    }
+}
+
+/// The full name of a card, e.g. "Reine de Coeur", "Queen of Hearts", "Herz Dame" or "Reina de Corazones".
+pub fn card_name(rank: Rank, suit: Suit, language: Language) -> String {
+    suited_name(rank_name(rank, language), suit, language)
+}
+
+/// The name of a tarot knight, e.g. "Cavalier de Coeur" or "Knight of Hearts".
+pub fn knight_name(suit: Suit, language: Language) -> String {
+    let knight = match language {
+        Language::French => "Cavalier",
+        Language::English => "Knight",
+        Language::German => "Reiter",
+        Language::Spanish => "Caballo",
+    };
+    suited_name(knight, suit, language)
+}
+
+/// The name of a tarot trump, e.g. "21 d'atout" or "Trump 21".
+pub fn trump_name(number: u8, language: Language) -> String {
+    match language {
+        Language::French => format!("{} d'atout", number),
+        Language::English => format!("Trump {}", number),
+        Language::German => format!("Trumpf {}", number),
+        Language::Spanish => format!("Triunfo {}", number),
+    }
+}
+
+pub fn excuse_name(language: Language) -> &'static str {
+    match language {
+        Language::French => "Excuse",
+        Language::English => "Fool",
+        Language::German => "Sküs",
+        Language::Spanish => "Excusa",
+    }
+}
+
+fn suited_name(rank: &str, suit: Suit, language: Language) -> String {
+    let suit = suit_name(suit, language);
+    match language {
+        Language::French => format!("{} de {}", rank, suit),
+        Language::English => format!("{} of {}", rank, suit),
+        Language::German => format!("{} {}", suit, rank),
+        Language::Spanish => format!("{} de {}", rank, suit),
+    }
+}
+
+pub fn joker_name(motif: Card, language: Language) -> &'static str {
+    match (motif, language) {
+        (RED_JOKER, Language::French) => "Joker rouge",
+        (RED_JOKER, Language::English) => "Red Joker",
+        (RED_JOKER, Language::German) => "Roter Joker",
+        (RED_JOKER, Language::Spanish) => "Comodín rojo",
+        (BLACK_JOKER, Language::French) => "Joker noir",
+        (BLACK_JOKER, Language::English) => "Black Joker",
+        (BLACK_JOKER, Language::German) => "Schwarzer Joker",
+        (BLACK_JOKER, Language::Spanish) => "Comodín negro",
+        _ => panic!("Incorrect value.")
+    }
+}
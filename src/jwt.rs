@@ -0,0 +1,175 @@
+use std::env;
+use std::sync::OnceLock;
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::api_key::AccessError;
+
+/// How long a token is valid by default, in seconds.
+pub const DEFAULT_LIFETIME: i64 = 3600;
+/// The longest lifetime of a token, in seconds.
+pub const MAX_LIFETIME: i64 = 7 * 24 * 3600;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Draw,
+    Put,
+    Shuffle,
+    /// Every other scope, along with deleting the deck.
+    Admin,
+}
+
+/// The claims of a token given to a player or to a spectator, for one deck or one game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Claims {
+    /// The id of the player.
+    pub sub: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deck: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<Uuid>,
+    pub scopes: Vec<Scope>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+impl Claims {
+    /// Creates the claims of a token valid for a number of seconds.
+    pub fn new(sub: Uuid, deck: Option<Uuid>, game: Option<Uuid>, scopes: Vec<Scope>, lifetime: i64) -> Claims {
+        let now = Utc::now().timestamp();
+        Claims { sub, deck, game, scopes, iat: now, exp: now + lifetime }
+    }
+
+    /// Tells if the token allows an action on a deck or a game, the admin scope allowing them all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::jwt::{Claims, Scope};
+    /// use uuid::Uuid;
+    ///
+    /// fn main() {
+    ///     let deck_id = Uuid::new_v4();
+    ///     let claims = Claims::new(Uuid::new_v4(), Some(deck_id), None, vec![Scope::Read, Scope::Draw], 60);
+    ///     assert!(claims.allows(deck_id, Scope::Draw));
+    ///     assert!(!claims.allows(deck_id, Scope::Shuffle));
+    ///     assert!(!claims.allows(Uuid::new_v4(), Scope::Read));
+    /// }
+    /// ```
+    pub fn allows(&self, target: Uuid, scope: Scope) -> bool {
+        let targeted = self.deck == Some(target) || self.game == Some(target);
+        targeted && (self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin))
+    }
+}
+
+/// Checks the lifetime asked for a token.
+///
+/// returns: Option<i64>, the lifetime in seconds, None when it is not between 1 second and `MAX_LIFETIME`.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::jwt::{lifetime, DEFAULT_LIFETIME, MAX_LIFETIME};
+///
+/// fn main() {
+///     assert_eq!(lifetime(None), Some(DEFAULT_LIFETIME));
+///     assert_eq!(lifetime(Some(60)), Some(60));
+///     assert_eq!(lifetime(Some(0)), None);
+///     assert_eq!(lifetime(Some(MAX_LIFETIME + 1)), None);
+/// }
+/// ```
+pub fn lifetime(expires_in: Option<i64>) -> Option<i64> {
+    match expires_in {
+        None => Some(DEFAULT_LIFETIME),
+        Some(expires_in) if (1..=MAX_LIFETIME).contains(&expires_in) => Some(expires_in),
+        Some(_) => None
+    }
+}
+
+/// Tells if a token allows every scope on a deck or a game.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::api_key::AccessError;
+/// use jeu_de_carte::jwt::{check_scopes, Claims, Scope};
+/// use uuid::Uuid;
+///
+/// fn main() {
+///     let game_id = Uuid::new_v4();
+///     let spectator = Claims::new(Uuid::new_v4(), None, Some(game_id), vec![Scope::Read], 60);
+///     assert_eq!(check_scopes(&spectator, game_id, &[Scope::Read]), Ok(()));
+///     assert_eq!(check_scopes(&spectator, game_id, &[Scope::Read, Scope::Draw]), Err(AccessError::MissingScope));
+/// }
+/// ```
+pub fn check_scopes(claims: &Claims, target: Uuid, scopes: &[Scope]) -> Result<(), AccessError> {
+    if scopes.iter().all(|scope| claims.allows(target, *scope)) {
+        Ok(())
+    } else {
+        Err(AccessError::MissingScope)
+    }
+}
+
+/// The secret signing the tokens, from the `JWT_SECRET` environment variable.
+///
+/// Without it, a random secret is used, the tokens being invalid once the API restarts.
+pub fn secret() -> &'static [u8] {
+    static SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+    SECRET.get_or_init(|| match env::var("JWT_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => rand::thread_rng().gen::<[u8; 32]>().to_vec()
+    })
+}
+
+/// Signs claims into a token with HS256.
+pub fn issue(secret: &[u8], claims: &Claims) -> String {
+    jsonwebtoken::encode(&Header::new(Algorithm::HS256), claims, &EncodingKey::from_secret(secret)).expect("An error occurred.")
+}
+
+/// Checks the signature and the expiration of a token.
+///
+/// returns: Option<Claims>, None when the token is invalid or expired.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::jwt::{issue, verify, Claims, Scope};
+/// use uuid::Uuid;
+///
+/// fn main() {
+///     let claims = Claims::new(Uuid::new_v4(), Some(Uuid::new_v4()), None, vec![Scope::Read], 60);
+///     let token = issue(b"secret", &claims);
+///     assert_eq!(verify(b"secret", &token), Some(claims));
+///     assert_eq!(verify(b"other", &token), None);
+/// }
+/// ```
+pub fn verify(secret: &[u8], token: &str) -> Option<Claims> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = 0;
+    jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(secret), &validation)
+        .ok()
+        .map(|data| data.claims)
+}
+
+/// Tells if a credential looks like a token rather than an API key.
+pub fn is_token(credential: &str) -> bool {
+    credential.split('.').count() == 3
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TokenRepresentation {
+    pub token: String,
+    pub claims: Claims,
+}
+
+impl TokenRepresentation {
+    /// Signs the claims with the secret of the API.
+    pub fn issue(claims: Claims) -> TokenRepresentation {
+        TokenRepresentation { token: issue(secret(), &claims), claims }
+    }
+}
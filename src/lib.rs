@@ -1,20 +1,104 @@
+pub mod api_key;
+pub mod batch;
+pub mod belote;
+pub mod blackjack;
+pub mod card;
 mod card_generator;
 pub mod database;
+pub mod events;
+pub mod game;
+pub mod health;
+pub mod hearts;
+pub mod i18n;
+pub mod jwt;
+pub mod logging;
+pub mod metrics;
+pub mod odds;
+pub mod openapi;
+pub mod poker;
+pub mod rate_limit;
+pub mod render;
+pub mod session;
+pub mod tarot;
+pub mod theme;
+pub mod trick;
+pub mod webhook;
+pub mod whist;
 mod tests;
 
+use actix_web::http::StatusCode;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use rand::prelude::SliceRandom;
 use uuid::Uuid;
-use postgres::Client;
+use postgres::GenericClient;
+use postgres::types::ToSql;
+use i18n::Language;
+use theme::Theme;
 
-#[derive(Serialize)]
-pub struct JsonMessage<'a> {
-    pub message: &'a str,
+/// The content type of the errors, told as the problem details of the RFC 7807.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// An error of the API, told as the problem details of the RFC 7807.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Problem {
+    /// Always `about:blank`, the kind of problem being told by `code`.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// The reason phrase of the status.
+    pub title: String,
+    pub status: u16,
+    /// The explanation of the problem, in the language of the request.
+    pub detail: String,
+    /// A code identifying the kind of problem whatever the language, for the clients to rely on.
+    pub code: String,
+    /// The parameter of the request at fault, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+}
+
+impl Problem {
+    /// Tells a message as a problem.
+    ///
+    /// # Arguments
+    ///
+    /// * `status`: The status of the answer.
+    /// * `message`: The message telling the problem.
+    /// * `parameter`: The parameter of the request at fault, if any.
+    /// * `language`: The language of the detail.
+    ///
+    /// returns: Problem
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix_web::http::StatusCode;
+    /// use jeu_de_carte::Problem;
+    /// use jeu_de_carte::i18n::{Language, Message};
+    ///
+    /// fn main() {
+    ///     let problem = Problem::new(StatusCode::BAD_REQUEST, &Message::IncorrectParameter, Some("id"), Language::English);
+    ///     assert_eq!(problem.title, "Bad Request");
+    ///     assert_eq!(problem.status, 400);
+    ///     assert_eq!(problem.code, "incorrect_parameter");
+    ///     assert_eq!(problem.parameter.as_deref(), Some("id"));
+    /// }
+    /// ```
+    pub fn new(status: StatusCode, message: &i18n::Message, parameter: Option<&str>, language: Language) -> Problem {
+        Problem {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: message.translate(language),
+            code: message.code().to_string(),
+            parameter: parameter.map(str::to_string),
+        }
+    }
 }
 
 type Card = i32;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct CardRepresentation {
     pub name: String,
     pub value: Card,
@@ -25,21 +109,28 @@ pub struct CardRepresentation {
 pub enum DeckSize {
     Small,
     Normal,
+    /// The 78 cards French tarot deck, with the knights, the 21 trumps and the Excuse.
+    Tarot,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct Deck {
     pub id: Uuid,
     pub cards: Vec<Card>,
     pub discarded: Vec<Card>,
+    pub theme: Option<String>,
+    /// The API key owning the deck, None when anybody may change it.
+    pub owner: Option<Uuid>,
+    /// The game session playing with the deck, which is then only changed through the session.
+    pub session: Option<Uuid>,
 }
 
 impl Deck {
-    /// Creates a new card deck with with 52 or 32 cards inside.
+    /// Creates a new card deck with with 52, 32 or 78 cards inside.
     ///
     /// # Arguments
     ///
-    /// * `deck_size`: The desired card deck size, 52 for a normal deck either 32 for a small card deck.
+    /// * `deck_size`: The desired card deck size, 52 for a normal deck, 32 for a small card deck or 78 for a tarot deck.
     ///
     /// returns: Deck
     ///
@@ -51,19 +142,49 @@ impl Deck {
     /// fn main() {
     ///     let normal_deck = Deck::new(DeckSize::Normal);
     ///     let small_deck = Deck::new(DeckSize::Small);
+    ///     let tarot_deck = Deck::new(DeckSize::Tarot);
+    ///     assert_eq!(tarot_deck.cards.len(), 78);
     /// }
     /// ```
     pub fn new(deck_size: DeckSize) -> Deck {
-        let cards = (0..52)
-            .filter(|motif| deck_size == DeckSize::Normal || ((motif % 13) < 1 || 5 < (motif % 13)))
-            .collect();
+        let cards = match deck_size {
+            DeckSize::Tarot => (0..52).chain(card::KNIGHTS..=card::EXCUSE).collect(),
+            _ => (0..52)
+                .filter(|motif| deck_size == DeckSize::Normal || ((motif % 13) < 1 || 5 < (motif % 13)))
+                .collect()
+        };
 
         let discarded = Vec::new();
 
-        Deck { id: Uuid::new_v4(), cards, discarded }
+        Deck { id: Uuid::new_v4(), cards, discarded, theme: None, owner: None, session: None }
+    }
+
+    /// Creates a shoe made of several normal card decks.
+    ///
+    /// # Arguments
+    ///
+    /// * `decks`: The number of 52 cards decks in the shoe.
+    ///
+    /// returns: Deck
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::Deck;
+    ///
+    /// fn main() {
+    ///     let shoe = Deck::shoe(6);
+    ///     assert_eq!(shoe.cards.len(), 312);
+    /// }
+    /// ```
+    pub fn shoe(decks: usize) -> Deck {
+        let mut shoe = Deck::new(DeckSize::Normal);
+        shoe.cards = shoe.cards.repeat(decks);
+        shoe
     }
 
     pub fn shuffle(&mut self, shuffle_discarded: bool) {
+        let _span = logging::deck_span("shuffle", self.id);
         let mut rng = rand::thread_rng();
         if shuffle_discarded {
             self.discarded.shuffle(&mut rng);
@@ -80,7 +201,7 @@ impl Deck {
     /// * `length`: Number of cards to take.
     /// * `move_as_block`: If true, moves all cards as one block, otherwise puts cards one by one in the discarded stack.
     ///
-    /// returns: ()
+    /// returns: Vec<Card>, the moved cards in the order they were added to the discarded stack.
     ///
     /// # Examples
     ///
@@ -93,8 +214,9 @@ impl Deck {
     ///     normal_deck.take(false, 5, false);
     /// }
     /// ```
-    pub fn take(&mut self, lifo: bool, length: usize, move_as_block: bool) {
-        if self.cards.len() == 0 || length == 0 { return; }
+    pub fn take(&mut self, lifo: bool, length: usize, move_as_block: bool) -> Vec<Card> {
+        let _span = logging::deck_span("take", self.id);
+        if self.cards.is_empty() || length == 0 { return Vec::new(); }
         let at = if length >= self.cards.len() {
             0
         } else {
@@ -104,10 +226,13 @@ impl Deck {
         let mut cards: Vec<Card> = self.cards.drain(at..).collect();
         if lifo {
             if !move_as_block { cards.reverse() }
-            self.discarded.extend_from_slice(&cards)
+            self.discarded.extend_from_slice(&cards);
+            cards
         } else {
+            let moved = cards.clone();
             cards.extend_from_slice(&self.discarded);
-            self.discarded = cards
+            self.discarded = cards;
+            moved
         }
     }
 
@@ -119,7 +244,7 @@ impl Deck {
     /// * `length`: Number of cards to take.
     /// * `move_as_block`: If true, moves all cards as one block, otherwise puts cards one by one in the cards stack.
     ///
-    /// returns: ()
+    /// returns: Vec<Card>, the moved cards in the order they were added to the cards stack.
     ///
     /// # Examples
     ///
@@ -132,8 +257,9 @@ impl Deck {
     ///     normal_deck.put(false, 5, false);
     /// }
     /// ```
-    pub fn put(&mut self, lifo: bool, length: usize, move_as_block: bool) {
-        if self.discarded.len() == 0 || length == 0 { return; }
+    pub fn put(&mut self, lifo: bool, length: usize, move_as_block: bool) -> Vec<Card> {
+        let _span = logging::deck_span("put", self.id);
+        if self.discarded.is_empty() || length == 0 { return Vec::new(); }
         let at = if length >= self.discarded.len() {
             0
         } else {
@@ -143,58 +269,180 @@ impl Deck {
         let mut cards: Vec<Card> = self.discarded.drain(at..).collect();
         if lifo {
             if !move_as_block { cards.reverse() }
-            self.cards.extend_from_slice(&cards)
+            self.cards.extend_from_slice(&cards);
+            cards
         } else {
+            let moved = cards.clone();
             cards.extend_from_slice(&self.cards);
-            self.cards = cards
+            self.cards = cards;
+            moved
         }
     }
 
-    pub fn insert_into_db(&self, connection: &mut Client) {
+    /// Moves specific cards from one stack to the top of the other one.
+    ///
+    /// # Arguments
+    ///
+    /// * `cards`: The cards to move, in the order they will be stacked.
+    /// * `to_discarded`: If true, moves the cards from the cards stack to the discarded stack, otherwise the opposite.
+    ///
+    /// returns: Result<(), Card>, the first card which is not in the source stack on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::{Deck, DeckSize};
+    ///
+    /// fn main() {
+    ///     let mut normal_deck = Deck::new(DeckSize::Normal);
+    ///     assert!(normal_deck.move_cards(&[0, 12], true).is_ok());
+    ///     assert_eq!(normal_deck.move_cards(&[0], true), Err(0));
+    /// }
+    /// ```
+    pub fn move_cards(&mut self, cards: &[Card], to_discarded: bool) -> Result<(), Card> {
+        let _span = logging::deck_span("move", self.id);
+        let (from, to) = if to_discarded {
+            (&mut self.cards, &mut self.discarded)
+        } else {
+            (&mut self.discarded, &mut self.cards)
+        };
+
+        let mut remaining = from.clone();
+        for card in cards {
+            match remaining.iter().position(|c| c == card) {
+                Some(index) => { remaining.remove(index); }
+                None => return Err(*card)
+            }
+        }
+
+        *from = remaining;
+        to.extend_from_slice(cards);
+        Ok(())
+    }
+
+    /// Deals cards from the top of the cards stack to several hands, one card at a time.
+    /// The dealt cards are put into the discarded stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `hands`: Number of hands to deal.
+    /// * `length`: Number of cards in each hand.
+    ///
+    /// returns: Option<Vec<Vec<Card>>>, the dealt hands or None if there are not enough cards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::{Deck, DeckSize};
+    ///
+    /// fn main() {
+    ///     let mut small_deck = Deck::new(DeckSize::Small);
+    ///     let hands = small_deck.deal(4, 8).unwrap();
+    ///     assert_eq!(hands.len(), 4);
+    ///     assert!(small_deck.deal(1, 1).is_none());
+    /// }
+    /// ```
+    pub fn deal(&mut self, hands: usize, length: usize) -> Option<Vec<Vec<Card>>> {
+        let _span = logging::deck_span("deal", self.id);
+        let total = hands.checked_mul(length)?;
+        if total > self.cards.len() { return None; }
+
+        let mut dealt = vec![Vec::with_capacity(length); hands];
+        for index in 0..total {
+            let card = self.cards.pop()?;
+            dealt[index % hands].push(card);
+            self.discarded.push(card);
+        }
+
+        Some(dealt)
+    }
+
+    pub fn insert_into_db<C: GenericClient>(&self, connection: &mut C) {
+        let _span = logging::deck_span("create", self.id);
         let stmt = connection
             .prepare(
-                "INSERT INTO decks (id, cards, discarded) VALUES ($1, $2, $3)"
+                "INSERT INTO decks (id, cards, discarded, theme, owner, session) VALUES ($1, $2, $3, $4, $5, $6)"
             ).unwrap();
-        connection.execute(&stmt, &[&self.id, &self.cards, &self.discarded]).expect("An error occurred.");
+        metrics::time_query("insert_deck", || connection.execute(&stmt, &[&self.id, &self.cards, &self.discarded, &self.theme, &self.owner, &self.session])).expect("An error occurred.");
+        metrics::DECKS_CREATED.inc(&[]);
     }
 
-    pub fn update_db(&self, connection: &mut Client) {
+    pub fn update_db<C: GenericClient>(&self, connection: &mut C) {
+        let _span = logging::deck_span("update", self.id);
         let stmt = connection
             .prepare(
                 "UPDATE decks SET cards = $2, discarded = $3 WHERE id = $1"
             ).unwrap();
 
-        connection.execute(&stmt, &[&self.id, &self.cards, &self.discarded]).expect("An error occurred.");
+        metrics::time_query("update_deck", || connection.execute(&stmt, &[&self.id, &self.cards, &self.discarded])).expect("An error occurred.");
     }
 
-    pub fn delete_from_db(&self, connection: &mut Client) {
+    pub fn delete_from_db<C: GenericClient>(&self, connection: &mut C) {
+        let _span = logging::deck_span("delete", self.id);
         let stmt = connection
             .prepare(
                 "DELETE FROM decks WHERE id = $1"
             ).unwrap();
 
-        connection.execute(&stmt, &[&self.id]).expect("An error occurred.");
+        metrics::time_query("delete_deck", || connection.execute(&stmt, &[&self.id])).expect("An error occurred.");
+        metrics::DECKS_DELETED.inc(&[]);
     }
 
-    pub fn find_by_id(id: Uuid, connection: &mut Client) -> Option<Deck> {
+    /// Counts the live decks owned by an API key.
+    pub fn count_by_owner<C: GenericClient>(owner: Uuid, connection: &mut C) -> usize {
         let stmt = connection
             .prepare(
-                "SELECT id, cards, discarded FROM decks WHERE id = $1"
+                "SELECT COUNT(*) FROM decks WHERE owner = $1"
             ).unwrap();
-        let rows = connection.query(&stmt, &[&id]);
+        let count: i64 = metrics::time_query("count_decks", || connection.query_one(&stmt, &[&owner])).expect("An error occurred.").get(0);
+
+        count as usize
+    }
+
+    /// Finds a deck of the deck API, the decks of the game sessions being left out.
+    pub fn find_by_id<C: GenericClient>(id: Uuid, connection: &mut C) -> Option<Deck> {
+        Deck::find_with_query(id, connection, "SELECT id, cards, discarded, theme, owner, session FROM decks WHERE id = $1 AND session IS NULL", &[])
+    }
+
+    /// Finds a deck of the deck API and locks its row until the end of the current transaction.
+    pub fn find_by_id_for_update<C: GenericClient>(id: Uuid, connection: &mut C) -> Option<Deck> {
+        Deck::find_with_query(id, connection, "SELECT id, cards, discarded, theme, owner, session FROM decks WHERE id = $1 AND session IS NULL FOR UPDATE", &[])
+    }
+
+    /// Finds a deck of a game session.
+    pub fn find_in_session<C: GenericClient>(id: Uuid, session: Uuid, connection: &mut C) -> Option<Deck> {
+        Deck::find_with_query(id, connection, "SELECT id, cards, discarded, theme, owner, session FROM decks WHERE id = $1 AND session = $2", &[&session])
+    }
+
+    /// Finds a deck of a game session and locks its row until the end of the current transaction.
+    pub fn find_in_session_for_update<C: GenericClient>(id: Uuid, session: Uuid, connection: &mut C) -> Option<Deck> {
+        Deck::find_with_query(id, connection, "SELECT id, cards, discarded, theme, owner, session FROM decks WHERE id = $1 AND session = $2 FOR UPDATE", &[&session])
+    }
+
+    fn find_with_query<C: GenericClient>(id: Uuid, connection: &mut C, query: &str, params: &[&(dyn ToSql + Sync)]) -> Option<Deck> {
+        let _span = logging::deck_span("find", id);
+        let stmt = connection.prepare(query).unwrap();
+        let params: Vec<&(dyn ToSql + Sync)> = std::iter::once(&id as &(dyn ToSql + Sync)).chain(params.iter().copied()).collect();
+        let rows = metrics::time_query("find_deck", || connection.query(&stmt, &params));
 
         match rows {
             Ok(rows) => {
-                let row = rows.iter().next();
+                let row = rows.first();
                 match row {
                     Some(row) => {
                         let cards: Vec<Card> = row.get(1);
                         let discarded: Vec<Card> = row.get(2);
+                        let theme: Option<String> = row.get(3);
+                        let owner: Option<Uuid> = row.get(4);
+                        let session: Option<Uuid> = row.get(5);
 
                         Some(Deck {
                             id,
                             cards,
                             discarded,
+                            theme,
+                            owner,
+                            session,
                         })
                     }
                     None => None
@@ -205,23 +453,93 @@ impl Deck {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct DeckRepresentation {
     pub id: Uuid,
+    pub theme: String,
+    pub back: String,
     pub cards: Vec<CardRepresentation>,
     pub discarded: Vec<CardRepresentation>,
 }
 
 impl DeckRepresentation {
     pub fn from(deck: Deck) -> DeckRepresentation {
+        let theme = Theme::resolve(&[deck.theme.as_deref()]);
+        DeckRepresentation::translated(deck, Language::default(), &theme)
+    }
+
+    /// Represents a deck with the card names in the given language and the images of the given theme.
+    pub fn translated(deck: Deck, language: Language, theme: &Theme) -> DeckRepresentation {
         DeckRepresentation {
             id: deck.id,
-            cards: DeckRepresentation::translate_cards(deck.cards),
-            discarded: DeckRepresentation::translate_cards(deck.discarded),
+            theme: theme.name.clone(),
+            back: theme.back.clone(),
+            cards: DeckRepresentation::translate_cards(deck.cards, language, theme),
+            discarded: DeckRepresentation::translate_cards(deck.discarded, language, theme),
         }
     }
 
-    fn translate_cards(cards: Vec<Card>) -> Vec<CardRepresentation> {
-        cards.iter().map(|motif| card_generator::get_card(motif)).collect()
+    pub(crate) fn translate_cards(cards: Vec<Card>, language: Language, theme: &Theme) -> Vec<CardRepresentation> {
+        cards.iter().map(|motif| card_generator::get_card(motif, language, theme)).collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct CompactDeckRepresentation {
+    pub id: Uuid,
+    pub cards: Vec<String>,
+    pub discarded: Vec<String>,
+}
+
+impl CompactDeckRepresentation {
+    /// Represents a deck with short card codes ("QH", "10S") instead of full card objects.
+    pub fn from(deck: Deck) -> CompactDeckRepresentation {
+        CompactDeckRepresentation {
+            id: deck.id,
+            cards: CompactDeckRepresentation::translate_cards(&deck.cards),
+            discarded: CompactDeckRepresentation::translate_cards(&deck.discarded),
+        }
+    }
+
+    pub(crate) fn translate_cards(cards: &[Card]) -> Vec<String> {
+        cards.iter().map(card_generator::get_code).collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct MovedCardsRepresentation<T> {
+    pub id: Uuid,
+    pub moved: Vec<T>,
+    pub cards_count: usize,
+    pub discarded_count: usize,
+}
+
+impl MovedCardsRepresentation<CardRepresentation> {
+    /// Represents only the moved cards of a deck, along with the size of both stacks.
+    pub fn from(deck: &Deck, moved: Vec<Card>) -> MovedCardsRepresentation<CardRepresentation> {
+        let theme = Theme::resolve(&[deck.theme.as_deref()]);
+        MovedCardsRepresentation::translated(deck, moved, Language::default(), &theme)
+    }
+
+    /// Represents the moved cards with their names in the given language and the images of the given theme.
+    pub fn translated(deck: &Deck, moved: Vec<Card>, language: Language, theme: &Theme) -> MovedCardsRepresentation<CardRepresentation> {
+        MovedCardsRepresentation {
+            id: deck.id,
+            moved: DeckRepresentation::translate_cards(moved, language, theme),
+            cards_count: deck.cards.len(),
+            discarded_count: deck.discarded.len(),
+        }
+    }
+}
+
+impl MovedCardsRepresentation<String> {
+    /// Same as `MovedCardsRepresentation::from` but with short card codes.
+    pub fn compact(deck: &Deck, moved: Vec<Card>) -> MovedCardsRepresentation<String> {
+        MovedCardsRepresentation {
+            id: deck.id,
+            moved: CompactDeckRepresentation::translate_cards(&moved),
+            cards_count: deck.cards.len(),
+            discarded_count: deck.discarded.len(),
+        }
     }
 }
@@ -0,0 +1,140 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use actix_web::{Error, HttpMessage, HttpRequest};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use futures::FutureExt;
+use jeu_de_carte::api_key::ApiKey;
+use jeu_de_carte::i18n::Message;
+use jeu_de_carte::rate_limit::{self, Decision, Limit, Quota, RateLimiter};
+use crate::auth::service_language;
+use crate::controller::Failure;
+
+const RATE_LIMIT_LIMIT: &str = "ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "ratelimit-reset";
+
+/// Identifies the client of a request by its API key, or by its address.
+fn client(req: &ServiceRequest) -> String {
+    client_key(req.extensions().get::<ApiKey>(), req.peer_addr())
+}
+
+/// Identifies the client of a request already authenticated, such as the one opening a socket.
+pub(crate) fn request_client(req: &HttpRequest) -> String {
+    client_key(req.extensions().get::<ApiKey>(), req.peer_addr())
+}
+
+fn client_key(api_key: Option<&ApiKey>, address: Option<SocketAddr>) -> String {
+    match api_key {
+        Some(api_key) => format!("key:{}", api_key.id),
+        None => format!("ip:{}", address.map(|address| address.ip().to_string()).unwrap_or_default())
+    }
+}
+
+/// Identifies the client of a request by its address.
+pub(crate) fn address(req: &ServiceRequest) -> String {
+    client_key(None, req.peer_addr())
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(HeaderName::from_static(RATE_LIMIT_LIMIT), HeaderValue::from(decision.limit));
+    headers.insert(HeaderName::from_static(RATE_LIMIT_REMAINING), HeaderValue::from(decision.remaining));
+    headers.insert(HeaderName::from_static(RATE_LIMIT_RESET), HeaderValue::from(decision.reset));
+}
+
+pub(crate) fn too_many_requests(req: &ServiceRequest, decision: &Decision) -> Error {
+    let language = service_language(req);
+    let mut response = Failure::new(StatusCode::TOO_MANY_REQUESTS, Message::TooManyRequests).respond(language);
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(decision.retry_after));
+    insert_headers(response.headers_mut(), decision);
+    InternalError::from_response("", response).into()
+}
+
+/// Limits the requests of each client with token buckets, the requests creating decks having their own limit.
+///
+/// Runs after the authentication, the clients with an API key being limited by key rather than by address,
+/// the authentication limiting the failed attempts by address on its own.
+#[derive(Clone)]
+pub struct RateLimiting {
+    creation: Option<Arc<RateLimiter>>,
+    mutation: Option<Arc<RateLimiter>>,
+}
+
+impl RateLimiting {
+    /// Reads the limits from the `RATE_LIMIT_CREATION` and `RATE_LIMIT_MUTATION` environment variables.
+    pub fn from_env() -> RateLimiting {
+        let limiter = |name: &str, default: Limit| Limit::from_env(name, default).map(|limit| Arc::new(RateLimiter::new(limit)));
+        RateLimiting {
+            creation: limiter("RATE_LIMIT_CREATION", rate_limit::DEFAULT_CREATION_LIMIT),
+            mutation: limiter("RATE_LIMIT_MUTATION", rate_limit::DEFAULT_MUTATION_LIMIT),
+        }
+    }
+
+    /// The limiter of the requests changing something, also counting the commands sent over the sockets.
+    pub(crate) fn mutation(&self) -> Option<Arc<RateLimiter>> {
+        self.mutation.clone()
+    }
+}
+
+impl<S, B> Transform<S> for RateLimiting
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitingMiddleware { service, limits: self.clone() })
+    }
+}
+
+pub struct RateLimitingMiddleware<S> {
+    service: S,
+    limits: RateLimiting,
+}
+
+impl<S, B> Service for RateLimitingMiddleware<S>
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let limiter = match rate_limit::quota(req.method().as_str(), req.path()) {
+            Some(Quota::Creation) => self.limits.creation.as_ref(),
+            Some(Quota::Mutation) => self.limits.mutation.as_ref(),
+            None => None
+        };
+        let decision = match limiter {
+            Some(limiter) => limiter.check(&client(&req), Instant::now()),
+            None => return self.service.call(req).boxed_local()
+        };
+        if !decision.allowed {
+            return futures::future::err(too_many_requests(&req, &decision)).boxed_local();
+        }
+
+        self.service.call(req)
+            .map(move |response| response.map(|mut response| {
+                insert_headers(response.headers_mut(), &decision);
+                response
+            }))
+            .boxed_local()
+    }
+}
@@ -0,0 +1,76 @@
+use std::env;
+use tracing::span::EnteredSpan;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+/// The header carrying the ID of a request, taken from the client when it sends a valid one and echoed in the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// The log level used when `LOG_LEVEL` is missing or invalid.
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+/// The longest request ID accepted from a client.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Writes the logs as JSON lines on the standard output, along with the spans they were written in.
+///
+/// The `LOG_LEVEL` environment variable filters them, either with a level such as `debug`
+/// or with directives such as `info,jeu_de_carte=debug`.
+pub fn init() {
+    let filter = env::var("LOG_LEVEL")
+        .ok()
+        .and_then(|level| EnvFilter::try_new(level).ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_LOG_LEVEL));
+
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_current_span(true)
+        .with_span_list(true)
+        .init();
+}
+
+/// Gives the ID of a request, the one sent by the client being kept when it is safe to log and to echo.
+///
+/// # Arguments
+///
+/// * `header`: The value of the `X-Request-Id` header sent by the client, if any.
+///
+/// returns: String, the ID sent by the client, or a new UUID.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::logging::request_id;
+/// use uuid::Uuid;
+///
+/// fn main() {
+///     assert_eq!(request_id(Some("checkout-42")), "checkout-42");
+///     assert!(Uuid::parse_str(&request_id(None)).is_ok());
+///     assert!(Uuid::parse_str(&request_id(Some("with spaces"))).is_ok());
+/// }
+/// ```
+pub fn request_id(header: Option<&str>) -> String {
+    match header {
+        Some(id) if is_valid_request_id(id) => id.to_string(),
+        _ => Uuid::new_v4().to_string()
+    }
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Enters the span of an operation on a deck, the logs written until it is dropped telling the operation and the ID of the deck.
+///
+/// # Arguments
+///
+/// * `operation`: The operation, such as `shuffle` or `update`.
+/// * `deck_id`: The ID of the deck.
+///
+/// returns: EnteredSpan, leaving the span when dropped.
+pub fn deck_span(operation: &'static str, deck_id: Uuid) -> EnteredSpan {
+    let span = tracing::info_span!("deck", operation, deck_id = %deck_id).entered();
+    tracing::debug!("deck operation");
+    span
+}
@@ -1,13 +1,110 @@
+mod auth;
 mod controller;
+mod docs;
+mod limiter;
+mod socket;
+mod telemetry;
+mod v2;
 use dotenv::dotenv;
+use jeu_de_carte::events::EventHub;
+use jeu_de_carte::logging;
 use std::env;
+use actix_web::web;
+
+/// Declares a function registering routes, along with the table of their methods and paths
+/// which the documentation is checked against.
+macro_rules! routes {
+    ($(#[$doc:meta])* $configure:ident, $table:ident, { $($method:ident $path:literal => $handler:path,)* }) => {
+        $(#[$doc])*
+        fn $configure(cfg: &mut web::ServiceConfig) {
+            $(cfg.route($path, web::$method().to($handler));)*
+        }
+
+        /// The methods and paths of the routes registered by the function of the same name.
+        #[allow(dead_code)]
+        const $table: &[(&str, &str)] = &[$((stringify!($method), $path),)*];
+    };
+}
+
+routes!(
+    /// The routes of the version 1 of the API, also served without prefix for the clients written before the versions.
+    v1_routes, V1_ROUTES, {
+        post "/cards" => controller::post_cards,
+        get "/cards/{id}" => controller::get_cards,
+        post "/cards/{id}/shuffle" => controller::shuffle_cards,
+        post "/cards/{id}/take" => controller::take_cards,
+        post "/cards/{id}/put" => controller::put_cards,
+        post "/cards/{id}/move" => controller::move_cards,
+        post "/cards/{id}/batch" => controller::batch_cards,
+        delete "/cards/{id}" => controller::delete_cards,
+        get "/cards/{id}/socket" => socket::deck_socket,
+        get "/cards/{id}/events/stream" => controller::stream_deck_events,
+        post "/cards/{id}/tokens" => controller::post_deck_token,
+        post "/cards/{id}/grants" => controller::grant_deck,
+        delete "/cards/{id}/grants/{api_key_id}" => controller::revoke_deck,
+        post "/admin/api_keys" => controller::post_api_key,
+        delete "/admin/api_keys/{id}" => controller::delete_api_key,
+        post "/webhooks" => controller::post_webhook,
+        get "/webhooks/{id}" => controller::get_webhook,
+        delete "/webhooks/{id}" => controller::delete_webhook,
+        get "/webhooks/{id}/dead_letters" => controller::get_webhook_dead_letters,
+        get "/assets/cards/{code}.svg" => controller::get_card_image,
+        post "/evaluate/poker" => controller::evaluate_poker,
+        post "/odds/holdem" => controller::holdem_odds,
+        post "/blackjack/tables" => controller::post_blackjack_table,
+        get "/blackjack/tables/{id}" => controller::get_blackjack_table,
+        post "/blackjack/tables/{id}/deal" => controller::deal_blackjack,
+        post "/blackjack/tables/{id}/insurance" => controller::insure_blackjack,
+        post "/blackjack/tables/{id}/{action}" => controller::play_blackjack,
+        post "/belote/games" => controller::post_belote_game,
+        get "/belote/games/{id}" => controller::get_belote_game,
+        post "/belote/games/{id}/deal" => controller::deal_belote,
+        post "/belote/games/{id}/bid" => controller::bid_belote,
+        post "/belote/games/{id}/play" => controller::play_belote,
+        post "/belote/games/{id}/tokens" => controller::post_belote_token,
+        post "/games" => controller::post_game,
+        get "/games/{id}" => controller::get_game,
+        post "/games/{id}/join" => controller::join_game,
+        post "/games/{id}/tokens" => controller::post_game_token,
+        post "/games/{id}/take" => controller::take_game_cards,
+        post "/games/{id}/end_turn" => controller::end_game_turn,
+        get "/games/{id}/socket" => socket::game_socket,
+    }
+);
+
+routes!(
+    /// The routes of the version 2 of the API, taking JSON bodies and answering structured errors.
+    v2_routes, V2_ROUTES, {
+        post "/decks" => v2::post_deck,
+        get "/decks/{id}" => v2::get_deck,
+        delete "/decks/{id}" => v2::delete_deck,
+        post "/decks/{id}/shuffle" => v2::shuffle_deck,
+        post "/decks/{id}/take" => v2::take_cards,
+        post "/decks/{id}/put" => v2::put_cards,
+        post "/decks/{id}/move" => v2::move_cards,
+        post "/decks/{id}/deal" => v2::deal_cards,
+        post "/decks/{id}/operations" => v2::apply_operations,
+    }
+);
+
+routes!(
+    /// The routes outside of the versions: the documentation, the metrics and the health checks.
+    root_routes, ROOT_ROUTES, {
+        get "/openapi.json" => docs::get_openapi,
+        get "/docs" => docs::get_docs,
+        get "/metrics" => telemetry::get_metrics,
+        get "/health/live" => telemetry::get_liveness,
+        get "/health/ready" => telemetry::get_readiness,
+    }
+);
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    use actix_web::{App, HttpServer, web};
+    use actix_web::{App, HttpServer};
     use actix_cors::Cors;
 
     dotenv().ok(); // Load .env file
+    logging::init();
 
     let api_host = env::var("HOST")
         .expect("HOST must be set");
@@ -15,21 +112,32 @@ async fn main() -> std::io::Result<()> {
     let api_port = env::var("PORT")
         .expect("PORT must be set");
 
-    HttpServer::new(|| {
+    let hub = web::Data::new(EventHub::new());
+    let rate_limiting = limiter::RateLimiting::from_env();
+    let authentication = auth::Authentication::from_env();
+
+    HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_method()
             .allow_any_origin();
 
         App::new()
+            .wrap(controller::error_handlers())
+            .wrap(rate_limiting.clone())
+            .wrap(authentication.clone())
             .wrap(cors)
-            .route("/cards", web::post().to(controller::post_cards))
-            .route("/cards/{id}", web::get().to(controller::get_cards))
-            .route("/cards/{id}/shuffle", web::post().to(controller::shuffle_cards))
-            .route("/cards/{id}/take", web::post().to(controller::take_cards))
-            .route("/cards/{id}/put", web::post().to(controller::put_cards))
-            .route("/cards/{id}", web::delete().to(controller::delete_cards))
+            .wrap(telemetry::Metrics)
+            .wrap(telemetry::RequestTracing)
+            .app_data(hub.clone())
+            .app_data(web::Data::new(rate_limiting.clone()))
+            .app_data(controller::query_config())
+            .app_data(controller::json_config())
+            .service(web::scope("/v1").configure(v1_routes))
+            .service(web::scope("/v2").configure(v2_routes))
+            .configure(v1_routes)
+            .configure(root_routes)
     })
-        .bind(&[ api_host, api_port ].join(":"))?
+        .bind([ api_host, api_port ].join(":"))?
         .run()
         .await
 }
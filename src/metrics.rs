@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Instant;
+
+/// The content type of the metrics, in the text format of Prometheus.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// The default buckets of the histograms, in seconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub static HTTP_REQUESTS: Counter = Counter::new("http_requests_total", "The HTTP requests answered.", &["method", "route", "status"]);
+pub static HTTP_REQUEST_DURATION: Histogram = Histogram::new("http_request_duration_seconds", "The time taken to answer the HTTP requests.", &["method", "route"], DEFAULT_BUCKETS);
+pub static ERRORS: Counter = Counter::new("errors_total", "The errors answered, by code.", &["code", "status"]);
+pub static DECKS_CREATED: Counter = Counter::new("decks_created_total", "The decks created.", &[]);
+pub static DECKS_DELETED: Counter = Counter::new("decks_deleted_total", "The decks deleted.", &[]);
+pub static CARDS_DRAWN: Counter = Counter::new("cards_drawn_total", "The cards drawn from the decks.", &[]);
+pub static SHUFFLES: Counter = Counter::new("deck_shuffles_total", "The shuffles of the decks.", &[]);
+pub static DB_QUERY_DURATION: Histogram = Histogram::new("db_query_duration_seconds", "The time taken by the database queries.", &["query"], DEFAULT_BUCKETS);
+pub static DB_CONNECTIONS_OPENED: Counter = Counter::new("db_connections_opened_total", "The connections opened to the database.", &[]);
+pub static DB_CONNECTION_ERRORS: Counter = Counter::new("db_connection_errors_total", "The connections to the database which failed.", &[]);
+pub static DB_CONNECTIONS_OPEN: Gauge = Gauge::new("db_connections_open", "The connections open on the database when the metrics were gathered.");
+pub static DB_CONNECTIONS_MAX: Gauge = Gauge::new("db_connections_max", "The connections the database accepts at most.");
+
+/// Writes every metric in the text format of Prometheus.
+pub fn render() -> String {
+    let mut output = String::new();
+    HTTP_REQUESTS.render(&mut output);
+    HTTP_REQUEST_DURATION.render(&mut output);
+    ERRORS.render(&mut output);
+    DECKS_CREATED.render(&mut output);
+    DECKS_DELETED.render(&mut output);
+    CARDS_DRAWN.render(&mut output);
+    SHUFFLES.render(&mut output);
+    DB_QUERY_DURATION.render(&mut output);
+    DB_CONNECTIONS_OPENED.render(&mut output);
+    DB_CONNECTION_ERRORS.render(&mut output);
+    DB_CONNECTIONS_OPEN.render(&mut output);
+    DB_CONNECTIONS_MAX.render(&mut output);
+    output
+}
+
+/// Runs a database query, observing the time it takes and logging it in the current spans.
+///
+/// # Arguments
+///
+/// * `query`: The name of the query, such as `find_deck`.
+/// * `run`: The query.
+///
+/// returns: T, the result of the query.
+pub fn time_query<T, F: FnOnce() -> T>(query: &str, run: F) -> T {
+    let start = Instant::now();
+    let result = run();
+    let elapsed = start.elapsed();
+    DB_QUERY_DURATION.observe(&[query], elapsed.as_secs_f64());
+    tracing::debug!(query, duration_ms = elapsed.as_secs_f64() * 1000.0, "database query");
+    result
+}
+
+/// A value which only goes up, one per combination of labels.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Counter {
+        Counter { name, help, labels, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn inc(&self, labels: &[&str]) {
+        self.inc_by(labels, 1);
+    }
+
+    pub fn inc_by(&self, labels: &[&str], value: u64) {
+        let mut values = self.values.lock().expect("An error occurred.");
+        *values.entry(label_values(labels)).or_insert(0) += value;
+    }
+
+    pub fn get(&self, labels: &[&str]) -> u64 {
+        let values = self.values.lock().expect("An error occurred.");
+        values.get(&label_values(labels)).copied().unwrap_or(0)
+    }
+
+    /// Writes the counter in the text format of Prometheus.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::metrics::Counter;
+    ///
+    /// fn main() {
+    ///     let counter = Counter::new("requests_total", "The requests.", &["method"]);
+    ///     counter.inc(&["GET"]);
+    ///     counter.inc_by(&["POST"], 2);
+    ///     let mut output = String::new();
+    ///     counter.render(&mut output);
+    ///     assert!(output.contains("# TYPE requests_total counter\n"));
+    ///     assert!(output.contains("requests_total{method=\"GET\"} 1\n"));
+    ///     assert!(output.contains("requests_total{method=\"POST\"} 2\n"));
+    /// }
+    /// ```
+    pub fn render(&self, output: &mut String) {
+        header(output, self.name, self.help, "counter");
+        let values = self.values.lock().expect("An error occurred.");
+        if values.is_empty() && self.labels.is_empty() {
+            writeln!(output, "{} 0", self.name).ok();
+        }
+        for (values, count) in values.iter() {
+            writeln!(output, "{}{} {}", self.name, format_labels(self.labels, values, None), count).ok();
+        }
+    }
+}
+
+/// A value which goes up and down.
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicI64,
+}
+
+impl Gauge {
+    pub const fn new(name: &'static str, help: &'static str) -> Gauge {
+        Gauge { name, help, value: AtomicI64::new(0) }
+    }
+
+    pub fn set(&self, value: i64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    pub fn render(&self, output: &mut String) {
+        header(output, self.name, self.help, "gauge");
+        writeln!(output, "{} {}", self.name, self.get()).ok();
+    }
+}
+
+#[derive(Default)]
+struct Observations {
+    /// How many values fell into each bucket, not counting the smaller buckets.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Values counted into buckets, along with their sum, one per combination of labels.
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    buckets: &'static [f64],
+    values: Mutex<BTreeMap<Vec<String>, Observations>>,
+}
+
+impl Histogram {
+    pub const fn new(name: &'static str, help: &'static str, labels: &'static [&'static str], buckets: &'static [f64]) -> Histogram {
+        Histogram { name, help, labels, buckets, values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        let mut values = self.values.lock().expect("An error occurred.");
+        let observations = values.entry(label_values(labels)).or_default();
+        if observations.buckets.is_empty() {
+            observations.buckets = vec![0; self.buckets.len()];
+        }
+        if let Some(index) = self.buckets.iter().position(|bound| value <= *bound) {
+            observations.buckets[index] += 1;
+        }
+        observations.sum += value;
+        observations.count += 1;
+    }
+
+    pub fn count(&self, labels: &[&str]) -> u64 {
+        let values = self.values.lock().expect("An error occurred.");
+        values.get(&label_values(labels)).map(|observations| observations.count).unwrap_or(0)
+    }
+
+    /// Writes the histogram in the text format of Prometheus, the buckets being cumulative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::metrics::Histogram;
+    ///
+    /// fn main() {
+    ///     let histogram = Histogram::new("latency_seconds", "The latency.", &["route"], &[0.1, 1.0]);
+    ///     histogram.observe(&["/cards"], 0.05);
+    ///     histogram.observe(&["/cards"], 0.5);
+    ///     histogram.observe(&["/cards"], 3.0);
+    ///     let mut output = String::new();
+    ///     histogram.render(&mut output);
+    ///     assert!(output.contains("latency_seconds_bucket{route=\"/cards\",le=\"0.1\"} 1\n"));
+    ///     assert!(output.contains("latency_seconds_bucket{route=\"/cards\",le=\"1\"} 2\n"));
+    ///     assert!(output.contains("latency_seconds_bucket{route=\"/cards\",le=\"+Inf\"} 3\n"));
+    ///     assert!(output.contains("latency_seconds_sum{route=\"/cards\"} 3.55\n"));
+    ///     assert!(output.contains("latency_seconds_count{route=\"/cards\"} 3\n"));
+    /// }
+    /// ```
+    pub fn render(&self, output: &mut String) {
+        header(output, self.name, self.help, "histogram");
+        let values = self.values.lock().expect("An error occurred.");
+        for (values, observations) in values.iter() {
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(observations.buckets.iter()) {
+                cumulative += count;
+                writeln!(output, "{}_bucket{} {}", self.name, format_labels(self.labels, values, Some(&bound.to_string())), cumulative).ok();
+            }
+            writeln!(output, "{}_bucket{} {}", self.name, format_labels(self.labels, values, Some("+Inf")), observations.count).ok();
+            writeln!(output, "{}_sum{} {}", self.name, format_labels(self.labels, values, None), observations.sum).ok();
+            writeln!(output, "{}_count{} {}", self.name, format_labels(self.labels, values, None), observations.count).ok();
+        }
+    }
+}
+
+fn label_values(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+}
+
+fn header(output: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(output, "# HELP {} {}", name, help).ok();
+    writeln!(output, "# TYPE {} {}", name, kind).ok();
+}
+
+/// Writes labels as `{name="value",...}`, with the bound of a bucket if any.
+fn format_labels(names: &[&str], values: &[String], bound: Option<&str>) -> String {
+    let mut pairs: Vec<String> = names.iter()
+        .zip(values.iter())
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+    if let Some(bound) = bound {
+        pairs.push(format!("le=\"{}\"", bound));
+    }
+
+    if pairs.is_empty() { String::new() } else { format!("{{{}}}", pairs.join(",")) }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,217 @@
+use std::convert::TryFrom;
+use std::fmt;
+use rand::seq::SliceRandom;
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::{Card, Deck, DeckSize};
+use crate::card::PlayingCard;
+use crate::i18n::{Language, Message};
+use crate::poker::{self, HandValue};
+
+/// Above this number of scenarios, the odds are estimated with Monte Carlo sampling.
+pub const EXHAUSTIVE_LIMIT: u64 = 200_000;
+pub const DEFAULT_ITERATIONS: usize = 10_000;
+pub const MAX_ITERATIONS: usize = 100_000;
+pub const MAX_OPPONENTS: usize = 9;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OddsError {
+    InvalidHoleCards(usize),
+    InvalidBoard(usize),
+    InvalidOpponents(usize),
+    UnavailableCard(PlayingCard),
+    Joker,
+}
+
+impl OddsError {
+    pub fn message(&self) -> Message {
+        match self {
+            OddsError::InvalidHoleCards(size) => Message::InvalidHoleCards(*size),
+            OddsError::InvalidBoard(size) => Message::InvalidBoard(*size),
+            OddsError::InvalidOpponents(opponents) => Message::InvalidOpponents(*opponents),
+            OddsError::UnavailableCard(card) => Message::DuplicateCard(card.to_string()),
+            OddsError::Joker => Message::JokerNotAllowed,
+        }
+    }
+}
+
+impl fmt::Display for OddsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsMethod {
+    Exhaustive,
+    MonteCarlo,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Odds {
+    pub method: OddsMethod,
+    pub scenarios: u64,
+    pub win: f64,
+    pub tie: f64,
+    pub loss: f64,
+    /// Share of the pot won on average, ties being split between the tied players.
+    pub equity: f64,
+}
+
+#[derive(Default)]
+struct Tally {
+    scenarios: u64,
+    wins: u64,
+    ties: u64,
+    equity: f64,
+}
+
+impl Tally {
+    fn record(&mut self, hero: HandValue, opponents: &[HandValue]) {
+        self.scenarios += 1;
+        let best = opponents.iter().max().copied();
+        match best {
+            Some(best) if best > hero => {}
+            Some(best) if best == hero => {
+                let tied = opponents.iter().filter(|value| **value == hero).count();
+                self.ties += 1;
+                self.equity += 1.0 / (tied as f64 + 1.0);
+            }
+            _ => {
+                self.wins += 1;
+                self.equity += 1.0;
+            }
+        }
+    }
+
+    fn odds(&self, method: OddsMethod) -> Odds {
+        let total = self.scenarios.max(1) as f64;
+        Odds {
+            method,
+            scenarios: self.scenarios,
+            win: self.wins as f64 / total,
+            tie: self.ties as f64 / total,
+            loss: (self.scenarios - self.wins - self.ties) as f64 / total,
+            equity: self.equity / total,
+        }
+    }
+}
+
+fn binomial(n: usize, k: usize) -> u64 {
+    if k > n { return 0; }
+    (0..k).fold(1u64, |result, i| result * (n - i) as u64 / (i as u64 + 1))
+}
+
+fn to_cards(motifs: &[Card]) -> Vec<PlayingCard> {
+    motifs.iter().map(|motif| PlayingCard::try_from(*motif).expect("Incorrect value.")).collect()
+}
+
+fn value(hole: &[PlayingCard], board: &[PlayingCard]) -> HandValue {
+    poker::evaluate(&[hole, board].concat()).expect("Valid hand.").0
+}
+
+/// Calls `visit` with every combination of `size` cards among `cards`.
+fn combinations(cards: &[Card], size: usize, visit: &mut dyn FnMut(&[Card], &[Card])) {
+    fn recurse(cards: &[Card], size: usize, start: usize, chosen: &mut Vec<Card>, visit: &mut dyn FnMut(&[Card], &[Card])) {
+        if chosen.len() == size {
+            let rest: Vec<Card> = cards.iter().filter(|card| !chosen.contains(card)).copied().collect();
+            visit(chosen, &rest);
+            return;
+        }
+        for index in start..cards.len() {
+            chosen.push(cards[index]);
+            recurse(cards, size, index + 1, chosen, visit);
+            chosen.pop();
+        }
+    }
+
+    recurse(cards, size, 0, &mut Vec::with_capacity(size), visit);
+}
+
+fn enumerate_opponents(remaining: &[Card], opponents: usize, hands: &mut Vec<Vec<PlayingCard>>, visit: &mut dyn FnMut(&[Vec<PlayingCard>])) {
+    if hands.len() == opponents {
+        visit(hands);
+        return;
+    }
+    combinations(remaining, 2, &mut |hand, rest| {
+        hands.push(to_cards(hand));
+        enumerate_opponents(rest, opponents, hands, visit);
+        hands.pop();
+    });
+}
+
+/// Computes the odds of a Texas Hold'em hand against random opponent hands.
+///
+/// Every possible scenario is enumerated when there are at most `EXHAUSTIVE_LIMIT` of them,
+/// otherwise `iterations` random scenarios are drawn from the remaining cards.
+///
+/// # Arguments
+///
+/// * `hole`: The two hole cards of the player.
+/// * `board`: The known community cards, up to 5.
+/// * `opponents`: The number of opponents, between 1 and 9.
+/// * `iterations`: The number of Monte Carlo iterations, at most `MAX_ITERATIONS`.
+///
+/// returns: Result<Odds, OddsError>
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::card::PlayingCard;
+/// use jeu_de_carte::odds::{holdem, OddsMethod};
+///
+/// fn main() {
+///     let hole: Vec<PlayingCard> = vec!["AS".parse().unwrap(), "AH".parse().unwrap()];
+///     let board: Vec<PlayingCard> = vec!["AD".parse().unwrap(), "AC".parse().unwrap(), "KD".parse().unwrap(), "2C".parse().unwrap(), "7H".parse().unwrap()];
+///     let odds = holdem(&hole, &board, 1, 1000).unwrap();
+///     assert_eq!(odds.method, OddsMethod::Exhaustive);
+///     assert_eq!(odds.win, 1.0);
+/// }
+/// ```
+pub fn holdem(hole: &[PlayingCard], board: &[PlayingCard], opponents: usize, iterations: usize) -> Result<Odds, OddsError> {
+    if hole.len() != 2 { return Err(OddsError::InvalidHoleCards(hole.len())); }
+    if board.len() > 5 { return Err(OddsError::InvalidBoard(board.len())); }
+    if opponents == 0 || opponents > MAX_OPPONENTS { return Err(OddsError::InvalidOpponents(opponents)); }
+
+    let mut deck = Deck::new(DeckSize::Normal);
+    for card in hole.iter().chain(board.iter()) {
+        if card.rank().is_none() { return Err(OddsError::Joker); }
+        deck.move_cards(&[card.motif()], true).map_err(|_| OddsError::UnavailableCard(*card))?;
+    }
+    deck.discarded.clear();
+
+    let missing = 5 - board.len();
+    let mut scenarios = binomial(deck.cards.len(), missing);
+    for opponent in 0..opponents {
+        scenarios = scenarios.saturating_mul(binomial(deck.cards.len() - missing - 2 * opponent, 2));
+    }
+
+    let mut tally = Tally::default();
+    if scenarios <= EXHAUSTIVE_LIMIT {
+        combinations(&deck.cards, missing, &mut |extra, rest| {
+            let full_board = [board, &to_cards(extra)].concat();
+            let hero = value(hole, &full_board);
+            enumerate_opponents(rest, opponents, &mut Vec::with_capacity(opponents), &mut |hands| {
+                let values: Vec<HandValue> = hands.iter().map(|hand| value(hand, &full_board)).collect();
+                tally.record(hero, &values);
+            });
+        });
+
+        Ok(tally.odds(OddsMethod::Exhaustive))
+    } else {
+        let mut rng = rand::thread_rng();
+        let mut remaining = deck.cards;
+        for _ in 0..iterations.clamp(1, MAX_ITERATIONS) {
+            let (sample, _) = remaining.partial_shuffle(&mut rng, 2 * opponents + missing);
+            let (hands, extra) = sample.split_at(2 * opponents);
+
+            let full_board = [board, &to_cards(extra)].concat();
+            let hero = value(hole, &full_board);
+            let values: Vec<HandValue> = hands.chunks(2).map(|hand| value(&to_cards(hand), &full_board)).collect();
+            tally.record(hero, &values);
+        }
+
+        Ok(tally.odds(OddsMethod::MonteCarlo))
+    }
+}
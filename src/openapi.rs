@@ -0,0 +1,180 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use crate::{Problem, PROBLEM_CONTENT_TYPE};
+
+/// The version of the OpenAPI specification followed by the documents.
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+enum Content {
+    Empty,
+    Json(SchemaFn, &'static str),
+    Media(&'static str),
+}
+
+/// An operation of the API, described from the Rust types of its parameters and of its answers.
+pub struct Operation {
+    id: &'static str,
+    summary: &'static str,
+    queries: Vec<SchemaFn>,
+    body: Option<SchemaFn>,
+    responses: Vec<(u16, &'static str, Content)>,
+}
+
+impl Operation {
+    pub fn new(id: &'static str, summary: &'static str) -> Operation {
+        Operation { id, summary, queries: Vec::new(), body: None, responses: Vec::new() }
+    }
+
+    /// Documents the query parameters from the fields of a query struct, several structs being able to be given.
+    pub fn query<T: JsonSchema>(mut self) -> Operation {
+        self.queries.push(T::json_schema);
+        self
+    }
+
+    /// Documents the JSON body of the request.
+    pub fn body<T: JsonSchema>(mut self) -> Operation {
+        self.body = Some(SchemaGenerator::subschema_for::<T>);
+        self
+    }
+
+    /// Documents a JSON answer.
+    pub fn response<T: JsonSchema>(mut self, status: u16, description: &'static str) -> Operation {
+        self.responses.push((status, description, Content::Json(SchemaGenerator::subschema_for::<T>, "application/json")));
+        self
+    }
+
+    /// Documents an answer without body.
+    pub fn empty(mut self, status: u16, description: &'static str) -> Operation {
+        self.responses.push((status, description, Content::Empty));
+        self
+    }
+
+    /// Documents an answer which isn't JSON, such as an image or a stream.
+    pub fn media(mut self, status: u16, description: &'static str, content_type: &'static str) -> Operation {
+        self.responses.push((status, description, Content::Media(content_type)));
+        self
+    }
+
+    /// Documents an error answered with a `Problem`.
+    pub fn error(mut self, status: u16, description: &'static str) -> Operation {
+        self.responses.push((status, description, Content::Json(SchemaGenerator::subschema_for::<Problem>, PROBLEM_CONTENT_TYPE)));
+        self
+    }
+
+    /// Documents an error unless the status is already documented.
+    fn default_error(self, status: u16, description: &'static str) -> Operation {
+        if self.responses.iter().any(|(documented, _, _)| *documented == status) { self } else { self.error(status, description) }
+    }
+}
+
+/// Builds an OpenAPI 3 document, the schemas of the Rust types being shared in its components.
+pub struct ApiDoc {
+    title: &'static str,
+    version: &'static str,
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+impl ApiDoc {
+    pub fn new(title: &'static str, version: &'static str) -> ApiDoc {
+        ApiDoc { title, version, generator: SchemaSettings::openapi3().into_generator(), paths: Map::new() }
+    }
+
+    /// Adds an operation to the document, along with the errors answered when its parameters or its body can't be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `method`: The HTTP method, in lowercase.
+    /// * `path`: The path of the route as declared with actix, such as `/cards/{id}`.
+    /// * `operation`: The description of the operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::DeckRepresentation;
+    /// use jeu_de_carte::openapi::{ApiDoc, Operation};
+    ///
+    /// fn main() {
+    ///     let mut doc = ApiDoc::new("Card Game API", "1.0.0");
+    ///     doc.add("get", "/cards/{id}", Operation::new("get_cards", "Gets a deck.")
+    ///         .response::<DeckRepresentation>(200, "The deck."));
+    ///     let document = doc.to_json();
+    ///     let operation = &document["paths"]["/cards/{id}"]["get"];
+    ///     assert_eq!(operation["parameters"][0]["in"], "path");
+    ///     assert_eq!(operation["responses"]["200"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/DeckRepresentation");
+    ///     assert_eq!(operation["responses"]["400"]["content"]["application/problem+json"]["schema"]["$ref"], "#/components/schemas/Problem");
+    ///     assert!(document["components"]["schemas"]["DeckRepresentation"].is_object());
+    /// }
+    /// ```
+    pub fn add(&mut self, method: &str, path: &str, mut operation: Operation) {
+        let mut parameters: Vec<Value> = path_parameters(path);
+        for query in operation.queries.iter() {
+            parameters.extend(query_parameters(query(&mut self.generator)));
+        }
+        if !parameters.is_empty() || operation.body.is_some() {
+            operation = operation.default_error(400, "A parameter is missing or invalid.");
+        }
+        if operation.body.is_some() {
+            operation = operation.default_error(422, "The body is invalid.");
+        }
+
+        let mut value = json!({
+            "operationId": operation.id,
+            "summary": operation.summary,
+            "responses": {},
+        });
+        if !parameters.is_empty() {
+            value["parameters"] = Value::Array(parameters);
+        }
+        if let Some(body) = operation.body {
+            value["requestBody"] = json!({ "required": true, "content": { "application/json": { "schema": body(&mut self.generator) } } });
+        }
+        for (status, description, content) in operation.responses {
+            value["responses"][status.to_string()] = match content {
+                Content::Empty => json!({ "description": description }),
+                Content::Json(schema, content_type) => json!({ "description": description, "content": { content_type: { "schema": schema(&mut self.generator) } } }),
+                Content::Media(content_type) => json!({ "description": description, "content": { content_type: {} } }),
+            };
+        }
+
+        let item = self.paths.entry(path.to_string()).or_insert_with(|| json!({}));
+        item[method] = value;
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": { "title": self.title, "version": self.version },
+            "paths": self.paths,
+            "components": { "schemas": self.generator.definitions() },
+        })
+    }
+}
+
+/// Documents the parameters of a path, the ids being UUIDs.
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.split('}').next())
+        .map(|name| {
+            let schema = if name == "id" || name.ends_with("_id") { json!({ "type": "string", "format": "uuid" }) } else { json!({ "type": "string" }) };
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect()
+}
+
+/// Turns the properties of a query struct into query parameters.
+fn query_parameters(schema: Schema) -> Vec<Value> {
+    let object = match schema {
+        Schema::Object(SchemaObject { object: Some(object), .. }) => object,
+        _ => return Vec::new()
+    };
+
+    object.properties
+        .iter()
+        .map(|(name, property)| json!({ "name": name, "in": "query", "required": object.required.contains(name), "schema": property }))
+        .collect()
+}
@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::card::{PlayingCard, Rank, Suit};
+use crate::i18n::{Language, Message};
+
+/// Number of distinct 5-card poker hand values.
+pub const HAND_CLASSES: u16 = 7462;
+
+const PRIMES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+/// The value of a poker hand, comparable with the other hand values: the stronger hand is the greater.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandValue {
+    strength: u16,
+}
+
+impl HandValue {
+    /// The rank of the hand among the 7462 distinct hand values, 1 being a royal flush and 7462 the worst high card.
+    pub fn rank(self) -> u16 {
+        HAND_CLASSES + 1 - self.strength
+    }
+
+    pub fn category(self) -> HandCategory {
+        match self.rank() {
+            1..=10 => HandCategory::StraightFlush,
+            11..=166 => HandCategory::FourOfAKind,
+            167..=322 => HandCategory::FullHouse,
+            323..=1599 => HandCategory::Flush,
+            1600..=1609 => HandCategory::Straight,
+            1610..=2467 => HandCategory::ThreeOfAKind,
+            2468..=3325 => HandCategory::TwoPair,
+            3326..=6185 => HandCategory::OnePair,
+            _ => HandCategory::HighCard,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PokerError {
+    InvalidHandSize(usize),
+    DuplicateCard(PlayingCard),
+    Joker,
+}
+
+impl PokerError {
+    pub fn message(&self) -> Message {
+        match self {
+            PokerError::InvalidHandSize(size) => Message::InvalidHandSize(*size),
+            PokerError::DuplicateCard(card) => Message::DuplicateCard(card.to_string()),
+            PokerError::Joker => Message::JokerNotAllowed,
+        }
+    }
+}
+
+impl fmt::Display for PokerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+/// The rank of a card for poker, from 0 for a two to 12 for an ace.
+fn poker_rank(rank: Rank) -> usize {
+    (rank.index() as usize + 12) % 13
+}
+
+/// A hand class: its ordering key, its ranks and whether it is suited.
+type HandClass = ((HandCategory, Vec<usize>), [usize; 5], bool);
+
+struct Tables {
+    flushes: Vec<u16>,
+    unique: Vec<u16>,
+    products: HashMap<u32, u16>,
+}
+
+/// Classifies 5 poker ranks the slow way, as a key whose ordering is the ordering of the hands.
+fn classify(ranks: &[usize; 5], flush: bool) -> (HandCategory, Vec<usize>) {
+    let mut counts = [0usize; 13];
+    for rank in ranks {
+        counts[*rank] += 1;
+    }
+
+    let mut groups: Vec<(usize, usize)> = (0..13).rev()
+        .filter(|rank| counts[*rank] > 0)
+        .map(|rank| (counts[rank], rank))
+        .collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    let kickers: Vec<usize> = groups.iter().map(|(_, rank)| *rank).collect();
+
+    let straight_high = if groups.len() == 5 {
+        if kickers[0] - kickers[4] == 4 {
+            Some(kickers[0])
+        } else if kickers == [12, 3, 2, 1, 0] {
+            Some(3)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let category = match (straight_high, flush, groups[0].0, groups.get(1).map(|group| group.0)) {
+        (Some(_), true, _, _) => HandCategory::StraightFlush,
+        (_, _, 4, _) => HandCategory::FourOfAKind,
+        (_, _, 3, Some(2)) => HandCategory::FullHouse,
+        (_, true, _, _) => HandCategory::Flush,
+        (Some(_), false, _, _) => HandCategory::Straight,
+        (_, _, 3, _) => HandCategory::ThreeOfAKind,
+        (_, _, 2, Some(2)) => HandCategory::TwoPair,
+        (_, _, 2, _) => HandCategory::OnePair,
+        _ => HandCategory::HighCard,
+    };
+
+    match straight_high {
+        Some(high) if category == HandCategory::Straight || category == HandCategory::StraightFlush => (category, vec![high]),
+        _ => (category, kickers),
+    }
+}
+
+/// Builds the lookup tables from every multiset of 5 ranks, sorted by strength.
+fn build_tables() -> Tables {
+    let mut classes: Vec<HandClass> = Vec::with_capacity(HAND_CLASSES as usize);
+    for a in 0..13 {
+        for b in a..13 {
+            for c in b..13 {
+                for d in c..13 {
+                    for e in d..13 {
+                        if a == e { continue; }
+                        let ranks = [a, b, c, d, e];
+                        classes.push((classify(&ranks, false), ranks, false));
+                        if a < b && b < c && c < d && d < e {
+                            classes.push((classify(&ranks, true), ranks, true));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    classes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut tables = Tables { flushes: vec![0; 1 << 13], unique: vec![0; 1 << 13], products: HashMap::new() };
+    for (index, (_, ranks, flush)) in classes.iter().enumerate() {
+        let strength = index as u16 + 1;
+        let mask: usize = ranks.iter().map(|rank| 1 << rank).fold(0, |mask, bit| mask | bit);
+        if *flush {
+            tables.flushes[mask] = strength;
+        } else if mask.count_ones() == 5 {
+            tables.unique[mask] = strength;
+        } else {
+            let product = ranks.iter().map(|rank| PRIMES[*rank]).product();
+            tables.products.insert(product, strength);
+        }
+    }
+
+    tables
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(build_tables)
+}
+
+fn check_cards(cards: &[PlayingCard]) -> Result<Vec<(Rank, Suit)>, PokerError> {
+    let mut checked: Vec<(Rank, Suit)> = Vec::with_capacity(cards.len());
+    for card in cards {
+        let card = match card {
+            PlayingCard::Standard { rank, suit } => (*rank, *suit),
+            _ => return Err(PokerError::Joker)
+        };
+        if checked.contains(&card) {
+            return Err(PokerError::DuplicateCard(PlayingCard::new(card.0, card.1)));
+        }
+        checked.push(card);
+    }
+
+    Ok(checked)
+}
+
+fn lookup(cards: &[(Rank, Suit); 5]) -> HandValue {
+    let tables = tables();
+    let mask = cards.iter().fold(0usize, |mask, (rank, _)| mask | 1 << poker_rank(*rank));
+    let flush = cards.iter().all(|(_, suit)| *suit == cards[0].1);
+
+    let strength = if flush {
+        tables.flushes[mask]
+    } else if mask.count_ones() == 5 {
+        tables.unique[mask]
+    } else {
+        let product: u32 = cards.iter().map(|(rank, _)| PRIMES[poker_rank(*rank)]).product();
+        tables.products[&product]
+    };
+
+    HandValue { strength }
+}
+
+/// Evaluates a hand of 5 to 7 cards, keeping the best 5 cards.
+///
+/// # Arguments
+///
+/// * `cards`: The cards of the hand, e.g. the two hole cards and the board in Texas Hold'em.
+///
+/// returns: Result<(HandValue, Vec<PlayingCard>), PokerError>, the value of the hand and its best 5 cards.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::card::PlayingCard;
+/// use jeu_de_carte::poker::{evaluate, HandCategory};
+///
+/// fn main() {
+///     let cards: Vec<PlayingCard> = ["AS", "KS", "QS", "JS", "10S", "2D", "2C"]
+///         .iter()
+///         .map(|code| code.parse().unwrap())
+///         .collect();
+///     let (value, best) = evaluate(&cards).unwrap();
+///     assert_eq!(value.category(), HandCategory::StraightFlush);
+///     assert_eq!(value.rank(), 1);
+///     assert_eq!(best.len(), 5);
+/// }
+/// ```
+pub fn evaluate(cards: &[PlayingCard]) -> Result<(HandValue, Vec<PlayingCard>), PokerError> {
+    if cards.len() < 5 || cards.len() > 7 {
+        return Err(PokerError::InvalidHandSize(cards.len()));
+    }
+    let checked = check_cards(cards)?;
+
+    let mut best: Option<(HandValue, [usize; 5])> = None;
+    let n = checked.len();
+    for a in 0..n {
+        for b in a + 1..n {
+            for c in b + 1..n {
+                for d in c + 1..n {
+                    for e in d + 1..n {
+                        let indexes = [a, b, c, d, e];
+                        let value = lookup(&indexes.map(|index| checked[index]));
+                        if best.is_none_or(|(best_value, _)| value > best_value) {
+                            best = Some((value, indexes));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let (value, indexes) = best.expect("At least one combination.");
+    Ok((value, indexes.iter().map(|index| cards[*index]).collect()))
+}
+
+/// Compares two hands of 5 to 7 cards.
+pub fn compare(first: &[PlayingCard], second: &[PlayingCard]) -> Result<Ordering, PokerError> {
+    Ok(evaluate(first)?.0.cmp(&evaluate(second)?.0))
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HandRepresentation {
+    pub category: HandCategory,
+    pub rank: u16,
+    pub best: Vec<String>,
+}
+
+impl HandRepresentation {
+    pub fn from(value: HandValue, best: Vec<PlayingCard>) -> HandRepresentation {
+        HandRepresentation {
+            category: value.category(),
+            rank: value.rank(),
+            best: best.iter().map(PlayingCard::to_string).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct EvaluationRepresentation {
+    pub hands: Vec<HandRepresentation>,
+    pub winners: Vec<usize>,
+}
+
+impl EvaluationRepresentation {
+    /// Evaluates each hand along with the shared board cards and finds the winning hands,
+    /// a card being dealt only once across the hands and the board.
+    pub fn evaluate(hands: &[Vec<PlayingCard>], board: &[PlayingCard]) -> Result<EvaluationRepresentation, PokerError> {
+        check_cards(&[hands.concat().as_slice(), board].concat())?;
+        let mut values = Vec::with_capacity(hands.len());
+        let mut representations = Vec::with_capacity(hands.len());
+        for hand in hands {
+            let cards = [hand.as_slice(), board].concat();
+            let (value, best) = evaluate(&cards)?;
+            values.push(value);
+            representations.push(HandRepresentation::from(value, best));
+        }
+
+        let winners = match values.iter().max() {
+            Some(max) => (0..values.len()).filter(|index| values[*index] == *max).collect(),
+            None => Vec::new()
+        };
+
+        Ok(EvaluationRepresentation { hands: representations, winners })
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The default limit of the requests creating decks, per client.
+pub const DEFAULT_CREATION_LIMIT: Limit = Limit { capacity: 10, period: Duration::from_secs(60) };
+/// The default limit of the other requests changing something, per client.
+pub const DEFAULT_MUTATION_LIMIT: Limit = Limit { capacity: 300, period: Duration::from_secs(60) };
+/// The default limit of the failed authentications, per address.
+pub const DEFAULT_AUTHENTICATION_LIMIT: Limit = Limit { capacity: 20, period: Duration::from_secs(60) };
+/// The default number of live decks an API key may own.
+pub const DEFAULT_MAX_DECKS: usize = 1000;
+/// How many clients are tracked before forgetting the ones whose bucket is full again,
+/// then the ones seen the longest time ago.
+pub const MAX_CLIENTS: usize = 10_000;
+
+/// The quota a request counts against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quota {
+    /// The requests creating decks, directly or for a game.
+    Creation,
+    /// The other requests changing something.
+    Mutation,
+}
+
+/// Tells which quota a request counts against, whatever the version of the API, the reads being unlimited.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::rate_limit::{quota, Quota};
+///
+/// fn main() {
+///     assert_eq!(quota("POST", "/cards"), Some(Quota::Creation));
+///     assert_eq!(quota("POST", "/v2/decks"), Some(Quota::Creation));
+///     assert_eq!(quota("POST", "/cards/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1/take"), Some(Quota::Mutation));
+///     assert_eq!(quota("GET", "/cards/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1"), None);
+/// }
+/// ```
+pub fn quota(method: &str, path: &str) -> Option<Quota> {
+    let path = path.trim_end_matches('/');
+    let path = path.strip_prefix("/v1").or_else(|| path.strip_prefix("/v2")).unwrap_or(path);
+    match (method, path) {
+        ("GET", _) | ("HEAD", _) | ("OPTIONS", _) => None,
+        ("POST", "/cards") | ("POST", "/decks") | ("POST", "/games") | ("POST", "/blackjack/tables") | ("POST", "/belote/games") => Some(Quota::Creation),
+        _ => Some(Quota::Mutation)
+    }
+}
+
+/// A number of requests allowed over a period, written `10/60` for 10 requests a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Limit {
+    /// Reads a limit from an environment variable, `off` disabling it.
+    ///
+    /// returns: Option<Limit>, the default limit when the variable is missing or invalid, None when disabled.
+    pub fn from_env(name: &str, default: Limit) -> Option<Limit> {
+        match env::var(name) {
+            Ok(value) if value.trim() == "off" => None,
+            Ok(value) => Some(value.parse().unwrap_or(default)),
+            Err(_) => Some(default)
+        }
+    }
+
+    /// How many requests are given back per second.
+    fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl FromStr for Limit {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Limit, ()> {
+        let (capacity, seconds) = value.trim().split_once('/').ok_or(())?;
+        let capacity = capacity.trim().parse::<u32>().map_err(|_| ())?;
+        let seconds = seconds.trim().parse::<u64>().map_err(|_| ())?;
+        if capacity == 0 || seconds == 0 {
+            return Err(());
+        }
+
+        Ok(Limit { capacity, period: Duration::from_secs(seconds) })
+    }
+}
+
+/// The number of live decks an API key may own, from the `MAX_DECKS_PER_OWNER` environment variable.
+pub fn max_decks() -> usize {
+    env::var("MAX_DECKS_PER_OWNER")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_DECKS)
+}
+
+/// What a rate limiter decided for a request, along with the values of the `RateLimit-*` headers.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until all the requests are given back.
+    pub reset: u64,
+    /// Seconds until the next request is allowed, 0 when this one is.
+    pub retry_after: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket per client: each request takes a token, the tokens being given back over the period of the limit.
+pub struct RateLimiter {
+    limit: Limit,
+    max_clients: usize,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: Limit) -> RateLimiter {
+        RateLimiter::with_max_clients(limit, MAX_CLIENTS)
+    }
+
+    /// Creates a limiter tracking at most `max_clients` clients, the ones seen the longest time ago being forgotten beyond.
+    pub fn with_max_clients(limit: Limit, max_clients: usize) -> RateLimiter {
+        RateLimiter { limit, max_clients: max_clients.max(1), buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Takes a token from the bucket of a client.
+    ///
+    /// # Arguments
+    ///
+    /// * `client`: The API key or the address of the client.
+    /// * `now`: The time of the request.
+    ///
+    /// returns: Decision
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use jeu_de_carte::rate_limit::{Limit, RateLimiter};
+    ///
+    /// fn main() {
+    ///     let limiter = RateLimiter::new(Limit { capacity: 2, period: Duration::from_secs(10) });
+    ///     let now = Instant::now();
+    ///     assert!(limiter.check("client", now).allowed);
+    ///     assert!(limiter.check("client", now).allowed);
+    ///     let refused = limiter.check("client", now);
+    ///     assert!(!refused.allowed);
+    ///     assert_eq!(refused.retry_after, 5);
+    ///     assert!(limiter.check("other", now).allowed);
+    ///     assert!(limiter.check("client", now + Duration::from_secs(5)).allowed);
+    /// }
+    /// ```
+    pub fn check(&self, client: &str, now: Instant) -> Decision {
+        self.take(client, now, 1.0)
+    }
+
+    /// Tells if a client would be allowed a request, without taking a token from its bucket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use jeu_de_carte::rate_limit::{Limit, RateLimiter};
+    ///
+    /// fn main() {
+    ///     let limiter = RateLimiter::new(Limit { capacity: 1, period: Duration::from_secs(10) });
+    ///     let now = Instant::now();
+    ///     assert!(limiter.peek("client", now).allowed);
+    ///     assert!(limiter.check("client", now).allowed);
+    ///     assert!(!limiter.peek("client", now).allowed);
+    /// }
+    /// ```
+    pub fn peek(&self, client: &str, now: Instant) -> Decision {
+        self.take(client, now, 0.0)
+    }
+
+    fn take(&self, client: &str, now: Instant, cost: f64) -> Decision {
+        let capacity = self.limit.capacity as f64;
+        let rate = self.limit.refill_rate();
+        let refill = |bucket: &Bucket| (bucket.tokens + now.saturating_duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+
+        let mut buckets = self.buckets.lock().expect("An error occurred.");
+        if buckets.len() >= self.max_clients && !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| refill(bucket) < capacity);
+            if buckets.len() >= self.max_clients {
+                Self::forget_oldest(&mut buckets, self.max_clients / 2);
+            }
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= cost;
+        }
+        let retry_after = if allowed { 0 } else { ((1.0 - bucket.tokens) / rate).ceil() as u64 };
+
+        Decision {
+            allowed,
+            limit: self.limit.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset: ((capacity - bucket.tokens) / rate).ceil() as u64,
+            retry_after,
+        }
+    }
+
+    /// Forgets the clients seen the longest time ago, until only `kept` clients are left.
+    fn forget_oldest(buckets: &mut HashMap<String, Bucket>, kept: usize) {
+        let mut clients: Vec<(Instant, String)> = buckets.iter().map(|(client, bucket)| (bucket.updated, client.clone())).collect();
+        clients.sort();
+        for (_, client) in clients.iter().take(clients.len().saturating_sub(kept)) {
+            buckets.remove(client);
+        }
+    }
+}
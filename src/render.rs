@@ -0,0 +1,187 @@
+use crate::card::{PlayingCard, Rank, Suit};
+
+const WIDTH: f32 = 250.0;
+const HEIGHT: f32 = 350.0;
+
+/// Positions of the pips of each numbered card, relative to the card size.
+fn pip_positions(rank: Rank) -> Vec<(f32, f32)> {
+    const LEFT: f32 = 0.3;
+    const CENTER: f32 = 0.5;
+    const RIGHT: f32 = 0.7;
+
+    let corners = vec![(LEFT, 0.2), (RIGHT, 0.2), (LEFT, 0.8), (RIGHT, 0.8)];
+    let mut positions = match rank {
+        Rank::Two => vec![(CENTER, 0.2), (CENTER, 0.8)],
+        Rank::Three => vec![(CENTER, 0.2), (CENTER, 0.5), (CENTER, 0.8)],
+        Rank::Four => corners,
+        Rank::Five => [corners, vec![(CENTER, 0.5)]].concat(),
+        Rank::Six => [corners, vec![(LEFT, 0.5), (RIGHT, 0.5)]].concat(),
+        Rank::Seven => [corners, vec![(LEFT, 0.5), (RIGHT, 0.5), (CENTER, 0.35)]].concat(),
+        Rank::Eight => [corners, vec![(LEFT, 0.5), (RIGHT, 0.5), (CENTER, 0.35), (CENTER, 0.65)]].concat(),
+        Rank::Nine | Rank::Ten => [LEFT, RIGHT].iter()
+            .flat_map(|x| [0.2, 0.4, 0.6, 0.8].iter().map(move |y| (*x, *y)))
+            .collect(),
+        _ => vec![(CENTER, 0.5)],
+    };
+
+    match rank {
+        Rank::Nine => positions.push((CENTER, 0.5)),
+        Rank::Ten => positions.extend_from_slice(&[(CENTER, 0.3), (CENTER, 0.7)]),
+        _ => {}
+    }
+
+    positions
+}
+
+fn suit_symbol(suit: Suit) -> &'static str {
+    match suit {
+        Suit::Clubs => "♣",
+        Suit::Diamonds => "♦",
+        Suit::Hearts => "♥",
+        Suit::Spades => "♠",
+    }
+}
+
+fn suit_color(suit: Suit) -> &'static str {
+    if suit.is_red() { "#c62828" } else { "#212121" }
+}
+
+fn document(content: &str) -> String {
+    format!(
+        concat!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"##,
+            r##"<rect x="2" y="2" width="{rw}" height="{rh}" rx="14" fill="#ffffff" stroke="#9e9e9e" stroke-width="2"/>"##,
+            "{content}</svg>"
+        ),
+        w = WIDTH, h = HEIGHT, rw = WIDTH - 4.0, rh = HEIGHT - 4.0, content = content
+    )
+}
+
+fn corner_indexes(label: &str, symbol: &str, color: &str) -> String {
+    format!(
+        concat!(
+            r##"<g fill="{color}" font-family="serif" text-anchor="middle">"##,
+            r##"<text x="22" y="36" font-size="28">{label}</text><text x="22" y="62" font-size="24">{symbol}</text>"##,
+            r##"<g transform="rotate(180 {cx} {cy})"><text x="22" y="36" font-size="28">{label}</text><text x="22" y="62" font-size="24">{symbol}</text></g>"##,
+            "</g>"
+        ),
+        color = color, label = label, symbol = symbol, cx = WIDTH / 2.0, cy = HEIGHT / 2.0
+    )
+}
+
+/// Renders the face of a card as an SVG document.
+///
+/// Numbered cards get their pips laid out as on a classic deck, while court cards, jokers
+/// and tarot cards get a framed placeholder with their index letter or number.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::card::{PlayingCard, Rank, Suit};
+/// use jeu_de_carte::render::render_card;
+///
+/// fn main() {
+///     let svg = render_card(PlayingCard::new(Rank::Seven, Suit::Hearts));
+///     assert!(svg.starts_with("<svg"));
+/// }
+/// ```
+pub fn render_card(card: PlayingCard) -> String {
+    match card {
+        PlayingCard::Standard { rank, suit } => {
+            let symbol = suit_symbol(suit);
+            let color = suit_color(suit);
+            let mut content = corner_indexes(rank.symbol(), symbol, color);
+
+            match rank {
+                Rank::Jack | Rank::Queen | Rank::King => content.push_str(&format!(
+                    concat!(
+                        r##"<rect x="50" y="70" width="150" height="210" fill="none" stroke="{color}" stroke-width="3"/>"##,
+                        r##"<text x="125" y="190" font-family="serif" font-size="110" fill="{color}" text-anchor="middle">{label}</text>"##,
+                        r##"<text x="125" y="255" font-family="serif" font-size="48" fill="{color}" text-anchor="middle">{symbol}</text>"##
+                    ),
+                    color = color, label = rank.symbol(), symbol = symbol
+                )),
+                Rank::Ace => content.push_str(&format!(
+                    r##"<text x="125" y="210" font-family="serif" font-size="140" fill="{color}" text-anchor="middle">{symbol}</text>"##,
+                    color = color, symbol = symbol
+                )),
+                _ => for (x, y) in pip_positions(rank) {
+                    let (px, py) = ((x * WIDTH).round(), (y * HEIGHT).round());
+                    // Pips of the bottom half are drawn upside down, as on printed cards.
+                    let transform = if y > 0.5 { format!(r##" transform="rotate(180 {} {})""##, px, py) } else { String::new() };
+                    content.push_str(&format!(
+                        r##"<text x="{x}" y="{y}" font-family="serif" font-size="48" fill="{color}" text-anchor="middle" dominant-baseline="central"{transform}>{symbol}</text>"##,
+                        x = px, y = py, color = color, symbol = symbol, transform = transform
+                    ));
+                }
+            }
+
+            document(&content)
+        }
+        PlayingCard::RedJoker | PlayingCard::BlackJoker => {
+            let color = if card == PlayingCard::RedJoker { "#c62828" } else { "#212121" };
+            let mut content = corner_indexes("J", "★", color);
+            content.push_str(&format!(
+                concat!(
+                    r##"<text x="125" y="190" font-family="serif" font-size="110" fill="{color}" text-anchor="middle">★</text>"##,
+                    r##"<text x="125" y="250" font-family="serif" font-size="40" fill="{color}" text-anchor="middle">JOKER</text>"##
+                ),
+                color = color
+            ));
+
+            document(&content)
+        }
+        PlayingCard::Knight(suit) => {
+            let symbol = suit_symbol(suit);
+            let color = suit_color(suit);
+            let mut content = corner_indexes("C", symbol, color);
+            content.push_str(&format!(
+                concat!(
+                    r##"<rect x="50" y="70" width="150" height="210" fill="none" stroke="{color}" stroke-width="3"/>"##,
+                    r##"<text x="125" y="190" font-family="serif" font-size="110" fill="{color}" text-anchor="middle">♞</text>"##,
+                    r##"<text x="125" y="255" font-family="serif" font-size="48" fill="{color}" text-anchor="middle">{symbol}</text>"##
+                ),
+                color = color, symbol = symbol
+            ));
+
+            document(&content)
+        }
+        PlayingCard::Trump(number) => {
+            let color = "#212121";
+            let label = number.to_string();
+            let mut content = corner_indexes(&label, "", color);
+            content.push_str(&format!(
+                concat!(
+                    r##"<rect x="50" y="70" width="150" height="210" rx="10" fill="#fff8e1" stroke="{color}" stroke-width="3"/>"##,
+                    r##"<text x="125" y="205" font-family="serif" font-size="100" fill="{color}" text-anchor="middle">{label}</text>"##
+                ),
+                color = color, label = label
+            ));
+
+            document(&content)
+        }
+        PlayingCard::Excuse => {
+            let color = "#212121";
+            let mut content = corner_indexes("★", "", color);
+            content.push_str(&format!(
+                concat!(
+                    r##"<text x="125" y="190" font-family="serif" font-size="110" fill="{color}" text-anchor="middle">★</text>"##,
+                    r##"<text x="125" y="250" font-family="serif" font-size="40" fill="{color}" text-anchor="middle">EXCUSE</text>"##
+                ),
+                color = color
+            ));
+
+            document(&content)
+        }
+    }
+}
+
+/// Renders the back of the cards as an SVG document.
+pub fn render_back() -> String {
+    document(concat!(
+        r##"<defs><pattern id="lattice" width="20" height="20" patternUnits="userSpaceOnUse">"##,
+        r##"<path d="M0 10 L10 0 L20 10 L10 20 Z" fill="none" stroke="#ffffff" stroke-width="2"/></pattern></defs>"##,
+        r##"<rect x="16" y="16" width="218" height="318" rx="8" fill="#1565c0"/>"##,
+        r##"<rect x="16" y="16" width="218" height="318" rx="8" fill="url(#lattice)"/>"##
+    ))
+}
@@ -0,0 +1,62 @@
+use postgres::GenericClient;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+use crate::metrics;
+
+/// A game session stored as a JSON state, along with the deck used to deal its cards.
+pub struct Session<T> {
+    pub id: Uuid,
+    pub kind: String,
+    pub deck_id: Uuid,
+    pub state: T,
+}
+
+impl<T: Serialize + DeserializeOwned> Session<T> {
+    pub fn new(kind: &str, deck_id: Uuid, state: T) -> Session<T> {
+        Session { id: Uuid::new_v4(), kind: kind.to_string(), deck_id, state }
+    }
+
+    pub fn insert_into_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "INSERT INTO sessions (id, kind, deck_id, state) VALUES ($1, $2, $3, $4)"
+            ).unwrap();
+        let state = serde_json::to_value(&self.state).expect("An error occurred.");
+        metrics::time_query("insert_session", || connection.execute(&stmt, &[&self.id, &self.kind, &self.deck_id, &state])).expect("An error occurred.");
+    }
+
+    pub fn update_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "UPDATE sessions SET state = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1"
+            ).unwrap();
+        let state = serde_json::to_value(&self.state).expect("An error occurred.");
+        metrics::time_query("update_session", || connection.execute(&stmt, &[&self.id, &state])).expect("An error occurred.");
+    }
+
+    pub fn find_by_id<C: GenericClient>(id: Uuid, kind: &str, connection: &mut C) -> Option<Session<T>> {
+        Session::find_with_query(id, kind, connection, "SELECT deck_id, state FROM sessions WHERE id = $1 AND kind = $2")
+    }
+
+    /// Finds a session and locks its row until the end of the current transaction.
+    pub fn find_by_id_for_update<C: GenericClient>(id: Uuid, kind: &str, connection: &mut C) -> Option<Session<T>> {
+        Session::find_with_query(id, kind, connection, "SELECT deck_id, state FROM sessions WHERE id = $1 AND kind = $2 FOR UPDATE")
+    }
+
+    fn find_with_query<C: GenericClient>(id: Uuid, kind: &str, connection: &mut C, query: &str) -> Option<Session<T>> {
+        let stmt = connection.prepare(query).unwrap();
+        let rows = metrics::time_query("find_session", || connection.query(&stmt, &[&id, &kind]));
+
+        match rows {
+            Ok(rows) => {
+                let row = rows.first()?;
+                let deck_id: Uuid = row.get(0);
+                let state: serde_json::Value = row.get(1);
+
+                Some(Session { id, kind: kind.to_string(), deck_id, state: serde_json::from_value(state).ok()? })
+            }
+            Err(_) => None
+        }
+    }
+}
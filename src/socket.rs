@@ -0,0 +1,292 @@
+use std::sync::Arc;
+use std::time::Instant;
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::{HttpResponse, Result, HttpRequest, web};
+use actix_web::http::StatusCode;
+use actix_web_actors::ws;
+use uuid::Uuid;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use jeu_de_carte::Deck;
+use jeu_de_carte::batch::{BatchRepresentation, Operation};
+use jeu_de_carte::database::establish_connection;
+use jeu_de_carte::events::{Event, EventHub};
+use jeu_de_carte::game::{self, Command, GameRepresentation};
+use jeu_de_carte::i18n::{Language, Message};
+use jeu_de_carte::jwt::{self, Scope};
+use jeu_de_carte::rate_limit::RateLimiter;
+use jeu_de_carte::session::Session;
+use jeu_de_carte::theme::Theme;
+use crate::controller::{access_denied, apply_batch, player_token, request_language, request_theme, transact_session, Credentials, Failure};
+use crate::limiter::{request_client, RateLimiting};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SocketRequest {
+    /// The token of the player or a signed token, browsers being unable to send headers when opening a socket.
+    pub token: Option<String>,
+}
+
+/// A frame answering a command sent over the socket, the events being sent as they are.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reply<S> {
+    Result { body: S },
+    Error { message: String },
+}
+
+impl<S> Reply<S> {
+    fn error(message: Message, language: Language) -> Reply<S> {
+        Reply::Error { message: message.translate(language) }
+    }
+}
+
+enum Target {
+    /// Commands are batch operations applied to the deck.
+    Deck,
+    /// Commands are played by the player of the token, if any.
+    Game { token: Option<String> },
+}
+
+/// A socket pushing the events of a deck or of a game, and running the commands of its client.
+pub struct EventSocket {
+    topic: Uuid,
+    target: Target,
+    /// The player allowed to see their own events, None for a spectator.
+    player: Option<Uuid>,
+    /// The API key or the token authenticating the socket, allowed to change the deck or not.
+    credentials: Credentials,
+    hub: web::Data<EventHub>,
+    /// The limit of the requests changing something, each command counting as one.
+    limiter: Option<Arc<RateLimiter>>,
+    /// The API key or the address of the client, as counted by the limiter.
+    client: String,
+    language: Language,
+    theme: Theme,
+}
+
+impl EventSocket {
+    fn run(&self, text: &str) -> String {
+        let language = self.language;
+        if let Some(limiter) = &self.limiter {
+            if !limiter.check(&self.client, Instant::now()).allowed {
+                return serde_json::to_string(&Reply::<()>::error(Message::TooManyRequests, language)).expect("An error occurred.");
+            }
+        }
+        match &self.target {
+            Target::Deck => {
+                let reply = match serde_json::from_str::<Operation>(text) {
+                    Ok(operation) => match apply_batch(Some(&self.hub), &self.credentials, self.topic, &[operation]) {
+                        Some(Ok((deck, results))) => Reply::Result { body: BatchRepresentation::translated(deck, results, language, &self.theme) },
+                        Some(Err(failure)) => Reply::error(failure.message(), language),
+                        None => Reply::error(Message::DeckNotFound, language)
+                    },
+                    Err(_) => Reply::error(Message::IncorrectParameter, language)
+                };
+                serde_json::to_string(&reply).expect("An error occurred.")
+            }
+            Target::Game { token } => {
+                let token = token.as_deref();
+                let reply = match serde_json::from_str::<Command>(text) {
+                    Ok(_) if self.credentials.authorize_token(self.topic, &[Scope::Draw]).is_err() => Reply::error(Message::MissingScope, language),
+                    Ok(command) => {
+                        let outcome = transact_session(Some(&self.hub), self.topic, game::SESSION_KIND, |game: &mut game::Game, draw_pile| {
+                            game.run(draw_pile, token, &command).map_err(|game_error| game_error.message())
+                        });
+                        match outcome {
+                            Some(Ok((session, draw_pile))) => Reply::Result {
+                                body: GameRepresentation::translated(session.id, &session.state, &draw_pile, token, language, &self.theme)
+                            },
+                            Some(Err(message)) => Reply::error(message, language),
+                            None => Reply::error(Message::SessionNotFound, language)
+                        }
+                    }
+                    Err(_) => Reply::error(Message::IncorrectParameter, language)
+                };
+                serde_json::to_string(&reply).expect("An error occurred.")
+            }
+        }
+    }
+}
+
+impl Actor for EventSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.add_stream(self.hub.subscribe(self.topic, self.player));
+    }
+}
+
+impl StreamHandler<Event> for EventSocket {
+    fn handle(&mut self, event: Event, ctx: &mut Self::Context) {
+        ctx.text(serde_json::to_string(&event).expect("An error occurred."));
+    }
+
+    /// The events end when the deck or the game is deleted.
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseCode::Normal.into()));
+        ctx.stop();
+    }
+}
+
+impl StreamHandler<std::result::Result<ws::Message, ws::ProtocolError>> for EventSocket {
+    fn handle(&mut self, message: std::result::Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match message {
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Text(text)) => ctx.text(self.run(&text)),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => (),
+            Err(_) => ctx.stop()
+        }
+    }
+}
+
+/// The credentials of a socket, a signed token being also accepted in the query.
+///
+/// returns: None when the token of the query is invalid or expired.
+fn socket_credentials(req: &HttpRequest, token: Option<&str>) -> Option<Credentials> {
+    let mut credentials = Credentials::from_request(req);
+    if let Some(token) = token.filter(|token| jwt::is_token(token)) {
+        credentials.claims = Some(jwt::verify(jwt::secret(), token)?);
+    }
+
+    Some(credentials)
+}
+
+fn event_hub(req: &HttpRequest) -> web::Data<EventHub> {
+    req.app_data::<web::Data<EventHub>>().expect("The event hub must be registered.").clone()
+}
+
+fn mutation_limiter(req: &HttpRequest) -> Option<Arc<RateLimiter>> {
+    req.app_data::<web::Data<RateLimiting>>().and_then(|limits| limits.mutation())
+}
+
+pub async fn deck_socket(req: HttpRequest, query: web::Query<SocketRequest>, stream: web::Payload) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let credentials = match socket_credentials(&req, query.token.as_deref()) {
+        Some(credentials) => credentials,
+        None => return Ok(Failure::new(StatusCode::UNAUTHORIZED, Message::InvalidToken).parameter("token").respond(language))
+    };
+    let deck_id = req.match_info().get("id");
+    match deck_id {
+        Some(deck_id) => {
+            let deck_id = Uuid::parse_str(deck_id);
+            match deck_id {
+                Ok(deck_id) => {
+                    if let Err(error) = credentials.authorize_token(deck_id, &[Scope::Read]) {
+                        return Ok(access_denied(error, language));
+                    }
+                    let mut connection = establish_connection();
+                    let deck = Deck::find_by_id(deck_id, &mut connection);
+                    connection.close().ok();
+
+                    match deck {
+                        Some(deck) => {
+                            let socket = EventSocket {
+                                topic: deck.id,
+                                target: Target::Deck,
+                                player: None,
+                                credentials,
+                                hub: event_hub(&req),
+                                limiter: mutation_limiter(&req),
+                                client: request_client(&req),
+                                language,
+                                theme: request_theme(&req, &deck),
+                            };
+                            ws::start(socket, &req, stream)
+                        }
+                        None => Ok(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound).respond(language))
+                    }
+                }
+                Err(_) => Ok(Failure::incorrect("id").respond(language))
+            }
+        }
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+pub async fn game_socket(req: HttpRequest, query: web::Query<SocketRequest>, stream: web::Payload) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    let token = query.into_inner().token;
+    let credentials = match socket_credentials(&req, token.as_deref()) {
+        Some(credentials) => credentials,
+        None => return Ok(Failure::new(StatusCode::UNAUTHORIZED, Message::InvalidToken).parameter("token").respond(language))
+    };
+    let header = token.filter(|token| !jwt::is_token(token)).or_else(|| player_token(&req).map(str::to_string));
+    let game_id = req.match_info().get("id").map(Uuid::parse_str);
+    match game_id {
+        Some(Ok(game_id)) => {
+            if let Err(error) = credentials.authorize_token(game_id, &[Scope::Read]) {
+                return Ok(access_denied(error, language));
+            }
+            let mut connection = establish_connection();
+            let session = Session::<game::Game>::find_by_id(game_id, game::SESSION_KIND, &mut connection);
+            let draw_pile = session.as_ref().and_then(|session| Deck::find_in_session(session.deck_id, session.id, &mut connection));
+            connection.close().ok();
+
+            match (session, draw_pile) {
+                (Some(session), Some(draw_pile)) => {
+                    let token = credentials.player_token(header.as_deref(), &session.state);
+                    let player = token.as_deref().and_then(|token| session.state.player(token)).map(|player| player.id);
+                    if token.is_some() && player.is_none() {
+                        return Ok(Failure::from(Message::InvalidToken).parameter("token").respond(language));
+                    }
+
+                    let socket = EventSocket {
+                        topic: session.id,
+                        target: Target::Game { token },
+                        player,
+                        credentials,
+                        hub: event_hub(&req),
+                        limiter: mutation_limiter(&req),
+                        client: request_client(&req),
+                        language,
+                        theme: request_theme(&req, &draw_pile),
+                    };
+                    ws::start(socket, &req, stream)
+                }
+                _ => Ok(Failure::new(StatusCode::NOT_FOUND, Message::SessionNotFound).respond(language))
+            }
+        }
+        Some(Err(_)) => Ok(Failure::incorrect("id").respond(language)),
+        None => Ok(Failure::missing("id").respond(language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use actix_web::web;
+    use uuid::Uuid;
+    use jeu_de_carte::events::EventHub;
+    use jeu_de_carte::i18n::{Language, Message};
+    use jeu_de_carte::rate_limit::{Limit, RateLimiter};
+    use jeu_de_carte::theme::Theme;
+    use crate::controller::Credentials;
+    use super::{EventSocket, Target};
+
+    #[test]
+    fn test_socket_commands_rate_limited() {
+        let capacity = 3;
+        let socket = EventSocket {
+            topic: Uuid::new_v4(),
+            target: Target::Deck,
+            player: None,
+            credentials: Credentials::default(),
+            hub: web::Data::new(EventHub::new()),
+            limiter: Some(Arc::new(RateLimiter::new(Limit { capacity, period: Duration::from_secs(60) }))),
+            client: "ip:127.0.0.1".to_string(),
+            language: Language::English,
+            theme: Theme::default_theme(),
+        };
+
+        let message = |message: Message| format!("{{\"type\":\"error\",\"message\":\"{}\"}}", message.translate(Language::English));
+        for _ in 0..capacity {
+            assert_eq!(socket.run("shuffle everything"), message(Message::IncorrectParameter));
+        }
+        assert_eq!(socket.run("shuffle everything"), message(Message::TooManyRequests));
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::{Card, Deck, DeckSize};
+use crate::card::{PlayingCard, Rank};
+use crate::i18n::{Language, Message};
+use crate::trick::to_card;
+
+/// The three oudlers: the Petit (trump 1), the 21 of trump and the Excuse.
+pub const OUDLERS: [PlayingCard; 3] = [PlayingCard::Trump(1), PlayingCard::Trump(21), PlayingCard::Excuse];
+/// Total of the card points of the deck.
+pub const TOTAL_POINTS: f32 = 91.0;
+/// The base score of a contract, to which the difference with the required points is added.
+pub const CONTRACT_BASE: i32 = 25;
+
+pub fn is_oudler(card: PlayingCard) -> bool {
+    OUDLERS.contains(&card)
+}
+
+/// The points of a card: 4.5 for the oudlers and the kings, 3.5 for the queens,
+/// 2.5 for the knights, 1.5 for the jacks and 0.5 for the other cards.
+pub fn card_points(card: PlayingCard) -> f32 {
+    match card {
+        _ if is_oudler(card) => 4.5,
+        PlayingCard::Standard { rank: Rank::King, .. } => 4.5,
+        PlayingCard::Standard { rank: Rank::Queen, .. } => 3.5,
+        PlayingCard::Knight(_) => 2.5,
+        PlayingCard::Standard { rank: Rank::Jack, .. } => 1.5,
+        _ => 0.5,
+    }
+}
+
+pub fn count_points(cards: &[Card]) -> f32 {
+    cards.iter().map(|card| card_points(to_card(*card))).sum()
+}
+
+pub fn count_oudlers(cards: &[Card]) -> usize {
+    cards.iter().filter(|card| is_oudler(to_card(**card))).count()
+}
+
+/// The points the taker must make, depending on the number of oudlers they won.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::tarot::required_points;
+///
+/// fn main() {
+///     assert_eq!(required_points(0), 56.0);
+///     assert_eq!(required_points(3), 36.0);
+/// }
+/// ```
+pub fn required_points(oudlers: usize) -> f32 {
+    match oudlers {
+        0 => 56.0,
+        1 => 51.0,
+        2 => 41.0,
+        _ => 36.0,
+    }
+}
+
+/// Number of cards in the chien, depending on the number of players.
+pub fn chien_size(players: usize) -> Option<usize> {
+    match players {
+        3 | 4 => Some(6),
+        5 => Some(3),
+        _ => None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Contract {
+    Petite,
+    Garde,
+    GardeSans,
+    GardeContre,
+}
+
+impl Contract {
+    pub fn multiplier(self) -> i32 {
+        match self {
+            Contract::Petite => 1,
+            Contract::Garde => 2,
+            Contract::GardeSans => 4,
+            Contract::GardeContre => 6,
+        }
+    }
+}
+
+/// Scores a contract, from the points and the oudlers won by the taker.
+///
+/// returns: i32, the score of the taker against each defender, negative when the contract fails.
+pub fn score(contract: Contract, points: f32, oudlers: usize) -> i32 {
+    let difference = points - required_points(oudlers);
+    let base = CONTRACT_BASE + difference.abs().round() as i32;
+    let score = base * contract.multiplier();
+    if difference >= 0.0 { score } else { -score }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TarotError {
+    InvalidPlayers(usize),
+    NotYourTurn,
+    IllegalBid,
+    IllegalAction,
+    InvalidDiscard,
+}
+
+impl TarotError {
+    pub fn message(&self) -> Message {
+        match self {
+            TarotError::InvalidPlayers(players) => Message::InvalidPlayers(*players),
+            TarotError::NotYourTurn => Message::NotYourTurn,
+            TarotError::IllegalBid => Message::IllegalBid,
+            TarotError::IllegalAction => Message::IllegalAction,
+            TarotError::InvalidDiscard => Message::InvalidDiscard,
+        }
+    }
+}
+
+impl fmt::Display for TarotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Bidding,
+    /// The taker makes their discard after taking the chien.
+    Discarding,
+    /// The cards are ready to be played.
+    Playing,
+    /// Everybody passed, the cards must be dealt again.
+    Passed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidRecord {
+    pub seat: usize,
+    /// None when the player passes.
+    pub contract: Option<Contract>,
+}
+
+/// The dealing and the bidding of a French tarot round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Round {
+    pub players: usize,
+    pub dealer: usize,
+    pub phase: Phase,
+    pub turn: usize,
+    pub hands: Vec<Vec<Card>>,
+    pub chien: Vec<Card>,
+    pub bids: Vec<BidRecord>,
+    pub taker: Option<usize>,
+    pub contract: Option<Contract>,
+    /// The cards counting for the taker before the first trick: the discard, or the chien in a garde sans.
+    pub discard: Vec<Card>,
+}
+
+impl Round {
+    /// Creates the shuffled 78 cards deck.
+    pub fn new_deck() -> Deck {
+        let mut deck = Deck::new(DeckSize::Tarot);
+        deck.shuffle(false);
+        deck
+    }
+
+    /// Deals the cards by packets of 3 from the left of the dealer, putting the chien cards one at a time
+    /// between the packets, but never after the last ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `deck`: The tarot deck, the dealt cards being put into its discarded stack.
+    /// * `players`: The number of players, from 3 to 5.
+    /// * `dealer`: The seat of the dealer.
+    ///
+    /// returns: Result<Round, TarotError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::tarot::Round;
+    ///
+    /// fn main() {
+    ///     let mut deck = Round::new_deck();
+    ///     let round = Round::deal(&mut deck, 4, 0).unwrap();
+    ///     assert_eq!(round.hands[0].len(), 18);
+    ///     assert_eq!(round.chien.len(), 6);
+    /// }
+    /// ```
+    pub fn deal(deck: &mut Deck, players: usize, dealer: usize) -> Result<Round, TarotError> {
+        let chien_size = chien_size(players).ok_or(TarotError::InvalidPlayers(players))?;
+        if deck.cards.len() != 78 { return Err(TarotError::IllegalAction); }
+
+        let packets = (78 - chien_size) / players / 3;
+        let mut hands = vec![Vec::new(); players];
+        let mut chien = Vec::with_capacity(chien_size);
+        for packet in 0..packets {
+            for offset in 1..=players {
+                let cards = deck.deal(1, 3).expect("The deck has 78 cards.");
+                hands[(dealer + offset) % players].extend(cards.concat());
+            }
+            let slots = packets - 1 - packet;
+            if slots > 0 {
+                let remaining = chien_size - chien.len();
+                let count = remaining.div_ceil(slots);
+                chien.extend(deck.deal(1, count).expect("The deck has 78 cards.").concat());
+            }
+        }
+
+        Ok(Round {
+            players,
+            dealer,
+            phase: Phase::Bidding,
+            turn: (dealer + 1) % players,
+            hands,
+            chien,
+            bids: Vec::new(),
+            taker: None,
+            contract: None,
+            discard: Vec::new(),
+        })
+    }
+
+    /// Bids once for each player, from the left of the dealer: a contract must be higher than the previous ones.
+    pub fn bid(&mut self, seat: usize, contract: Option<Contract>) -> Result<(), TarotError> {
+        if self.phase != Phase::Bidding { return Err(TarotError::IllegalAction); }
+        if seat != self.turn { return Err(TarotError::NotYourTurn); }
+        if let Some(contract) = contract {
+            if self.contract.is_some_and(|current| contract <= current) { return Err(TarotError::IllegalBid); }
+            self.contract = Some(contract);
+            self.taker = Some(seat);
+        }
+        self.bids.push(BidRecord { seat, contract });
+        self.turn = (self.turn + 1) % self.players;
+        if self.bids.len() < self.players {
+            return Ok(());
+        }
+
+        match (self.taker, self.contract) {
+            (Some(taker), Some(Contract::Petite)) | (Some(taker), Some(Contract::Garde)) => {
+                // The chien is shown and taken by the taker, who then discards as many cards.
+                self.hands[taker].extend(self.chien.iter());
+                self.turn = taker;
+                self.phase = Phase::Discarding;
+            }
+            (Some(_), Some(Contract::GardeSans)) => {
+                self.discard = std::mem::take(&mut self.chien);
+                self.start_play();
+            }
+            // In a garde contre, the chien stays aside and counts for the defenders.
+            (Some(_), Some(Contract::GardeContre)) => self.start_play(),
+            _ => self.phase = Phase::Passed,
+        }
+        Ok(())
+    }
+
+    /// Discards the cards of the taker after taking the chien.
+    ///
+    /// Kings and oudlers cannot be discarded, and trumps only when the taker has no other choice.
+    pub fn discard(&mut self, seat: usize, cards: &[Card]) -> Result<(), TarotError> {
+        if self.phase != Phase::Discarding { return Err(TarotError::IllegalAction); }
+        if Some(seat) != self.taker { return Err(TarotError::NotYourTurn); }
+
+        let hand = &self.hands[seat];
+        let mut unique = cards.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        let allowed = |card: &PlayingCard| !is_oudler(*card) && card.rank() != Some(Rank::King);
+        let plain = |card: &PlayingCard| allowed(card) && !matches!(card, PlayingCard::Trump(_));
+        let plain_in_hand = hand.iter().filter(|card| plain(&to_card(**card))).count();
+
+        let valid = unique.len() == self.chien.len()
+            && cards.len() == unique.len()
+            && cards.iter().all(|card| hand.contains(card))
+            && cards.iter().map(|card| to_card(*card)).all(|card| allowed(&card))
+            && (plain_in_hand < cards.len() || cards.iter().all(|card| plain(&to_card(*card))));
+        if !valid { return Err(TarotError::InvalidDiscard); }
+
+        self.hands[seat].retain(|card| !cards.contains(card));
+        self.discard = cards.to_vec();
+        self.chien.clear();
+        self.start_play();
+        Ok(())
+    }
+
+    fn start_play(&mut self) {
+        self.turn = (self.dealer + 1) % self.players;
+        self.phase = Phase::Playing;
+    }
+}
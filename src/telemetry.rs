@@ -0,0 +1,182 @@
+use std::task::{Context, Poll};
+use std::time::Instant;
+use actix_web::{Error, HttpResponse, Result};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use futures::FutureExt;
+use tracing::Instrument;
+use jeu_de_carte::database;
+use jeu_de_carte::health::{Liveness, Readiness};
+use jeu_de_carte::logging::{self, REQUEST_ID_HEADER};
+use jeu_de_carte::metrics;
+
+/// The route of the requests matching none, so that unknown paths don't make new series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Counts the requests and observes their latency per route, the routes being their patterns such as `/cards/{id}`.
+///
+/// Wraps every other middleware, the requests refused by them being counted too.
+pub struct Metrics;
+
+impl<S, B> Transform<S> for Metrics
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MetricsMiddleware { service })
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for MetricsMiddleware<S>
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let method = req.method().to_string();
+        let route = req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+        self.service.call(req)
+            .map(move |response| {
+                let status = match &response {
+                    Ok(response) => response.status(),
+                    Err(error) => error.as_response_error().status_code(),
+                };
+                metrics::HTTP_REQUESTS.inc(&[&method, &route, status.as_str()]);
+                metrics::HTTP_REQUEST_DURATION.observe(&[&method, &route], start.elapsed().as_secs_f64());
+                response
+            })
+            .boxed_local()
+    }
+}
+
+/// Gives each request an ID, logs it once answered and echoes its ID in the `X-Request-Id` header.
+///
+/// The handlers and the database queries run in the span of the request, so that each of their logs tells its ID.
+/// Wraps every other middleware, the requests refused by them being logged too.
+pub struct RequestTracing;
+
+impl<S, B> Transform<S> for RequestTracing
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestTracingMiddleware { service })
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service for RequestTracingMiddleware<S>
+    where S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+          S::Future: 'static,
+          B: 'static
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let request_id = logging::request_id(req.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()));
+        let span = tracing::info_span!("request", request_id = %request_id, method = %req.method(), path = %req.path());
+
+        self.service.call(req)
+            .instrument(span.clone())
+            .map(move |response| {
+                let status = match &response {
+                    Ok(response) => response.status(),
+                    Err(error) => error.as_response_error().status_code(),
+                };
+                let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+                span.in_scope(|| match status.is_server_error() {
+                    true => tracing::error!(status = status.as_u16(), latency_ms, "request answered"),
+                    false => tracing::info!(status = status.as_u16(), latency_ms, "request answered")
+                });
+
+                match response {
+                    Ok(mut response) => {
+                        echo_request_id(response.headers_mut(), &request_id);
+                        Ok(response)
+                    }
+                    Err(error) => {
+                        let mut response = error.as_response_error().error_response();
+                        echo_request_id(response.headers_mut(), &request_id);
+                        Err(InternalError::from_response("", response).into())
+                    }
+                }
+            })
+            .boxed_local()
+    }
+}
+
+fn echo_request_id(headers: &mut HeaderMap, request_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        headers.insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+}
+
+/// Serves the metrics in the text format of Prometheus, the usage of the database connections being read when scraped.
+pub async fn get_metrics() -> Result<HttpResponse> {
+    if let Ok(mut connection) = database::try_establish_connection() {
+        if let Ok((open, max)) = database::connection_usage(&mut connection) {
+            metrics::DB_CONNECTIONS_OPEN.set(open);
+            metrics::DB_CONNECTIONS_MAX.set(max);
+        }
+        connection.close().ok();
+    }
+
+    Ok(HttpResponse::Ok().content_type(metrics::CONTENT_TYPE).body(metrics::render()))
+}
+
+/// Answers as long as the process is up, without looking at the database.
+pub async fn get_liveness() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(Liveness::new()))
+}
+
+/// Checks the database and its schema, answering 503 when the API can't serve requests.
+pub async fn get_readiness() -> Result<HttpResponse> {
+    let readiness = Readiness::check();
+    let status = if readiness.is_ready() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    Ok(HttpResponse::build(status).json(readiness))
+}
@@ -1,7 +1,37 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
+    use std::convert::TryFrom;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use futures::channel::mpsc::TryRecvError;
+    use futures::StreamExt;
+    use actix_web::http::StatusCode;
     use uuid::Uuid;
-    use crate::{Card, Deck, DeckSize};
+    use crate::{card_generator, Card, CompactDeckRepresentation, Deck, DeckSize, MovedCardsRepresentation, Problem};
+    use crate::card::{parse_cards, PlayingCard, Rank, Suit};
+    use crate::i18n::{self, Language, Message};
+    use crate::render::{render_back, render_card};
+    use crate::theme::Theme;
+    use crate::poker::{self, HandCategory, HAND_CLASSES};
+    use crate::odds::{self, OddsError, OddsMethod};
+    use crate::batch::{self, BatchErrorKind, Operation, StepResult};
+    use crate::belote::{self, Bid, BeloteError};
+    use crate::trick::{Played, Round, Trick, TrickError, TrickRules};
+    use crate::hearts::{self, Hearts};
+    use crate::whist::Whist;
+    use crate::tarot::{self, Contract, TarotError};
+    use crate::game::{self, GameError, GameRepresentation};
+    use crate::events::{self, Audience, DeckChange, EventHub, EventKind};
+    use crate::api_key::{self, AccessError, ApiKey};
+    use crate::jwt::{self, Claims, Scope};
+    use crate::logging;
+    use crate::metrics;
+    use crate::rate_limit::{self, Limit, Quota, RateLimiter};
+    use crate::webhook::{self, DeadLetter, Delivery, Payload, RetryPolicy, Webhook, WebhookEvent};
+    use crate::blackjack::{self, Action, BlackjackError, Outcome, Phase, Rules, Score, Table};
 
     #[test]
     fn test_create_small_deck() {
@@ -20,7 +50,7 @@ mod tests {
     #[test]
     fn test_shuffle_deck() {
         let mut deck = Deck::new(DeckSize::Normal);
-        let deck_copy = Deck { id: Uuid::new_v4(), cards: deck.cards.clone(), discarded: vec![] };
+        let deck_copy = Deck { id: Uuid::new_v4(), cards: deck.cards.clone(), discarded: vec![], theme: None, owner: None, session: None };
 
         assert_eq!(deck.cards, deck_copy.cards);
         assert_eq!(deck.discarded, deck_copy.discarded);
@@ -35,7 +65,7 @@ mod tests {
     fn test_shuffle_discarded_deck() {
         let mut deck = Deck::new(DeckSize::Normal);
         deck.take(false, 52, true);
-        let deck_copy = Deck { id: Uuid::new_v4(), cards: vec![], discarded: deck.discarded.clone() };
+        let deck_copy = Deck { id: Uuid::new_v4(), cards: vec![], discarded: deck.discarded.clone(), theme: None, owner: None, session: None };
 
         assert_eq!(deck.cards, deck_copy.cards);
         assert_eq!(deck.discarded, deck_copy.discarded);
@@ -159,4 +189,1215 @@ mod tests {
         witness_cards = witness_deck.discarded.clone().drain(47..).collect();
         assert_eq!(deck.cards, witness_cards);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_move_cards() {
+        let mut deck = Deck::new(DeckSize::Normal);
+
+        assert_eq!(deck.move_cards(&[3, 40], true), Ok(()));
+        assert_eq!(deck.cards.len(), 50);
+        assert_eq!(deck.discarded, vec![3, 40]);
+
+        assert_eq!(deck.move_cards(&[40, 60], false), Err(60));
+        assert_eq!(deck.discarded, vec![3, 40]);
+
+        assert_eq!(deck.move_cards(&[40], false), Ok(()));
+        assert_eq!(deck.cards.last(), Some(&40));
+    }
+
+    #[test]
+    fn test_deal_deck() {
+        let mut deck = Deck::new(DeckSize::Normal);
+        let witness_deck = Deck::new(DeckSize::Normal);
+
+        let hands = deck.deal(2, 3).unwrap();
+        assert_eq!(hands, vec![vec![51, 49, 47], vec![50, 48, 46]]);
+        assert_eq!(deck.cards, witness_deck.cards[..46].to_vec());
+        assert_eq!(deck.discarded.len(), 6);
+
+        assert_eq!(deck.deal(5, 10), None);
+        assert_eq!(deck.cards.len(), 46);
+    }
+
+    #[test]
+    fn test_batch_deck() {
+        let mut deck = Deck::new(DeckSize::Normal);
+        let operations = vec![
+            Operation::Take { lifo: Some(true), length: Some(2), move_as_block: None },
+            Operation::Put { lifo: Some(true), length: Some(1), move_as_block: None },
+            Operation::Move { cards: vec![0], to_discarded: None },
+            Operation::Deal { hands: 2, length: 1 },
+        ];
+
+        let results = batch::apply(&mut deck, &operations).unwrap();
+        assert_eq!(results, vec![
+            StepResult::Moved(vec![51, 50]),
+            StepResult::Moved(vec![50]),
+            StepResult::Moved(vec![0]),
+            StepResult::Dealt(vec![vec![50], vec![49]]),
+        ]);
+        assert_eq!(deck.cards.len(), 48);
+        assert_eq!(deck.discarded, vec![51, 0, 50, 49]);
+    }
+
+    #[test]
+    fn test_batch_deck_is_atomic() {
+        let mut deck = Deck::new(DeckSize::Normal);
+        let witness_deck = Deck::new(DeckSize::Normal);
+        let operations = vec![
+            Operation::Take { lifo: Some(true), length: Some(5), move_as_block: None },
+            Operation::Move { cards: vec![51], to_discarded: Some(false) },
+            Operation::Move { cards: vec![51], to_discarded: Some(false) },
+        ];
+
+        let error = batch::apply(&mut deck, &operations).unwrap_err();
+        assert_eq!(error.step, 2);
+        assert_eq!(error.kind, BatchErrorKind::CardNotFound(51));
+        assert_eq!(deck.cards, witness_deck.cards);
+        assert!(deck.discarded.is_empty());
+    }
+
+    #[test]
+    fn test_card_codes() {
+        assert_eq!(card_generator::get_code(&0), "AC");
+        assert_eq!(card_generator::get_code(&14), "2D");
+        assert_eq!(card_generator::get_code(&35), "10H");
+        assert_eq!(card_generator::get_code(&37), "QH");
+        assert_eq!(card_generator::get_code(&51), "KS");
+    }
+
+    #[test]
+    fn test_compact_representations() {
+        let mut deck = Deck::new(DeckSize::Normal);
+        let moved = deck.take(true, 2, false);
+
+        let representation = MovedCardsRepresentation::compact(&deck, moved);
+        assert_eq!(representation.moved, vec!["KS", "QS"]);
+        assert_eq!(representation.cards_count, 50);
+        assert_eq!(representation.discarded_count, 2);
+
+        let representation = CompactDeckRepresentation::from(deck);
+        assert_eq!(representation.cards.len(), 50);
+        assert_eq!(representation.discarded, vec!["KS", "QS"]);
+    }
+
+    #[test]
+    fn test_card_notation_roundtrip() {
+        for motif in 0..80 {
+            let card = PlayingCard::try_from(motif).unwrap();
+            assert_eq!(card.motif(), motif);
+            assert_eq!(card.to_string().parse::<PlayingCard>(), Ok(card));
+            assert_eq!(card.to_unicode().to_string().parse::<PlayingCard>(), Ok(card));
+        }
+        assert!(PlayingCard::try_from(80).is_err());
+    }
+
+    #[test]
+    fn test_parse_card_notation() {
+        let ten_of_hearts = PlayingCard::new(Rank::Ten, Suit::Hearts);
+        assert_eq!("10H".parse(), Ok(ten_of_hearts));
+        assert_eq!("th".parse(), Ok(ten_of_hearts));
+        assert_eq!("10♥".parse(), Ok(ten_of_hearts));
+        assert_eq!("🂺".parse(), Ok(ten_of_hearts));
+        assert_eq!("AS".parse(), Ok(PlayingCard::new(Rank::Ace, Suit::Spades)));
+        assert_eq!("🂡".parse(), Ok(PlayingCard::new(Rank::Ace, Suit::Spades)));
+        assert_eq!("QD".parse(), Ok(PlayingCard::new(Rank::Queen, Suit::Diamonds)));
+        assert_eq!("🃟".parse(), Ok(PlayingCard::BlackJoker));
+        assert_eq!("RJ".parse(), Ok(PlayingCard::RedJoker));
+        assert!("1H0".parse::<PlayingCard>().is_err());
+        assert!("11S".parse::<PlayingCard>().is_err());
+        assert_eq!("🂬".parse(), Ok(PlayingCard::Knight(Suit::Spades)));
+        assert_eq!("CH".parse(), Ok(PlayingCard::Knight(Suit::Hearts)));
+        assert_eq!("t21".parse(), Ok(PlayingCard::Trump(21)));
+        assert_eq!("EX".parse(), Ok(PlayingCard::Excuse));
+        assert!("T22".parse::<PlayingCard>().is_err());
+
+        assert_eq!(PlayingCard::new(Rank::Ace, Suit::Spades).to_unicode(), '🂡');
+        assert_eq!(PlayingCard::new(Rank::King, Suit::Clubs).to_unicode(), '🃞');
+        assert_eq!(parse_cards("AC, 10H,KS"), Ok(vec![0, 35, 51]));
+    }
+
+    #[test]
+    fn test_negotiate_language() {
+        assert_eq!(Language::negotiate(None, None), Language::French);
+        assert_eq!(Language::negotiate(None, Some("en-US,en;q=0.9")), Language::English);
+        assert_eq!(Language::negotiate(None, Some("it, es;q=0.4, de;q=0.7")), Language::German);
+        assert_eq!(Language::negotiate(None, Some("en;q=0, it")), Language::French);
+        assert_eq!(Language::negotiate(Some("es-MX"), Some("de")), Language::Spanish);
+        assert_eq!(Language::negotiate(Some("xx"), Some("de_DE")), Language::German);
+    }
+
+    #[test]
+    fn test_french_names() {
+        let queen = PlayingCard::new(Rank::Queen, Suit::Hearts);
+        assert_eq!(i18n::card_name(Rank::Queen, Suit::Hearts, Language::French), "Reine de Coeur");
+        assert_eq!(i18n::card_name(Rank::Seven, Suit::Clubs, Language::French), "7 de Trèfle");
+        assert_eq!(card_generator::get_card_name(queen, Language::French), "Reine de Coeur");
+        assert_eq!(Message::DeckNotFound.translate(Language::French), "Impossible de trouver ce paquet de carte.");
+    }
+
+    #[test]
+    fn test_english_names() {
+        assert_eq!(i18n::card_name(Rank::Queen, Suit::Hearts, Language::English), "Queen of Hearts");
+        assert_eq!(i18n::card_name(Rank::Ten, Suit::Spades, Language::English), "10 of Spades");
+        assert_eq!(card_generator::get_card_name(PlayingCard::RedJoker, Language::English), "Red Joker");
+        assert_eq!(Message::MissingParameter.translate(Language::English), "Missing parameter.");
+    }
+
+    #[test]
+    fn test_german_names() {
+        assert_eq!(i18n::card_name(Rank::Queen, Suit::Hearts, Language::German), "Herz Dame");
+        assert_eq!(i18n::card_name(Rank::Ace, Suit::Clubs, Language::German), "Kreuz Ass");
+        assert_eq!(card_generator::get_card_name(PlayingCard::BlackJoker, Language::German), "Schwarzer Joker");
+        assert_eq!(Message::IncorrectParameter.translate(Language::German), "Ungültiger Parameter.");
+    }
+
+    #[test]
+    fn test_spanish_names() {
+        assert_eq!(i18n::card_name(Rank::Queen, Suit::Hearts, Language::Spanish), "Reina de Corazones");
+        assert_eq!(i18n::card_name(Rank::King, Suit::Diamonds, Language::Spanish), "Rey de Diamantes");
+        assert_eq!(Message::BatchNotEnoughCards(2).translate(Language::Spanish), "Operación 2: no hay suficientes cartas para repartir.");
+        assert_eq!(Message::CardNotInStack(3).translate(Language::Spanish), "La carta 3 no está en el montón.");
+    }
+
+    #[test]
+    fn test_render_cards() {
+        for rank in Rank::ALL.iter() {
+            let svg = render_card(PlayingCard::new(*rank, Suit::Spades));
+            let pips = svg.matches("♠").count();
+            // Two corner indexes, plus the pips of numbered cards or one suit symbol for the others.
+            match rank.index() {
+                1..=9 => assert_eq!(pips, 2 + rank.index() as usize + 1),
+                _ => assert_eq!(pips, 3),
+            }
+            assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        }
+
+        assert!(render_card(PlayingCard::new(Rank::Queen, Suit::Hearts)).contains("#c62828"));
+        assert!(render_card(PlayingCard::BlackJoker).contains("JOKER"));
+        assert!(render_back().contains("lattice"));
+    }
+
+    #[test]
+    fn test_theme_templates() {
+        let themes = Theme::parse_all(
+            "pixel = https://cdn.test/pixel/{suit}/{rank}.png | https://cdn.test/pixel/back.png;\
+             legacy=https://cdn.test/legacy/{motif}-{suit_letter}.jpg;=ignored"
+        );
+        assert_eq!(themes.len(), 2);
+        assert_eq!(themes[0].name, "pixel");
+        assert_eq!(themes[0].back, "https://cdn.test/pixel/back.png");
+        assert_eq!(themes[0].image(&37), "https://cdn.test/pixel/hearts/Q.png");
+        assert_eq!(themes[0].image(&52), "https://cdn.test/pixel/red/joker.png");
+        assert_eq!(themes[1].back, "");
+        assert_eq!(themes[1].image(&9), "https://cdn.test/legacy/9-C.jpg");
+
+        let theme = Theme { name: "code".to_string(), face: "/{code}.svg".to_string(), back: "/back.svg".to_string() };
+        assert_eq!(theme.image(&35), "/10H.svg");
+        assert_eq!(theme.image(&53), "/BJ.svg");
+    }
+
+    fn hand(codes: &str) -> Vec<PlayingCard> {
+        codes.split_whitespace().map(|code| code.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_poker_categories() {
+        let cases = [
+            ("AS KS QS JS 10S", HandCategory::StraightFlush, 1),
+            ("5H 4H 3H 2H AH", HandCategory::StraightFlush, 10),
+            ("AC AD AH AS KD", HandCategory::FourOfAKind, 11),
+            ("AC AD AH KS KD", HandCategory::FullHouse, 167),
+            ("AH KH QH JH 9H", HandCategory::Flush, 323),
+            ("7D 5D 4D 3D 2D", HandCategory::Flush, 1599),
+            ("AH KD QH JC 10S", HandCategory::Straight, 1600),
+            ("5C 4D 3H 2S AD", HandCategory::Straight, 1609),
+            ("AC AD AH KS QD", HandCategory::ThreeOfAKind, 1610),
+            ("AC AD KH KS QD", HandCategory::TwoPair, 2468),
+            ("AC AD KH QS JD", HandCategory::OnePair, 3326),
+            ("AC KD QH JS 9D", HandCategory::HighCard, 6186),
+            ("7C 5D 4H 3S 2D", HandCategory::HighCard, HAND_CLASSES),
+        ];
+
+        for (codes, category, rank) in cases.iter() {
+            let (value, _) = poker::evaluate(&hand(codes)).unwrap();
+            assert_eq!(value.category(), *category, "{}", codes);
+            assert_eq!(value.rank(), *rank, "{}", codes);
+        }
+    }
+
+    #[test]
+    fn test_poker_comparison() {
+        use std::cmp::Ordering;
+
+        assert_eq!(poker::compare(&hand("KC KD 5H 5S 2D"), &hand("KH KS 5C 5D 3C")), Ok(Ordering::Less));
+        assert_eq!(poker::compare(&hand("KC KD 5H 5S 2D"), &hand("KH KS 5C 5D 2C")), Ok(Ordering::Equal));
+        assert_eq!(poker::compare(&hand("2C 2D 2H 3S 3D"), &hand("AH KH QH JH 9H")), Ok(Ordering::Greater));
+        assert_eq!(poker::compare(&hand("6C 5D 4H 3S 2D"), &hand("5C 4D 3H 2S AD")), Ok(Ordering::Greater));
+
+        assert_eq!(poker::evaluate(&hand("AC KD QH JS")), Err(poker::PokerError::InvalidHandSize(4)));
+        assert_eq!(poker::evaluate(&hand("AC KD QH JS AC")), Err(poker::PokerError::DuplicateCard(PlayingCard::new(Rank::Ace, Suit::Clubs))));
+        assert_eq!(poker::evaluate(&hand("AC KD QH JS RJ")), Err(poker::PokerError::Joker));
+    }
+
+    #[test]
+    fn test_poker_best_of_seven() {
+        let (value, best) = poker::evaluate(&hand("AH KH 2C 2D 9H 4H 7H")).unwrap();
+        assert_eq!(value.category(), HandCategory::Flush);
+        assert_eq!(best, hand("AH KH 9H 4H 7H"));
+
+        let (value, best) = poker::evaluate(&hand("8C 8D 8H 2S 2D 9C 9D")).unwrap();
+        assert_eq!(value.category(), HandCategory::FullHouse);
+        assert_eq!(best, hand("8C 8D 8H 9C 9D"));
+    }
+
+    #[test]
+    fn test_poker_shared_card() {
+        let board = hand("2C 7D 9H");
+        let evaluation = poker::EvaluationRepresentation::evaluate(&[hand("AS KS"), hand("QD QC")], &board).unwrap();
+        assert_eq!(evaluation.winners, vec![1]);
+
+        let shared = poker::EvaluationRepresentation::evaluate(&[hand("AS KS"), hand("AS QC")], &board);
+        assert_eq!(shared.err(), Some(poker::PokerError::DuplicateCard(PlayingCard::new(Rank::Ace, Suit::Spades))));
+        let on_board = poker::EvaluationRepresentation::evaluate(&[hand("AS KS"), hand("7D QC")], &board);
+        assert_eq!(on_board.err(), Some(poker::PokerError::DuplicateCard(PlayingCard::new(Rank::Seven, Suit::Diamonds))));
+    }
+
+    #[test]
+    fn test_holdem_odds_exhaustive() {
+        let odds = odds::holdem(&hand("2C 7D"), &hand("AS KS QS JS 10S"), 1, 100).unwrap();
+        assert_eq!(odds.method, OddsMethod::Exhaustive);
+        assert_eq!(odds.scenarios, 990);
+        assert_eq!(odds.tie, 1.0);
+        assert_eq!(odds.equity, 0.5);
+
+        let odds = odds::holdem(&hand("AH AD"), &hand("AC 7S 7D 2H"), 1, 100).unwrap();
+        assert_eq!(odds.method, OddsMethod::Exhaustive);
+        assert_eq!(odds.scenarios, 46 * 990);
+        assert!((odds.win + odds.tie + odds.loss - 1.0).abs() < 1e-9);
+        assert!(odds.win > 0.99);
+    }
+
+    #[test]
+    fn test_holdem_odds_monte_carlo() {
+        let odds = odds::holdem(&hand("AH AD"), &[], 1, 20_000).unwrap();
+        assert_eq!(odds.method, OddsMethod::MonteCarlo);
+        assert_eq!(odds.scenarios, 20_000);
+        // Pocket aces win about 85% of the time against a random hand.
+        assert!((odds.equity - 0.85).abs() < 0.02, "{}", odds.equity);
+    }
+
+    #[test]
+    fn test_holdem_odds_errors() {
+        assert_eq!(odds::holdem(&hand("AH"), &[], 1, 10).unwrap_err(), OddsError::InvalidHoleCards(1));
+        assert_eq!(odds::holdem(&hand("AH AD"), &hand("2C 3C 4C 5C 6C 7C"), 1, 10).unwrap_err(), OddsError::InvalidBoard(6));
+        assert_eq!(odds::holdem(&hand("AH AD"), &[], 0, 10).unwrap_err(), OddsError::InvalidOpponents(0));
+        assert_eq!(odds::holdem(&hand("AH AD"), &hand("AH 2C 3C"), 1, 10).unwrap_err(), OddsError::UnavailableCard("AH".parse().unwrap()));
+        assert_eq!(odds::holdem(&hand("AH BJ"), &[], 1, 10).unwrap_err(), OddsError::Joker);
+    }
+
+    /// A shoe dealing the given cards in order.
+    fn stacked_shoe(codes: &str) -> Deck {
+        let mut shoe = Deck::shoe(1);
+        shoe.cards = motifs(codes).into_iter().rev().collect();
+        shoe
+    }
+
+    /// A table which never reshuffles the stacked shoe.
+    fn blackjack_table(dealer_hits_soft_17: bool) -> Table {
+        Table::new(Rules { reshuffle_at: 0, ..Rules::new(1, dealer_hits_soft_17) })
+    }
+
+    fn motifs(codes: &str) -> Vec<Card> {
+        hand(codes).iter().map(|card| card.motif()).collect()
+    }
+
+    #[test]
+    fn test_blackjack_score() {
+        assert_eq!(blackjack::score(&motifs("AC 6H")), Score { total: 17, soft: true });
+        assert_eq!(blackjack::score(&motifs("AC 6H KS")), Score { total: 17, soft: false });
+        assert_eq!(blackjack::score(&motifs("AC AD")), Score { total: 12, soft: true });
+        assert_eq!(blackjack::score(&motifs("KC QD 5H")), Score { total: 25, soft: false });
+        assert!(blackjack::is_blackjack(&motifs("AS JD")));
+        assert!(!blackjack::is_blackjack(&motifs("7S 7D 7H")));
+    }
+
+    #[test]
+    fn test_blackjack_dealer_soft_17() {
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("10C 6D 9C AS 4H");
+        table.deal(&mut shoe, 10).unwrap();
+        table.play(&mut shoe, Action::Stand).unwrap();
+        assert_eq!(table.dealer.len(), 2);
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Win));
+        assert_eq!(table.balance, 10);
+
+        let mut table = blackjack_table(true);
+        let mut shoe = stacked_shoe("10C 6D 9C AS 4H");
+        table.deal(&mut shoe, 10).unwrap();
+        table.play(&mut shoe, Action::Stand).unwrap();
+        assert_eq!(table.dealer.len(), 3);
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Lose));
+        assert_eq!(table.balance, -10);
+    }
+
+    #[test]
+    fn test_blackjack_double_and_surrender() {
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("5C 10D 6C 7D 10H");
+        table.deal(&mut shoe, 10).unwrap();
+        table.play(&mut shoe, Action::Double).unwrap();
+        assert_eq!(table.phase, Phase::Finished);
+        assert_eq!(table.hands[0].bet, 20);
+        assert_eq!(table.balance, 20);
+
+        let mut shoe = stacked_shoe("10C 10D 6C 9D");
+        table.deal(&mut shoe, 10).unwrap();
+        table.play(&mut shoe, Action::Surrender).unwrap();
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Surrender));
+        assert_eq!(table.balance, 15);
+        assert_eq!(table.play(&mut shoe, Action::Hit), Err(BlackjackError::IllegalAction));
+    }
+
+    #[test]
+    fn test_blackjack_split() {
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("8C 10H 8D 7S 3C 10C");
+        table.deal(&mut shoe, 10).unwrap();
+        table.play(&mut shoe, Action::Split).unwrap();
+        assert_eq!(table.hands.len(), 2);
+        assert_eq!(table.hands[0].cards, motifs("8C 3C"));
+
+        table.play(&mut shoe, Action::Stand).unwrap();
+        assert_eq!(table.active, 1);
+        assert_eq!(table.hands[1].cards, motifs("8D 10C"));
+
+        table.play(&mut shoe, Action::Stand).unwrap();
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Lose));
+        assert_eq!(table.hands[1].outcome, Some(Outcome::Win));
+        assert_eq!(table.balance, 0);
+    }
+
+    #[test]
+    fn test_blackjack_naturals_and_insurance() {
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("AC 9D KC 7S");
+        table.deal(&mut shoe, 10).unwrap();
+        assert_eq!(table.phase, Phase::Finished);
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Blackjack));
+        assert_eq!(table.balance, 15);
+
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("10C AD 9C KS");
+        table.deal(&mut shoe, 10).unwrap();
+        assert_eq!(table.phase, Phase::Insurance);
+        assert_eq!(table.play(&mut shoe, Action::Hit), Err(BlackjackError::IllegalAction));
+        table.insure(&mut shoe, true).unwrap();
+        assert_eq!(table.phase, Phase::Finished);
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Lose));
+        assert_eq!(table.balance, 0);
+
+        assert_eq!(table.deal(&mut shoe, 0), Err(BlackjackError::InvalidBet));
+        assert_eq!(table.deal(&mut shoe, blackjack::MAX_BET + 1), Err(BlackjackError::InvalidBet));
+        assert_eq!(table.deal(&mut shoe, u32::MAX), Err(BlackjackError::InvalidBet));
+        assert!("fold".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn test_blackjack_odd_bet() {
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("AC 9D KC 7S");
+        assert_eq!(table.deal(&mut shoe, 1), Err(BlackjackError::OddBet));
+        assert_eq!(table.phase, Phase::Betting);
+        table.deal(&mut shoe, 2).unwrap();
+        assert_eq!(table.hands[0].outcome, Some(Outcome::Blackjack));
+        assert_eq!(table.balance, 3);
+
+        let mut table = blackjack_table(false);
+        let mut shoe = stacked_shoe("10C AD 9C KS");
+        assert_eq!(table.deal(&mut shoe, 1), Err(BlackjackError::OddBet));
+        table.deal(&mut shoe, 2).unwrap();
+        table.insure(&mut shoe, true).unwrap();
+        assert_eq!(table.insurance, 1);
+        assert_eq!(table.balance, 0);
+    }
+
+    fn played(cards: &str) -> Vec<Played> {
+        motifs(cards).into_iter().enumerate().map(|(seat, card)| Played { seat, card }).collect()
+    }
+
+    #[test]
+    fn test_belote_card_ranking() {
+        let order = |code: &str| belote::card_order(code.parse().unwrap(), Suit::Hearts);
+        assert!(order("JH") > order("9H") && order("9H") > order("AH") && order("AH") > order("10H"));
+        assert!(order("AS") > order("10S") && order("10S") > order("KS") && order("JS") > order("9S"));
+
+        let points: i32 = Deck::new(DeckSize::Small).cards.iter()
+            .map(|card| belote::card_points(PlayingCard::try_from(*card).unwrap(), Suit::Hearts))
+            .sum();
+        assert_eq!(points + 10, belote::ROUND_POINTS);
+
+        assert_eq!(belote::trick_winner(&played("AS 10S 7H KS"), Suit::Hearts), Some(2));
+        assert_eq!(belote::trick_winner(&played("9S AD 10S KS"), Suit::Hearts), Some(2));
+        assert_eq!(belote::trick_winner(&played("9H JH AH 7H"), Suit::Hearts), Some(1));
+    }
+
+    #[test]
+    fn test_belote_legal_cards() {
+        let legal = |hand: &str, trick: &str, seat: usize| -> Vec<Card> {
+            belote::legal_cards(&motifs(hand), &played(trick), seat, Suit::Hearts)
+        };
+        // Following the led suit.
+        assert_eq!(legal("7S AD 8H", "KS", 1), motifs("7S"));
+        // Trumping without the led suit, going over the highest trump when possible.
+        assert_eq!(legal("AD 8H JH", "KS", 1), motifs("8H JH"));
+        assert_eq!(legal("AD 8H JH", "KS 9H", 2), motifs("JH"));
+        assert_eq!(legal("AD 8H 7H", "KS AH", 2), motifs("8H 7H"));
+        // Going over the highest trump when trump is led.
+        assert_eq!(legal("7H JH AS", "9H", 1), motifs("JH"));
+        assert_eq!(legal("7H 8H AS", "9H", 1), motifs("7H 8H"));
+        // The partner wins the trick.
+        assert_eq!(legal("AD 8H", "AS 7S", 2), motifs("AD 8H"));
+    }
+
+    /// A small deck dealing the given cards first, in order.
+    fn stacked_belote_deck(codes: &str) -> Deck {
+        let mut deck = Deck::new(DeckSize::Small);
+        let first = motifs(codes);
+        deck.cards.retain(|card| !first.contains(card));
+        deck.cards.extend(first.into_iter().rev());
+        deck
+    }
+
+    #[test]
+    fn test_belote_bidding() {
+        let mut game = belote::Game::new(1000);
+        let mut deck = game.new_deck();
+        game.deal(&mut deck).unwrap();
+        assert!(game.hands.iter().all(|hand| hand.len() == 8));
+        assert_eq!(deck.cards.len(), 0);
+        assert_eq!(game.turn, 1);
+
+        assert_eq!(game.bid(&mut deck, 2, Bid::Pass), Err(BeloteError::NotYourTurn));
+        assert_eq!(game.bid(&mut deck, 1, Bid::Contract { value: 85, trump: Suit::Hearts }), Err(BeloteError::IllegalBid));
+        assert_eq!(game.bid(&mut deck, 1, Bid::Coinche), Err(BeloteError::IllegalBid));
+        game.bid(&mut deck, 1, Bid::Contract { value: 80, trump: Suit::Hearts }).unwrap();
+        assert_eq!(game.bid(&mut deck, 2, Bid::Contract { value: 80, trump: Suit::Spades }), Err(BeloteError::IllegalBid));
+        game.bid(&mut deck, 2, Bid::Contract { value: 100, trump: Suit::Spades }).unwrap();
+        assert_eq!(game.bid(&mut deck, 3, Bid::Surcoinche), Err(BeloteError::IllegalBid));
+        game.bid(&mut deck, 3, Bid::Coinche).unwrap();
+        game.bid(&mut deck, 0, Bid::Surcoinche).unwrap();
+
+        assert_eq!(game.phase, belote::Phase::Playing);
+        let contract = game.contract.unwrap();
+        assert_eq!((contract.seat, contract.value, contract.trump, contract.multiplier), (2, 100, Suit::Spades, 4));
+        assert_eq!(game.turn, 1);
+
+        // Everybody passes: the next dealer deals again.
+        let mut game = belote::Game::new(1000);
+        let mut deck = game.new_deck();
+        game.deal(&mut deck).unwrap();
+        for seat in [1, 2, 3, 0] {
+            game.bid(&mut deck, seat, Bid::Pass).unwrap();
+        }
+        assert_eq!(game.phase, belote::Phase::Bidding);
+        assert_eq!(game.dealer, 1);
+        assert_eq!(game.turn, 2);
+        assert!(game.hands.iter().all(|hand| hand.len() == 8));
+    }
+
+    #[test]
+    fn test_belote_seats() {
+        let mut game = belote::Game::new(1000);
+        let mut deck = game.new_deck();
+        game.deal(&mut deck).unwrap();
+        assert_eq!(game.seats.len(), belote::PLAYERS);
+        assert_eq!(game.seat(&game.seats[3].token), Some(3));
+        assert_eq!(game.seat_by_id(game.seats[1].id), Some(1));
+        assert_ne!(game.seats[0].token, game.seats[1].token);
+
+        let seated = belote::GameRepresentation::translated(Uuid::new_v4(), &game, Some(1), Language::English, &Theme::default_theme());
+        assert_eq!(seated.seat, Some(1));
+        assert_eq!(seated.hand.map(|hand| hand.len()), Some(8));
+        assert!(seated.legal.is_none());
+        let json = serde_json::to_value(belote::GameRepresentation::translated(Uuid::new_v4(), &game, None, Language::English, &Theme::default_theme())).unwrap();
+        assert!(json["hand"].is_null());
+        assert!(json.get("tokens").is_none());
+
+        // The games stored before the seats can't be played anymore.
+        let mut state = serde_json::to_value(&game).unwrap();
+        state.as_object_mut().unwrap().remove("seats");
+        let stored: belote::Game = serde_json::from_value(state).unwrap();
+        assert_eq!(stored.seat(&game.seats[0].token), None);
+    }
+
+    #[test]
+    fn test_belote_round() {
+        // Seat 2 receives the king and the queen of hearts in its first packet.
+        let mut game = belote::Game::new(100);
+        let mut deck = stacked_belote_deck("7C 8C 9C KH QH 7H");
+        game.deal(&mut deck).unwrap();
+        assert_eq!(game.hands[1][..3], motifs("7C 8C 9C")[..]);
+        assert_eq!(game.hands[2][..3], motifs("KH QH 7H")[..]);
+
+        for seat in [1, 2, 3] {
+            game.bid(&mut deck, seat, Bid::Pass).unwrap();
+        }
+        game.bid(&mut deck, 0, Bid::Contract { value: 80, trump: Suit::Hearts }).unwrap();
+        for seat in [1, 2, 3] {
+            game.bid(&mut deck, seat, Bid::Pass).unwrap();
+        }
+        assert_eq!(game.belote, Some(2));
+
+        let card = PlayingCard::try_from(game.hands[0][0]).unwrap();
+        assert_eq!(game.play(&mut deck, 2, card), Err(BeloteError::NotYourTurn));
+        assert_eq!(game.play(&mut deck, 1, card), Err(BeloteError::CardNotInHand(card)));
+
+        while game.phase == belote::Phase::Playing {
+            let seat = game.turn;
+            let card = PlayingCard::try_from(game.legal_cards()[0]).unwrap();
+            game.play(&mut deck, seat, card).unwrap();
+        }
+
+        let result = game.result.unwrap();
+        assert_eq!(result.points[0] + result.points[1], belote::ROUND_POINTS + 20);
+        assert_eq!(game.scores, [result.scores[0], result.scores[1]]);
+        assert_eq!(deck.cards.len(), 32);
+        assert!(deck.discarded.is_empty());
+        assert!(game.phase == belote::Phase::Over || game.phase == belote::Phase::Finished);
+    }
+
+    /// Plays the first legal card until the end of the round.
+    fn play_round<R: TrickRules>(rules: &R, round: &mut Round) {
+        while !round.is_over() {
+            let seat = round.turn;
+            let card = PlayingCard::try_from(rules.legal_cards(round, seat)[0]).unwrap();
+            round.play(rules, seat, card).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_trick_round() {
+        let mut deck = Whist.new_deck();
+        let last = deck.cards[0];
+        let mut round = Round::deal(&Whist, &mut deck, 3).unwrap();
+        assert!(round.hands.iter().all(|hand| hand.len() == 13));
+        assert_eq!(round.trump, PlayingCard::try_from(last).unwrap().suit());
+        assert!(round.hands[3].contains(&last));
+        assert_eq!(round.turn, 0);
+        assert!(Round::deal(&Whist, &mut deck, 0).is_none());
+
+        let card = PlayingCard::try_from(round.hands[1][0]).unwrap();
+        assert_eq!(round.play(&Whist, 1, card), Err(TrickError::NotYourTurn));
+        assert_eq!(round.play(&Whist, 0, card), Err(TrickError::CardNotInHand(card)));
+
+        play_round(&Whist, &mut round);
+        assert_eq!(round.tricks.len(), 13);
+        let scores = Whist.scores(&round);
+        let won = round.tricks_won(4);
+        assert_eq!(scores[0], scores[2]);
+        assert_eq!(scores[0] + scores[1], (won[0] + won[2]).saturating_sub(6) as i32 + (won[1] + won[3]).saturating_sub(6) as i32);
+        assert_eq!(round.play(&Whist, round.turn, card), Err(TrickError::IllegalAction));
+    }
+
+    #[test]
+    fn test_trick_winner_with_trump() {
+        let mut round = Round { dealer: 0, hands: vec![Vec::new(); 4], trump: Some(Suit::Spades), turn: 0, trick: Vec::new(), tricks: Vec::new() };
+        assert_eq!(Whist.trick_winner(&round, &played("KH AH 2S 3D")), 2);
+        assert_eq!(Whist.trick_winner(&round, &played("KH AH 2H 3D")), 1);
+        round.trump = None;
+        assert_eq!(Hearts.trick_winner(&round, &played("10H AS 2S JH")), 3);
+    }
+
+    #[test]
+    fn test_hearts_rules() {
+        let mut deck = Hearts.new_deck();
+        let mut round = Round::deal(&Hearts, &mut deck, 0).unwrap();
+        let two = motifs("2C")[0];
+        assert!(round.hands[round.turn].contains(&two));
+        assert_eq!(Hearts.legal_cards(&round, round.turn), vec![two]);
+
+        // Points cannot be played on the first trick, and hearts cannot be led before being broken.
+        round.hands = vec![motifs("2C 3H"), motifs("QS 4H 5D"), motifs("6H 7D"), motifs("2H AH")];
+        round.turn = 0;
+        round.play(&Hearts, 0, "2C".parse().unwrap()).unwrap();
+        assert_eq!(Hearts.legal_cards(&round, 1), motifs("5D"));
+        assert_eq!(Hearts.legal_cards(&round, 3), motifs("2H AH"));
+
+        round.trick.clear();
+        round.tricks = vec![Trick { cards: played("2C 3C 4C 5C"), winner: 3 }];
+        round.turn = 1;
+        assert_eq!(Hearts.legal_cards(&round, 1), motifs("QS 5D"));
+        assert_eq!(Hearts.legal_cards(&round, 3), motifs("2H AH"));
+
+        round.tricks = vec![Trick { cards: played("QS 4H AH 2H"), winner: 2 }, Trick { cards: played("3H 5D"), winner: 0 }];
+        assert_eq!(Hearts.scores(&round), vec![1, 0, 16, 0]);
+
+        let moon: Vec<Trick> = ["QS 2H 3H 4H", "5H 6H 7H 8H", "9H 10H JH QH", "KH AH 2C 3C"].iter()
+            .map(|cards| Trick { cards: played(cards), winner: 1 })
+            .collect();
+        round.tricks = moon;
+        assert_eq!(Hearts.scores(&round), vec![hearts::MOON, 0, hearts::MOON, hearts::MOON]);
+
+        let mut deck = Hearts.new_deck();
+        let mut round = Round::deal(&Hearts, &mut deck, 2).unwrap();
+        play_round(&Hearts, &mut round);
+        let scores = Hearts.scores(&round);
+        assert!(scores.iter().sum::<i32>() == hearts::MOON || scores.iter().sum::<i32>() == 3 * hearts::MOON);
+    }
+
+    #[test]
+    fn test_tarot_deck() {
+        let deck = Deck::new(DeckSize::Tarot);
+        assert_eq!(deck.cards.len(), 78);
+        assert_eq!(tarot::count_points(&deck.cards), tarot::TOTAL_POINTS);
+        assert_eq!(tarot::count_oudlers(&deck.cards), 3);
+
+        assert_eq!(card_generator::get_card_name(PlayingCard::Knight(Suit::Hearts), Language::French), "Cavalier de Coeur");
+        assert_eq!(card_generator::get_card_name(PlayingCard::Knight(Suit::Clubs), Language::German), "Kreuz Reiter");
+        assert_eq!(card_generator::get_card_name(PlayingCard::Trump(21), Language::English), "Trump 21");
+        assert_eq!(card_generator::get_card_name(PlayingCard::Excuse, Language::French), "Excuse");
+        assert!(render_card(PlayingCard::Trump(7)).contains(">7</text>"));
+        assert_eq!(Theme::default_theme().image(&PlayingCard::Trump(1).motif()), "/assets/cards/T1.svg");
+
+        assert_eq!(tarot::score(Contract::Garde, 46.0, 2), 2 * (25 + 5));
+        assert_eq!(tarot::score(Contract::Petite, 50.5, 1), -26);
+    }
+
+    #[test]
+    fn test_tarot_deal() {
+        for (players, hand, chien) in [(3, 24, 6), (4, 18, 6), (5, 15, 3)] {
+            let mut deck = tarot::Round::new_deck();
+            let round = tarot::Round::deal(&mut deck, players, 0).unwrap();
+            assert!(round.hands.iter().all(|cards| cards.len() == hand));
+            assert_eq!(round.chien.len(), chien);
+            assert!(deck.cards.is_empty());
+        }
+        let mut deck = tarot::Round::new_deck();
+        assert_eq!(tarot::Round::deal(&mut deck, 6, 0).unwrap_err(), TarotError::InvalidPlayers(6));
+    }
+
+    #[test]
+    fn test_tarot_bidding() {
+        let mut deck = tarot::Round::new_deck();
+        let mut round = tarot::Round::deal(&mut deck, 4, 3).unwrap();
+        assert_eq!(round.bid(1, None), Err(TarotError::NotYourTurn));
+        round.bid(0, Some(Contract::Garde)).unwrap();
+        assert_eq!(round.bid(1, Some(Contract::Petite)), Err(TarotError::IllegalBid));
+        round.bid(1, None).unwrap();
+        round.bid(2, Some(Contract::GardeSans)).unwrap();
+        round.bid(3, None).unwrap();
+        assert_eq!(round.phase, tarot::Phase::Playing);
+        assert_eq!(round.taker, Some(2));
+        assert_eq!(round.discard.len(), 6);
+        assert!(round.chien.is_empty());
+
+        let mut deck = tarot::Round::new_deck();
+        let mut round = tarot::Round::deal(&mut deck, 4, 0).unwrap();
+        for seat in [1, 2, 3, 0] {
+            round.bid(seat, None).unwrap();
+        }
+        assert_eq!(round.phase, tarot::Phase::Passed);
+    }
+
+    #[test]
+    fn test_tarot_discard() {
+        let mut deck = tarot::Round::new_deck();
+        let mut round = tarot::Round::deal(&mut deck, 4, 0).unwrap();
+        round.hands[1] = motifs("KH T1 T5 2H 3H 4H 5H 6H 7H 8H 9H 10H JH QH CH AH 2S 3S");
+        round.chien = motifs("EX T2 T3 T4 T6 T7");
+        round.bid(1, Some(Contract::Petite)).unwrap();
+        for seat in [2, 3, 0] {
+            round.bid(seat, None).unwrap();
+        }
+        assert_eq!(round.phase, tarot::Phase::Discarding);
+        assert_eq!(round.hands[1].len(), 24);
+
+        assert_eq!(round.discard(0, &motifs("2H 3H 4H 5H 6H 7H")), Err(TarotError::NotYourTurn));
+        assert_eq!(round.discard(1, &motifs("KH 3H 4H 5H 6H 7H")), Err(TarotError::InvalidDiscard));
+        assert_eq!(round.discard(1, &motifs("T5 3H 4H 5H 6H 7H")), Err(TarotError::InvalidDiscard));
+        assert_eq!(round.discard(1, &motifs("2H 2H 4H 5H 6H 7H")), Err(TarotError::InvalidDiscard));
+        assert_eq!(round.discard(1, &motifs("2C 3H 4H 5H 6H 7H")), Err(TarotError::InvalidDiscard));
+        round.discard(1, &motifs("2H 3H 4H 5H 6H 7H")).unwrap();
+        assert_eq!(round.hands[1].len(), 18);
+        assert_eq!(round.phase, tarot::Phase::Playing);
+        assert_eq!(round.turn, 1);
+    }
+
+    #[test]
+    fn test_game_turns() {
+        let mut draw_pile = Deck::new(DeckSize::Normal);
+        let mut game = game::Game::new(draw_pile.id, 3);
+        let alice = game.join("Alice").unwrap().token.clone();
+        assert_eq!(game.take(&mut draw_pile, Some(&alice), 1), Err(GameError::IllegalAction));
+        let bob = game.join(" Bob ").unwrap().token.clone();
+        game.join("Carol").unwrap();
+        assert_eq!(game.status, game::Status::Playing);
+        assert_eq!(game.join("Dave").unwrap_err(), GameError::GameFull);
+        assert_ne!(alice, bob);
+        assert_eq!(game.player(&bob).unwrap().name, "Bob");
+
+        assert_eq!(game.take(&mut draw_pile, None, 1), Err(GameError::InvalidToken));
+        assert_eq!(game.take(&mut draw_pile, Some("nope"), 1), Err(GameError::InvalidToken));
+        assert_eq!(game.take(&mut draw_pile, Some(&bob), 1), Err(GameError::NotYourTurn));
+        assert_eq!(game.take(&mut draw_pile, Some(&alice), 2), Ok(vec![51, 50]));
+        assert_eq!(draw_pile.cards.len(), 50);
+        assert_eq!(draw_pile.discarded, vec![51, 50]);
+
+        assert_eq!(game.end_turn(Some(&bob)), Err(GameError::NotYourTurn));
+        game.end_turn(Some(&alice)).unwrap();
+        assert_eq!(game.turn, 1);
+        game.take(&mut draw_pile, Some(&bob), 1).unwrap();
+
+        let representation = GameRepresentation::translated(Uuid::new_v4(), &game, &draw_pile, Some(&alice), Language::English, &Theme::default_theme());
+        assert_eq!(representation.players.iter().map(|player| player.cards_count).collect::<Vec<usize>>(), vec![2, 1, 0]);
+        assert_eq!(representation.hand.unwrap()[0].name, "King of Spades");
+        assert_eq!(representation.draw_pile.cards_count, 49);
+        let anonymous = GameRepresentation::translated(Uuid::new_v4(), &game, &draw_pile, None, Language::English, &Theme::default_theme());
+        assert!(anonymous.hand.is_none());
+        // The decks belong to the session: their ids are never given.
+        let json = serde_json::to_string(&anonymous).unwrap();
+        assert!(!json.contains(&draw_pile.id.to_string()));
+    }
+
+    #[test]
+    fn test_event_hub_visibility() {
+        let hub = EventHub::new();
+        let game_id = Uuid::new_v4();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut alice_events = hub.subscribe(game_id, Some(alice));
+        let mut bob_events = hub.subscribe(game_id, Some(bob));
+        let mut spectator_events = hub.subscribe(game_id, None);
+        let mut other_events = hub.subscribe(Uuid::new_v4(), None);
+
+        let taken = hub.publish(game_id, EventKind::Taken, Audience::Everyone, ());
+        let hand = hub.publish(game_id, EventKind::Hand, Audience::Player(alice), vec!["AS"]);
+        assert!(hand.id > taken.id);
+
+        assert_eq!(alice_events.try_recv().unwrap().id, taken.id);
+        assert_eq!(alice_events.try_recv().unwrap().data, serde_json::json!(["AS"]));
+        assert_eq!(bob_events.try_recv().unwrap().kind, EventKind::Taken);
+        assert!(bob_events.try_recv().is_err());
+        assert_eq!(spectator_events.try_recv().unwrap().kind, EventKind::Taken);
+        assert!(spectator_events.try_recv().is_err());
+        assert!(other_events.try_recv().is_err());
+
+        hub.close(game_id);
+        assert!(matches!(alice_events.try_recv(), Err(TryRecvError::Closed)));
+    }
+
+    #[test]
+    fn test_deck_change_event() {
+        let mut deck = Deck::new(DeckSize::Normal);
+        let results = batch::apply(&mut deck, &[
+            Operation::Take { lifo: Some(true), length: Some(1), move_as_block: None },
+            Operation::Deal { hands: 2, length: 1 },
+        ]).unwrap();
+        let change = DeckChange::new(&deck, &results);
+        assert_eq!(change.moved, vec!["KS"]);
+        assert_eq!(change.dealt, vec![vec!["QS"], vec!["JS"]]);
+        assert_eq!(change.cards_count, 49);
+        assert_eq!(change.discarded_count, 3);
+
+        let shuffled = serde_json::to_value(DeckChange::new(&deck, &[StepResult::Shuffled])).unwrap();
+        assert_eq!(shuffled, serde_json::json!({ "cards_count": 49, "discarded_count": 3, "moved": [] }));
+    }
+
+    #[test]
+    fn test_game_notices() {
+        let mut draw_pile = Deck::new(DeckSize::Normal);
+        let mut game = game::Game::new(draw_pile.id, 2);
+        let alice = game.join("Alice").unwrap().token.clone();
+        game.join("Bob").unwrap();
+
+        let notices = game.run(&mut draw_pile, Some(&alice), &game::Command::Take { length: Some(2) }).unwrap();
+        assert_eq!(notices[0].audience, Audience::Everyone);
+        assert_eq!(notices[0].data["count"], 2);
+        assert!(notices[0].data.get("moved").is_none());
+        assert_eq!(notices[1].audience, Audience::Player(game.players[0].id));
+        assert_eq!(notices[1].data["moved"], serde_json::json!(["KS", "QS"]));
+
+        let notices = game.run(&mut draw_pile, Some(&alice), &game::Command::EndTurn).unwrap();
+        assert_eq!(notices[0].kind, EventKind::TurnEnded);
+        assert_eq!(notices[0].data["turn"], 1);
+        assert_eq!(game.run(&mut draw_pile, Some(&alice), &game::Command::EndTurn).unwrap_err(), GameError::NotYourTurn);
+    }
+
+    #[test]
+    fn test_event_hub_resume() {
+        let hub = EventHub::new();
+        let (deck_id, game_id, alice) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let first = hub.publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+        hub.publish(game_id, EventKind::Taken, Audience::Everyone, ());
+        hub.publish(game_id, EventKind::Hand, Audience::Player(alice), ());
+        let taken = hub.publish(deck_id, EventKind::Taken, Audience::Everyone, ());
+
+        let (missed, mut events) = hub.resume(deck_id, None, Some(first.id));
+        assert_eq!(missed.iter().map(|event| event.id).collect::<Vec<u64>>(), vec![taken.id]);
+        assert!(hub.resume(deck_id, None, Some(taken.id)).0.is_empty());
+        assert!(hub.resume(deck_id, None, None).0.is_empty());
+        assert_eq!(hub.resume(game_id, None, Some(0)).0.len(), 1);
+        assert_eq!(hub.resume(game_id, Some(alice), Some(0)).0.len(), 2);
+
+        let put = hub.publish(deck_id, EventKind::Put, Audience::Everyone, ());
+        assert_eq!(events.try_recv().unwrap().id, put.id);
+        for _ in 0..events::HISTORY_SIZE {
+            hub.publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+        }
+        assert_eq!(hub.resume(deck_id, None, Some(0)).0.len(), events::HISTORY_SIZE);
+
+        hub.close(deck_id);
+        assert!(hub.resume(deck_id, None, Some(0)).0.is_empty());
+    }
+
+    #[test]
+    fn test_event_hub_max_topics() {
+        let hub = EventHub::with_max_topics(2);
+        let (first_deck, second_deck, third_deck) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        hub.publish(first_deck, EventKind::Shuffled, Audience::Everyone, ());
+        hub.publish(second_deck, EventKind::Shuffled, Audience::Everyone, ());
+        hub.publish(first_deck, EventKind::Taken, Audience::Everyone, ());
+        hub.publish(third_deck, EventKind::Shuffled, Audience::Everyone, ());
+
+        assert_eq!(hub.resume(first_deck, None, Some(0)).0.len(), 2);
+        assert!(hub.resume(second_deck, None, Some(0)).0.is_empty());
+        assert_eq!(hub.resume(third_deck, None, Some(0)).0.len(), 1);
+
+        let mut events = hub.subscribe(second_deck, None);
+        let put = hub.publish(second_deck, EventKind::Put, Audience::Everyone, ());
+        assert_eq!(events.try_recv().unwrap().id, put.id);
+        assert!(hub.resume(first_deck, None, Some(0)).0.is_empty());
+    }
+
+    #[test]
+    fn test_event_ids_grow_across_restarts() {
+        let deck_id = Uuid::new_v4();
+        let before = EventHub::new().publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+        thread::sleep(Duration::from_millis(2));
+        let after = EventHub::new().publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+        assert!(after.id > before.id);
+        assert!(after.id < 1 << 53);
+    }
+
+    #[test]
+    fn test_event_stream_keep_alive() {
+        let hub = EventHub::new();
+        let deck_id = Uuid::new_v4();
+        let missed = hub.publish(deck_id, EventKind::Shuffled, Audience::Everyone, ());
+        let (missed_events, receiver) = hub.resume(deck_id, None, Some(missed.id - 1));
+        let mut stream = events::sse_stream(missed_events, receiver, Duration::from_millis(20));
+
+        actix_web::rt::System::new("test_event_stream_keep_alive").block_on(async move {
+            assert_eq!(stream.next().await.unwrap(), format!("retry: {}\n\n", events::RETRY));
+            assert_eq!(stream.next().await.unwrap(), missed.to_sse());
+            assert_eq!(stream.next().await.unwrap(), events::PING);
+
+            let taken = hub.publish(deck_id, EventKind::Taken, Audience::Everyone, ());
+            assert_eq!(stream.next().await.unwrap(), taken.to_sse());
+            hub.close(deck_id);
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_event_to_sse() {
+        let hub = EventHub::new();
+        let deck_id = Uuid::new_v4();
+        let event = hub.publish(deck_id, EventKind::Taken, Audience::Everyone, vec!["AS"]);
+        assert_eq!(
+            event.to_sse(),
+            format!("id: {0}\nevent: taken\ndata: {{\"id\":{0},\"topic\":\"{1}\",\"type\":\"taken\",\"data\":[\"AS\"]}}\n\n", event.id, deck_id)
+        );
+    }
+
+    /// Reads an HTTP request, headers and body.
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text.lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length || read == 0 {
+                    return text;
+                }
+            }
+        }
+    }
+
+    /// A local HTTP stand-in answering with the given statuses, giving back the requests it received.
+    fn stand_in(statuses: Vec<u16>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            statuses.into_iter()
+                .map(|status| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let request = read_request(&mut stream);
+                    write!(stream, "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+                    request
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    fn delivery(url: &str) -> Delivery {
+        let deck = Deck::new(DeckSize::Normal);
+        let payload = Payload::new(WebhookEvent::Drawn, &deck);
+        Delivery {
+            webhook: Webhook::new(url, None, WebhookEvent::ALL.to_vec()),
+            event: WebhookEvent::Drawn,
+            body: serde_json::to_string(&payload).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_webhook_signature() {
+        assert_eq!(
+            webhook::sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_eq!(WebhookEvent::from_name("emptied"), Some(WebhookEvent::Emptied));
+        assert_eq!(WebhookEvent::from_name("dealt"), None);
+    }
+
+    #[test]
+    fn test_webhook_retries() {
+        let (url, stand_in) = stand_in(vec![500, 503, 204]);
+        let delivery = delivery(&url);
+        let policy = RetryPolicy { attempts: 5, base_delay: Duration::from_millis(1) };
+        assert_eq!(webhook::send(&delivery, &policy), Ok(3));
+
+        let requests = stand_in.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        let signature = format!("x-webhook-signature: sha256={}", webhook::sign(&delivery.webhook.secret, &delivery.body));
+        for request in requests {
+            assert!(request.starts_with("POST /hook "));
+            assert!(request.to_lowercase().contains(&signature));
+            assert!(request.to_lowercase().contains("x-webhook-event: drawn"));
+            assert!(request.ends_with(&delivery.body));
+        }
+    }
+
+    #[test]
+    fn test_webhook_dead_letter() {
+        let (url, stand_in) = stand_in(vec![500, 500]);
+        let delivery = delivery(&url);
+        let policy = RetryPolicy { attempts: 2, base_delay: Duration::from_millis(1) };
+        let error = webhook::send(&delivery, &policy).unwrap_err();
+        assert_eq!(error.attempts, 2);
+        assert_eq!(error.error, "Unexpected status 500");
+        assert_eq!(stand_in.join().unwrap().len(), 2);
+
+        let dead_letter = DeadLetter::new(&delivery, &error);
+        assert_eq!(dead_letter.webhook_id, delivery.webhook.id);
+        assert_eq!(dead_letter.payload["event"], "drawn");
+        assert_eq!(dead_letter.payload["cards_count"], 52);
+    }
+
+    #[test]
+    fn test_webhook_private_host() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        assert_eq!(webhook::check_url(&url), Err(webhook::UrlError::ForbiddenHost));
+
+        let policy = RetryPolicy { attempts: 2, base_delay: Duration::from_millis(1) };
+        let error = webhook::deliver(&delivery(&url), &policy).unwrap_err();
+        assert_eq!(error.attempts, 2);
+        assert!(listener.accept().is_err());
+    }
+
+    #[test]
+    fn test_webhook_owner() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut webhook = Webhook::new("https://example.com/hook", Some(Uuid::new_v4()), WebhookEvent::ALL.to_vec());
+        assert_eq!(webhook.authorize(Some(owner)), Err(AccessError::AdminRequired));
+
+        webhook.owner = Some(owner);
+        assert_eq!(webhook.authorize(Some(owner)), Ok(()));
+        assert_eq!(webhook.authorize(Some(other)), Err(AccessError::Forbidden));
+        assert_eq!(webhook.authorize(None), Err(AccessError::Unauthorized));
+    }
+
+    #[test]
+    fn test_api_key_generation() {
+        let (api_key, key) = ApiKey::generate(" Backend ");
+        assert_eq!(api_key.name, "Backend");
+        assert!(key.starts_with(api_key::KEY_PREFIX));
+        assert_eq!(key.len(), api_key::KEY_PREFIX.len() + 64);
+        assert_eq!(api_key.key_hash, ApiKey::hash(&key));
+        assert_ne!(api_key.key_hash, key);
+        assert_ne!(ApiKey::generate("Backend").1, key);
+        assert_eq!(ApiKey::hash("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn test_deck_access() {
+        let (owner, grantee, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let granted = |api_key: Uuid| api_key == grantee;
+        assert_eq!(api_key::check_access(None, None, granted), Ok(()));
+        assert_eq!(api_key::check_access(Some(other), None, granted), Ok(()));
+        assert_eq!(api_key::check_access(Some(owner), Some(owner), granted), Ok(()));
+        assert_eq!(api_key::check_access(Some(grantee), Some(owner), granted), Ok(()));
+        assert_eq!(api_key::check_access(Some(other), Some(owner), granted), Err(AccessError::Forbidden));
+        assert_eq!(api_key::check_access(None, Some(owner), granted), Err(AccessError::Unauthorized));
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let deck_id = Uuid::new_v4();
+        let claims = Claims::new(Uuid::new_v4(), Some(deck_id), None, vec![Scope::Read, Scope::Draw], 60);
+        let token = jwt::issue(b"secret", &claims);
+        assert!(jwt::is_token(&token));
+        assert!(!jwt::is_token(&format!("{}deadbeef", api_key::KEY_PREFIX)));
+        assert_eq!(jwt::verify(b"secret", &token), Some(claims));
+        assert_eq!(jwt::verify(b"other", &token), None);
+
+        let expired = Claims::new(Uuid::new_v4(), Some(deck_id), None, vec![Scope::Read], -1);
+        assert_eq!(jwt::verify(b"secret", &jwt::issue(b"secret", &expired)), None);
+
+        let mut parts: Vec<String> = token.split('.').map(str::to_string).collect();
+        let forged = Claims::new(Uuid::new_v4(), Some(deck_id), None, vec![Scope::Admin], 60);
+        parts[1] = jwt::issue(b"other", &forged).split('.').nth(1).unwrap().to_string();
+        assert_eq!(jwt::verify(b"secret", &parts.join(".")), None);
+    }
+
+    #[test]
+    fn test_token_scopes() {
+        let (deck_id, game_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let player = Claims::new(Uuid::new_v4(), None, Some(game_id), vec![Scope::Read, Scope::Draw], 60);
+        assert_eq!(jwt::check_scopes(&player, game_id, &[Scope::Read, Scope::Draw]), Ok(()));
+        assert_eq!(jwt::check_scopes(&player, game_id, &[Scope::Shuffle]), Err(AccessError::MissingScope));
+        assert_eq!(jwt::check_scopes(&player, deck_id, &[Scope::Read]), Err(AccessError::MissingScope));
+        assert_eq!(AccessError::MissingScope.message().code(), "missing_scope");
+        assert_eq!(AccessError::AdminRequired.message().code(), "admin_required");
+
+        let admin = Claims::new(Uuid::new_v4(), Some(deck_id), None, vec![Scope::Admin], 60);
+        assert_eq!(jwt::check_scopes(&admin, deck_id, &[Scope::Shuffle, Scope::Put, Scope::Admin]), Ok(()));
+
+        let operations: Vec<Operation> = serde_json::from_str(r#"[
+            {"type": "shuffle"},
+            {"type": "take"},
+            {"type": "deal", "hands": 2, "length": 1},
+            {"type": "put"},
+            {"type": "move", "cards": ["AS"]},
+            {"type": "move", "cards": ["AS"], "to_discarded": false}
+        ]"#).unwrap();
+        let scopes: Vec<Scope> = operations.iter().map(Operation::scope).collect();
+        assert_eq!(scopes, vec![Scope::Shuffle, Scope::Draw, Scope::Draw, Scope::Put, Scope::Draw, Scope::Put]);
+    }
+
+    #[test]
+    fn test_rate_limit_bucket() {
+        let limiter = RateLimiter::new(Limit { capacity: 3, period: Duration::from_secs(30) });
+        let now = Instant::now();
+        let first = limiter.check("key", now);
+        assert!(first.allowed);
+        assert_eq!((first.limit, first.remaining, first.reset, first.retry_after), (3, 2, 10, 0));
+        limiter.check("key", now);
+        assert_eq!(limiter.check("key", now).remaining, 0);
+
+        let refused = limiter.check("key", now);
+        assert!(!refused.allowed);
+        assert_eq!((refused.remaining, refused.reset, refused.retry_after), (0, 30, 10));
+        assert!(!limiter.check("key", now + Duration::from_secs(9)).allowed);
+        assert!(limiter.check("key", now + Duration::from_secs(10)).allowed);
+        assert!(limiter.check("other", now).allowed);
+
+        let rested = limiter.check("key", now + Duration::from_secs(3600));
+        assert_eq!((rested.remaining, rested.reset), (2, 10));
+    }
+
+    #[test]
+    fn test_rate_limit_max_clients() {
+        let limiter = RateLimiter::with_max_clients(Limit { capacity: 1, period: Duration::from_secs(3600) }, 4);
+        let now = Instant::now();
+        for (seconds, client) in ["a", "b", "c", "d"].iter().enumerate() {
+            assert!(limiter.check(client, now + Duration::from_secs(seconds as u64)).allowed);
+        }
+        assert!(limiter.check("e", now + Duration::from_secs(4)).allowed);
+
+        assert!(!limiter.check("d", now + Duration::from_secs(5)).allowed);
+        assert!(!limiter.peek("c", now + Duration::from_secs(5)).allowed);
+        assert!(limiter.check("a", now + Duration::from_secs(5)).allowed);
+    }
+
+    #[test]
+    fn test_rate_limit_configuration() {
+        assert_eq!("20/60".parse::<Limit>(), Ok(Limit { capacity: 20, period: Duration::from_secs(60) }));
+        assert_eq!(" 5 / 1 ".parse::<Limit>(), Ok(Limit { capacity: 5, period: Duration::from_secs(1) }));
+        assert!("0/60".parse::<Limit>().is_err());
+        assert!("20/0".parse::<Limit>().is_err());
+        assert!("20".parse::<Limit>().is_err());
+
+        assert_eq!(rate_limit::quota("POST", "/cards/"), Some(Quota::Creation));
+        assert_eq!(rate_limit::quota("POST", "/games"), Some(Quota::Creation));
+        assert_eq!(rate_limit::quota("POST", "/v1/cards"), Some(Quota::Creation));
+        assert_eq!(rate_limit::quota("POST", "/v2/decks/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1/deal"), Some(Quota::Mutation));
+        assert_eq!(rate_limit::quota("DELETE", "/cards/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1"), Some(Quota::Mutation));
+        assert_eq!(rate_limit::quota("POST", "/games/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1/join"), Some(Quota::Mutation));
+        assert_eq!(rate_limit::quota("GET", "/games/b7c2d5a4-5bb5-4b1c-9bd7-3f3b86f0a2a1"), None);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let counter = metrics::Counter::new("errors_total", "The errors.", &["code"]);
+        counter.inc(&["say \"hi\"\n"]);
+        let mut output = String::new();
+        counter.render(&mut output);
+        assert!(output.contains("errors_total{code=\"say \\\"hi\\\"\\n\"} 1\n"));
+
+        let gauge = metrics::Gauge::new("connections", "The connections.");
+        gauge.set(3);
+        let mut output = String::new();
+        gauge.render(&mut output);
+        assert_eq!(output, "# HELP connections The connections.\n# TYPE connections gauge\nconnections 3\n");
+
+        let before = metrics::DB_QUERY_DURATION.count(&["test_query"]);
+        assert_eq!(metrics::time_query("test_query", || 42), 42);
+        assert_eq!(metrics::DB_QUERY_DURATION.count(&["test_query"]), before + 1);
+        let rendered = metrics::render();
+        assert!(rendered.contains("db_query_duration_seconds_count{query=\"test_query\"}"));
+        assert!(rendered.contains("# TYPE http_request_duration_seconds histogram\n"));
+        assert!(rendered.contains("\ndecks_created_total "));
+    }
+
+    #[test]
+    fn test_problem() {
+        let problem = Problem::new(StatusCode::NOT_FOUND, &Message::DeckNotFound, None, Language::English);
+        assert_eq!(problem.title, "Not Found");
+        assert_eq!(problem.code, "deck_not_found");
+        assert_eq!(problem.detail, Message::DeckNotFound.translate(Language::English));
+        assert_eq!(Problem::new(StatusCode::NOT_FOUND, &Message::DeckNotFound, None, Language::German).code, problem.code);
+
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["type"], "about:blank");
+        assert_eq!(json["status"], 404);
+        assert!(json.get("parameter").is_none());
+
+        let json = serde_json::to_value(Problem::new(StatusCode::BAD_REQUEST, &Message::MissingParameter, Some("cards"), Language::French)).unwrap();
+        assert_eq!(json["parameter"], "cards");
+        assert_eq!(json["detail"], "Paramètre manquant.");
+        assert_eq!(Message::UnknownTheme("neon".to_string()).code(), "unknown_theme");
+    }
+
+    #[test]
+    fn test_request_id() {
+        assert_eq!(logging::request_id(Some("a1b2.c3_d4-e5")), "a1b2.c3_d4-e5");
+        assert!(Uuid::parse_str(&logging::request_id(Some(""))).is_ok());
+        assert!(Uuid::parse_str(&logging::request_id(Some("id\r\nSet-Cookie: x"))).is_ok());
+        assert!(Uuid::parse_str(&logging::request_id(Some(&"a".repeat(129)))).is_ok());
+        assert_eq!(logging::request_id(Some(&"a".repeat(128))), "a".repeat(128));
+        assert_ne!(logging::request_id(None), logging::request_id(None));
+
+        let mut deck = Deck::new(DeckSize::Normal);
+        let _span = logging::deck_span("test", deck.id);
+        assert_eq!(deck.take(false, 2, true).len(), 2);
+    }
+}
@@ -0,0 +1,115 @@
+use std::convert::TryFrom;
+use std::env;
+use crate::Card;
+use crate::card::PlayingCard;
+
+pub const DEFAULT_THEME: &str = "default";
+
+/// A set of card images, described by URL templates.
+///
+/// The face template can use the following placeholders:
+///
+/// * `{code}`: the short code of the card, e.g. `QH` or `RJ`.
+/// * `{rank}`: the rank of the card, e.g. `Q`, `10`, `joker`, `C` for a tarot knight or the number of a tarot trump.
+/// * `{suit}`: the suit of the card, e.g. `hearts`, the color of a joker (`red`, `black`) or `trumps` for the tarot trumps.
+/// * `{suit_letter}`: the suit letter of the card, e.g. `H`, `T` for the tarot trumps, empty for jokers and the Excuse.
+/// * `{motif}`: the internal value of the card, e.g. `37`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub face: String,
+    pub back: String,
+}
+
+impl Theme {
+    /// The theme used when none is requested: the external `ASSET_URL` when set, the rendered SVG otherwise.
+    pub fn default_theme() -> Theme {
+        match env::var("ASSET_URL").ok().filter(|asset_url| !asset_url.is_empty()) {
+            Some(asset_url) => {
+                let extension = env::var("ASSET_EXTENSION").unwrap_or_default();
+                Theme {
+                    name: DEFAULT_THEME.to_string(),
+                    face: format!("{}{{motif}}{}", asset_url, extension),
+                    back: format!("{}back{}", asset_url, extension),
+                }
+            }
+            None => Theme {
+                name: DEFAULT_THEME.to_string(),
+                face: "/assets/cards/{code}.svg".to_string(),
+                back: "/assets/cards/back.svg".to_string(),
+            }
+        }
+    }
+
+    /// Parses the themes configuration, a `;` separated list of `name=face_template|back_url`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::theme::Theme;
+    ///
+    /// fn main() {
+    ///     let themes = Theme::parse_all("neon=https://cdn.test/neon/{code}.png|https://cdn.test/neon/back.png");
+    ///     assert_eq!(themes[0].name, "neon");
+    ///     assert_eq!(themes[0].image(&37), "https://cdn.test/neon/QH.png");
+    /// }
+    /// ```
+    pub fn parse_all(configuration: &str) -> Vec<Theme> {
+        configuration.split(';')
+            .filter_map(|entry| {
+                let (name, templates) = entry.split_once('=')?;
+                let (face, back) = templates.split_once('|').unwrap_or((templates, ""));
+                let name = name.trim();
+                if name.is_empty() { return None; }
+
+                Some(Theme { name: name.to_string(), face: face.trim().to_string(), back: back.trim().to_string() })
+            })
+            .collect()
+    }
+
+    /// The themes configured with the `THEMES` environment variable, plus the default theme.
+    pub fn all() -> Vec<Theme> {
+        let mut themes = vec![Theme::default_theme()];
+        themes.extend(Theme::parse_all(&env::var("THEMES").unwrap_or_default()));
+        themes
+    }
+
+    pub fn find(name: &str) -> Option<Theme> {
+        Theme::all().into_iter().find(|theme| theme.name == name)
+    }
+
+    /// Finds the theme to use, the first known name winning, or the default theme.
+    pub fn resolve(names: &[Option<&str>]) -> Theme {
+        names.iter()
+            .flatten()
+            .find_map(|name| Theme::find(name))
+            .unwrap_or_else(Theme::default_theme)
+    }
+
+    pub fn image(&self, motif: &Card) -> String {
+        let card = PlayingCard::try_from(*motif).expect("Incorrect value.");
+        let (rank, suit, suit_letter) = match card {
+            PlayingCard::Standard { rank, suit } => (
+                rank.symbol().to_string(),
+                format!("{:?}", suit).to_lowercase(),
+                suit.letter().to_string(),
+            ),
+            PlayingCard::RedJoker => ("joker".to_string(), "red".to_string(), String::new()),
+            PlayingCard::BlackJoker => ("joker".to_string(), "black".to_string(), String::new()),
+            PlayingCard::Knight(suit) => (
+                "C".to_string(),
+                format!("{:?}", suit).to_lowercase(),
+                suit.letter().to_string(),
+            ),
+            PlayingCard::Trump(number) => (number.to_string(), "trumps".to_string(), "T".to_string()),
+            PlayingCard::Excuse => ("excuse".to_string(), "trumps".to_string(), String::new()),
+        };
+
+        self.face
+            .replace("{code}", &card.to_string())
+            .replace("{rank}", &rank)
+            .replace("{suit_letter}", &suit_letter)
+            .replace("{suit}", &suit)
+            .replace("{motif}", &motif.to_string())
+    }
+}
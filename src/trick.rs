@@ -0,0 +1,228 @@
+use std::convert::TryFrom;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::{Card, Deck, DeckSize};
+use crate::card::{PlayingCard, Rank, Suit};
+use crate::i18n::{Language, Message};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Played {
+    pub seat: usize,
+    pub card: Card,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trick {
+    pub cards: Vec<Played>,
+    pub winner: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrickError {
+    NotYourTurn,
+    IllegalAction,
+    CardNotInHand(PlayingCard),
+    IllegalCard(PlayingCard),
+}
+
+impl TrickError {
+    pub fn message(&self) -> Message {
+        match self {
+            TrickError::NotYourTurn => Message::NotYourTurn,
+            TrickError::IllegalAction => Message::IllegalAction,
+            TrickError::CardNotInHand(card) => Message::CardNotInHand(card.to_string()),
+            TrickError::IllegalCard(card) => Message::IllegalCard(card.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for TrickError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message().translate(Language::default()))
+    }
+}
+
+pub(crate) fn to_card(motif: Card) -> PlayingCard {
+    PlayingCard::try_from(motif).expect("Incorrect value.")
+}
+
+/// The suit led on a trick, if any card was played.
+pub fn led_suit(trick: &[Played]) -> Option<Suit> {
+    trick.first().and_then(|played| to_card(played.card).suit())
+}
+
+/// The cards of the led suit when the player has some, otherwise the whole hand.
+pub fn follow_suit(hand: &[Card], trick: &[Played]) -> Vec<Card> {
+    let led = match led_suit(trick) {
+        Some(led) => led,
+        None => return hand.to_vec()
+    };
+    let following: Vec<Card> = hand.iter().filter(|card| to_card(**card).suit() == Some(led)).copied().collect();
+    if following.is_empty() { hand.to_vec() } else { following }
+}
+
+/// Finds the seat winning a trick: the highest trump, or the highest card of the led suit.
+///
+/// # Arguments
+///
+/// * `trick`: The cards played on the trick, the first one being led.
+/// * `trump`: The trump suit, if any.
+/// * `order`: The rank of a card within its suit, the highest card winning.
+///
+/// returns: Option<usize>, the winning seat or None for an empty trick.
+pub fn winner<F: Fn(PlayingCard) -> u8>(trick: &[Played], trump: Option<Suit>, order: F) -> Option<usize> {
+    let led = led_suit(trick)?;
+    trick.iter()
+        .max_by_key(|played| {
+            let card = to_card(played.card);
+            match card.suit() {
+                suit if suit.is_some() && suit == trump => (2, order(card)),
+                Some(suit) if suit == led => (1, order(card)),
+                _ => (0, 0),
+            }
+        })
+        .map(|played| played.seat)
+}
+
+/// The rules of a trick-taking game, each hook having the most common behavior by default.
+pub trait TrickRules {
+    fn players(&self) -> usize;
+
+    /// Number of cards dealt to each player.
+    fn hand_size(&self) -> usize;
+
+    /// Creates the shuffled deck of the game.
+    fn new_deck(&self) -> Deck {
+        let mut deck = Deck::new(DeckSize::Normal);
+        deck.shuffle(false);
+        deck
+    }
+
+    /// Picks the trump suit once the cards are dealt, from the last dealt card.
+    fn trump(&self, _last_dealt: PlayingCard) -> Option<Suit> {
+        None
+    }
+
+    /// The rank of a card within its suit, aces high by default.
+    fn card_order(&self, card: PlayingCard, _trump: Option<Suit>) -> u8 {
+        match card.rank() {
+            Some(Rank::Ace) => 14,
+            Some(rank) => rank.index() as u8 + 1,
+            None => 0,
+        }
+    }
+
+    /// The seat leading the first trick, the left of the dealer by default.
+    fn first_leader(&self, round: &Round) -> usize {
+        (round.dealer + 1) % self.players()
+    }
+
+    /// The cards a player may play, following suit when possible by default.
+    fn legal_cards(&self, round: &Round, seat: usize) -> Vec<Card> {
+        follow_suit(&round.hands[seat], &round.trick)
+    }
+
+    fn trick_winner(&self, round: &Round, trick: &[Played]) -> usize {
+        winner(trick, round.trump, |card| self.card_order(card, round.trump)).expect("A full trick.")
+    }
+
+    /// The points of a trick for the seat winning it.
+    fn trick_points(&self, _trick: &[Played]) -> i32 {
+        0
+    }
+
+    /// The score of each seat at the end of the round, the sum of their trick points by default.
+    fn scores(&self, round: &Round) -> Vec<i32> {
+        let mut scores = vec![0; self.players()];
+        for trick in round.tricks.iter() {
+            scores[trick.winner] += self.trick_points(&trick.cards);
+        }
+        scores
+    }
+}
+
+/// A round of a trick-taking game, played with any `TrickRules`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Round {
+    pub dealer: usize,
+    pub hands: Vec<Vec<Card>>,
+    pub trump: Option<Suit>,
+    pub turn: usize,
+    pub trick: Vec<Played>,
+    pub tricks: Vec<Trick>,
+}
+
+impl Round {
+    /// Deals a round one card at a time from the top of the deck, starting at the left of the dealer.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules`: The rules of the game.
+    /// * `deck`: The deck to deal from, the dealt cards being put into its discarded stack.
+    /// * `dealer`: The seat of the dealer.
+    ///
+    /// returns: Option<Round>, None if there are not enough cards in the deck.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jeu_de_carte::trick::{Round, TrickRules};
+    /// use jeu_de_carte::hearts::Hearts;
+    ///
+    /// fn main() {
+    ///     let mut deck = Hearts.new_deck();
+    ///     let round = Round::deal(&Hearts, &mut deck, 0).unwrap();
+    ///     assert_eq!(round.hands[1].len(), 13);
+    /// }
+    /// ```
+    pub fn deal<R: TrickRules + ?Sized>(rules: &R, deck: &mut Deck, dealer: usize) -> Option<Round> {
+        let players = rules.players();
+        let dealt = deck.deal(players, rules.hand_size())?;
+        let last = (players * rules.hand_size()).checked_sub(1)? % players;
+        let trump = rules.trump(to_card(*dealt[last].last()?));
+
+        let mut hands = vec![Vec::new(); players];
+        for (index, hand) in dealt.into_iter().enumerate() {
+            hands[(dealer + 1 + index) % players] = hand;
+        }
+
+        let mut round = Round { dealer, hands, trump, turn: 0, trick: Vec::new(), tricks: Vec::new() };
+        round.turn = rules.first_leader(&round);
+        Some(round)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.trick.is_empty() && self.hands.iter().all(Vec::is_empty)
+    }
+
+    /// Plays a card for a seat.
+    ///
+    /// returns: Result<Option<usize>, TrickError>, the winning seat when the card completes the trick.
+    pub fn play<R: TrickRules + ?Sized>(&mut self, rules: &R, seat: usize, card: PlayingCard) -> Result<Option<usize>, TrickError> {
+        if self.is_over() { return Err(TrickError::IllegalAction); }
+        if seat != self.turn { return Err(TrickError::NotYourTurn); }
+        if !self.hands[seat].contains(&card.motif()) { return Err(TrickError::CardNotInHand(card)); }
+        if !rules.legal_cards(self, seat).contains(&card.motif()) { return Err(TrickError::IllegalCard(card)); }
+
+        self.hands[seat].retain(|motif| *motif != card.motif());
+        self.trick.push(Played { seat, card: card.motif() });
+        if self.trick.len() < rules.players() {
+            self.turn = (self.turn + 1) % rules.players();
+            return Ok(None);
+        }
+
+        let winner = rules.trick_winner(self, &self.trick);
+        self.tricks.push(Trick { cards: std::mem::take(&mut self.trick), winner });
+        self.turn = winner;
+        Ok(Some(winner))
+    }
+
+    /// Number of tricks won by each seat.
+    pub fn tricks_won(&self, players: usize) -> Vec<usize> {
+        let mut won = vec![0; players];
+        for trick in self.tricks.iter() {
+            won[trick.winner] += 1;
+        }
+        won
+    }
+}
@@ -0,0 +1,118 @@
+use actix_web::{HttpResponse, Result, HttpRequest, web};
+use actix_web::http::StatusCode;
+use uuid::Uuid;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use jeu_de_carte::batch::{BatchRepresentation, BatchRequest, Operation};
+use jeu_de_carte::i18n::Message;
+use crate::controller::{apply_batch, create_deck, event_hub, read_deck, remove_deck, represent, request_language, request_theme, Credentials, Failure};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeckBody {
+    /// 32, 52 or 78 cards, 52 when missing.
+    pub size: Option<i32>,
+    pub theme: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ShuffleBody {
+    pub shuffle_discarded: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SwitchBody {
+    pub lifo: Option<bool>,
+    pub length: Option<usize>,
+    pub move_as_block: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MoveBody {
+    /// The cards to move, as motifs or codes such as `"QH"`.
+    #[serde(deserialize_with = "jeu_de_carte::card::deserialize_cards")]
+    pub cards: Vec<i32>,
+    pub to_discarded: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DealBody {
+    pub hands: usize,
+    pub length: usize,
+}
+
+/// The id of the deck of the path.
+fn deck_id(req: &HttpRequest) -> std::result::Result<Uuid, Failure> {
+    match req.match_info().get("id") {
+        Some(id) => Uuid::parse_str(id).map_err(|_| Failure::incorrect("id")),
+        None => Err(Failure::missing("id"))
+    }
+}
+
+/// Applies operations to the deck of the path, answering the deck along with the result of each operation.
+fn operate(req: &HttpRequest, operations: &[Operation]) -> HttpResponse {
+    let language = request_language(req);
+    let outcome = deck_id(req).and_then(|deck_id| {
+        match apply_batch(event_hub(req), &Credentials::from_request(req), deck_id, operations) {
+            Some(outcome) => outcome.map_err(Failure::from),
+            None => Err(Failure::new(StatusCode::NOT_FOUND, Message::DeckNotFound))
+        }
+    });
+
+    match outcome {
+        Ok((deck, results)) => {
+            let theme = request_theme(req, &deck);
+            HttpResponse::Ok().json(BatchRepresentation::translated(deck, results, language, &theme))
+        }
+        Err(failure) => failure.respond(language)
+    }
+}
+
+pub async fn post_deck(req: HttpRequest, body: web::Json<DeckBody>) -> Result<HttpResponse> {
+    let body = body.into_inner();
+    let language = request_language(&req);
+    match create_deck(&req, body.size, body.theme) {
+        Ok(deck) => Ok(HttpResponse::Created().json(represent(&req, deck, language))),
+        Err(failure) => Ok(failure.respond(language))
+    }
+}
+
+pub async fn get_deck(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    match deck_id(&req).and_then(|deck_id| read_deck(&req, deck_id)) {
+        Ok(deck) => Ok(HttpResponse::Ok().json(represent(&req, deck, language))),
+        Err(failure) => Ok(failure.respond(language))
+    }
+}
+
+pub async fn delete_deck(req: HttpRequest) -> Result<HttpResponse> {
+    let language = request_language(&req);
+    match deck_id(&req).and_then(|deck_id| remove_deck(&req, deck_id)) {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(failure) => Ok(failure.respond(language))
+    }
+}
+
+pub async fn shuffle_deck(req: HttpRequest, body: web::Json<ShuffleBody>) -> Result<HttpResponse> {
+    Ok(operate(&req, &[Operation::Shuffle { shuffle_discarded: body.shuffle_discarded }]))
+}
+
+pub async fn take_cards(req: HttpRequest, body: web::Json<SwitchBody>) -> Result<HttpResponse> {
+    Ok(operate(&req, &[Operation::Take { lifo: body.lifo, length: body.length, move_as_block: body.move_as_block }]))
+}
+
+pub async fn put_cards(req: HttpRequest, body: web::Json<SwitchBody>) -> Result<HttpResponse> {
+    Ok(operate(&req, &[Operation::Put { lifo: body.lifo, length: body.length, move_as_block: body.move_as_block }]))
+}
+
+pub async fn move_cards(req: HttpRequest, body: web::Json<MoveBody>) -> Result<HttpResponse> {
+    let body = body.into_inner();
+    Ok(operate(&req, &[Operation::Move { cards: body.cards, to_discarded: body.to_discarded }]))
+}
+
+pub async fn deal_cards(req: HttpRequest, body: web::Json<DealBody>) -> Result<HttpResponse> {
+    Ok(operate(&req, &[Operation::Deal { hands: body.hands, length: body.length }]))
+}
+
+pub async fn apply_operations(req: HttpRequest, body: web::Json<BatchRequest>) -> Result<HttpResponse> {
+    Ok(operate(&req, &body.operations))
+}
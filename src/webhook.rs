@@ -0,0 +1,525 @@
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use postgres::GenericClient;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use url::Url;
+use uuid::Uuid;
+use crate::Deck;
+use crate::api_key::{check_access, AccessError};
+use crate::database::establish_connection;
+use crate::metrics;
+
+/// The header carrying the HMAC-SHA256 of the body, as `sha256={hex}`.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DEFAULT_ATTEMPTS: usize = 5;
+pub const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of threads sending the deliveries.
+pub const WORKERS: usize = 4;
+/// Number of deliveries waiting to be sent, the next ones being stored as dead letters.
+pub const QUEUE_SIZE: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Created,
+    Shuffled,
+    Drawn,
+    /// The last cards of the deck were drawn.
+    Emptied,
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 5] = [
+        WebhookEvent::Created,
+        WebhookEvent::Shuffled,
+        WebhookEvent::Drawn,
+        WebhookEvent::Emptied,
+        WebhookEvent::Deleted,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::Created => "created",
+            WebhookEvent::Shuffled => "shuffled",
+            WebhookEvent::Drawn => "drawn",
+            WebhookEvent::Emptied => "emptied",
+            WebhookEvent::Deleted => "deleted",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WebhookEvent> {
+        WebhookEvent::ALL.iter().copied().find(|event| event.name() == name)
+    }
+}
+
+/// The events of a change drawing cards from a deck, which is emptied when its last cards are drawn.
+///
+/// # Arguments
+///
+/// * `before`: The number of cards in the deck before the change.
+/// * `deck`: The deck after the change.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::{Deck, DeckSize};
+/// use jeu_de_carte::webhook::{drawn, WebhookEvent};
+///
+/// fn main() {
+///     let mut deck = Deck::new(DeckSize::Small);
+///     deck.take(false, 32, false);
+///     assert_eq!(drawn(32, &deck), vec![WebhookEvent::Drawn, WebhookEvent::Emptied]);
+///     assert!(drawn(0, &deck).is_empty());
+/// }
+/// ```
+pub fn drawn(before: usize, deck: &Deck) -> Vec<WebhookEvent> {
+    match deck.cards.len() {
+        0 if before > 0 => vec![WebhookEvent::Drawn, WebhookEvent::Emptied],
+        after if after < before => vec![WebhookEvent::Drawn],
+        _ => Vec::new()
+    }
+}
+
+/// Signs a body with the secret of a webhook.
+///
+/// returns: String, the hexadecimal HMAC-SHA256 of the body.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size.");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Tells if an address can be reached from the internet, the webhooks never being sent to the network of the API.
+///
+/// # Examples
+///
+/// ```
+/// use std::net::IpAddr;
+/// use jeu_de_carte::webhook::is_public;
+///
+/// fn main() {
+///     assert!(is_public("93.184.216.34".parse::<IpAddr>().unwrap()));
+///     assert!(!is_public("127.0.0.1".parse::<IpAddr>().unwrap()));
+///     assert!(!is_public("169.254.169.254".parse::<IpAddr>().unwrap()));
+///     assert!(!is_public("10.0.0.1".parse::<IpAddr>().unwrap()));
+///     assert!(!is_public("::ffff:192.168.1.1".parse::<IpAddr>().unwrap()));
+///     assert!(!is_public("fd00::1".parse::<IpAddr>().unwrap()));
+/// }
+/// ```
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+                || ip.is_multicast() || ip.is_documentation() || first == 0 || (first == 100 && (64..128).contains(&second)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+            }
+        }
+    }
+}
+
+/// Resolves a host, given as `host:port`, refusing it when one of its addresses isn't public.
+fn resolve_public(netloc: &str) -> io::Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+    if addresses.is_empty() || !addresses.iter().all(|address| is_public(address.ip())) {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("The host {} has no public address", netloc)));
+    }
+
+    Ok(addresses)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UrlError {
+    /// The URL isn't an HTTP or HTTPS URL.
+    Invalid,
+    /// The host of the URL can't be resolved, or one of its addresses isn't public.
+    ForbiddenHost,
+}
+
+/// Checks that a webhook may be sent to a URL, its host being resolved to make sure it is public.
+///
+/// # Examples
+///
+/// ```
+/// use jeu_de_carte::webhook::{check_url, UrlError};
+///
+/// fn main() {
+///     assert_eq!(check_url("ftp://example.com"), Err(UrlError::Invalid));
+///     assert_eq!(check_url("http://127.0.0.1:8080/hook"), Err(UrlError::ForbiddenHost));
+///     assert_eq!(check_url("http://[::1]/hook"), Err(UrlError::ForbiddenHost));
+///     assert_eq!(check_url("http://169.254.169.254/latest/meta-data"), Err(UrlError::ForbiddenHost));
+///     assert_eq!(check_url("https://93.184.216.34/hook"), Ok(()));
+/// }
+/// ```
+pub fn check_url(url: &str) -> Result<(), UrlError> {
+    let url = Url::parse(url).map_err(|_| UrlError::Invalid)?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(UrlError::Invalid);
+    }
+    let netloc = match (url.host_str(), url.port_or_known_default()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        _ => return Err(UrlError::Invalid)
+    };
+
+    resolve_public(&netloc).map(|_| ()).map_err(|_| UrlError::ForbiddenHost)
+}
+
+/// A subscription to the events of a deck, or of every deck when it has no deck.
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub deck_id: Option<Uuid>,
+    pub url: String,
+    /// The key signing the payloads, only shown when the webhook is created.
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    /// The API key which created the webhook, None when created with the admin token.
+    pub owner: Option<Uuid>,
+}
+
+impl Webhook {
+    pub fn new(url: &str, deck_id: Option<Uuid>, events: Vec<WebhookEvent>) -> Webhook {
+        let secret: [u8; 32] = rand::thread_rng().gen();
+        Webhook { id: Uuid::new_v4(), deck_id, url: url.to_string(), secret: hex::encode(secret), events, owner: None }
+    }
+
+    /// Tells if an API key may see or delete the webhook, only its owner being allowed, and the admin for the webhooks without owner.
+    pub fn authorize(&self, api_key: Option<Uuid>) -> Result<(), AccessError> {
+        match self.owner {
+            Some(owner) => check_access(api_key, Some(owner), |_| false),
+            None => Err(AccessError::AdminRequired)
+        }
+    }
+
+    fn event_names(&self) -> Vec<&'static str> {
+        self.events.iter().map(|event| event.name()).collect()
+    }
+
+    pub fn insert_into_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "INSERT INTO webhooks (id, deck_id, url, secret, events, owner) VALUES ($1, $2, $3, $4, $5, $6)"
+            ).unwrap();
+        metrics::time_query("insert_webhook", || connection.execute(&stmt, &[&self.id, &self.deck_id, &self.url, &self.secret, &self.event_names(), &self.owner])).expect("An error occurred.");
+    }
+
+    pub fn delete_from_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "DELETE FROM webhooks WHERE id = $1"
+            ).unwrap();
+
+        metrics::time_query("delete_webhook", || connection.execute(&stmt, &[&self.id])).expect("An error occurred.");
+    }
+
+    pub fn find_by_id<C: GenericClient>(id: Uuid, connection: &mut C) -> Option<Webhook> {
+        let stmt = connection.prepare("SELECT id, deck_id, url, secret, events, owner FROM webhooks WHERE id = $1").unwrap();
+        match metrics::time_query("find_webhook", || connection.query(&stmt, &[&id])) {
+            Ok(rows) => rows.first().map(Webhook::from_row),
+            Err(_) => None
+        }
+    }
+
+    /// Finds the webhooks subscribed to an event of a deck, including the ones subscribed to every deck.
+    pub fn find_subscribed<C: GenericClient>(deck_id: Uuid, event: WebhookEvent, connection: &mut C) -> Vec<Webhook> {
+        let stmt = connection
+            .prepare(
+                "SELECT id, deck_id, url, secret, events, owner FROM webhooks WHERE (deck_id IS NULL OR deck_id = $1) AND $2 = ANY(events)"
+            ).unwrap();
+        match metrics::time_query("find_webhooks", || connection.query(&stmt, &[&deck_id, &event.name()])) {
+            Ok(rows) => rows.iter().map(Webhook::from_row).collect(),
+            Err(_) => Vec::new()
+        }
+    }
+
+    fn from_row(row: &postgres::Row) -> Webhook {
+        let events: Vec<String> = row.get(4);
+        Webhook {
+            id: row.get(0),
+            deck_id: row.get(1),
+            url: row.get(2),
+            secret: row.get(3),
+            events: events.iter().filter_map(|name| WebhookEvent::from_name(name)).collect(),
+            owner: row.get(5),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WebhookRepresentation {
+    pub id: Uuid,
+    pub deck_id: Option<Uuid>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl WebhookRepresentation {
+    /// Represents a webhook, its secret being only shown when asked.
+    pub fn from(webhook: &Webhook, show_secret: bool) -> WebhookRepresentation {
+        WebhookRepresentation {
+            id: webhook.id,
+            deck_id: webhook.deck_id,
+            url: webhook.url.clone(),
+            events: webhook.events.clone(),
+            secret: if show_secret { Some(webhook.secret.clone()) } else { None },
+        }
+    }
+}
+
+/// The JSON body sent to the webhooks.
+#[derive(Serialize)]
+pub struct Payload {
+    /// The id of the delivery, the same for every attempt.
+    pub id: Uuid,
+    pub event: WebhookEvent,
+    pub deck_id: Uuid,
+    pub cards_count: usize,
+    pub discarded_count: usize,
+    pub sent_at: String,
+}
+
+impl Payload {
+    pub fn new(event: WebhookEvent, deck: &Deck) -> Payload {
+        Payload {
+            id: Uuid::new_v4(),
+            event,
+            deck_id: deck.id,
+            cards_count: deck.cards.len(),
+            discarded_count: deck.discarded.len(),
+            sent_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// A payload to send to a webhook.
+pub struct Delivery {
+    pub webhook: Webhook,
+    pub event: WebhookEvent,
+    pub body: String,
+}
+
+impl Delivery {
+    /// Prepares the deliveries of the events of a deck to the webhooks subscribed to them.
+    pub fn prepare<C: GenericClient>(connection: &mut C, deck: &Deck, events: &[WebhookEvent]) -> Vec<Delivery> {
+        events.iter()
+            .flat_map(|event| {
+                let body = serde_json::to_string(&Payload::new(*event, deck)).expect("An error occurred.");
+                Webhook::find_subscribed(deck.id, *event, connection)
+                    .into_iter()
+                    .map(move |webhook| Delivery { webhook, event: *event, body: body.clone() })
+            })
+            .collect()
+    }
+}
+
+/// How many times a delivery is attempted, the delay doubling after each failure.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub attempts: usize,
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { attempts: DEFAULT_ATTEMPTS, base_delay: DEFAULT_DELAY }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the next attempt, after a number of failed attempts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use jeu_de_carte::webhook::RetryPolicy;
+    ///
+    /// fn main() {
+    ///     let policy = RetryPolicy { attempts: 5, base_delay: Duration::from_secs(1) };
+    ///     assert_eq!(policy.delay(1), Duration::from_secs(1));
+    ///     assert_eq!(policy.delay(3), Duration::from_secs(4));
+    /// }
+    /// ```
+    pub fn delay(&self, failures: usize) -> Duration {
+        self.base_delay * 2u32.saturating_pow(failures.saturating_sub(1) as u32)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeliveryError {
+    pub attempts: usize,
+    pub error: String,
+}
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (after {} attempts)", self.error, self.attempts)
+    }
+}
+
+/// Sends a delivery until the webhook answers with a success status, or the attempts run out.
+///
+/// returns: Result<usize, DeliveryError>, the number of attempts made.
+pub fn send(delivery: &Delivery, policy: &RetryPolicy) -> Result<usize, DeliveryError> {
+    send_with(&ureq::AgentBuilder::new().timeout(TIMEOUT).redirects(0).build(), delivery, policy)
+}
+
+/// Sends a delivery like `send`, only connecting to the public addresses of its host,
+/// which is resolved again for each attempt.
+pub fn deliver(delivery: &Delivery, policy: &RetryPolicy) -> Result<usize, DeliveryError> {
+    send_with(&ureq::AgentBuilder::new().timeout(TIMEOUT).redirects(0).resolver(resolve_public).build(), delivery, policy)
+}
+
+fn send_with(agent: &ureq::Agent, delivery: &Delivery, policy: &RetryPolicy) -> Result<usize, DeliveryError> {
+    let signature = format!("sha256={}", sign(&delivery.webhook.secret, &delivery.body));
+    let mut error = String::new();
+
+    for attempt in 1..=policy.attempts {
+        let response = agent.post(&delivery.webhook.url)
+            .set("Content-Type", "application/json")
+            .set(SIGNATURE_HEADER, &signature)
+            .set(EVENT_HEADER, delivery.event.name())
+            .send_string(&delivery.body);
+        match response {
+            Ok(response) if (200..300).contains(&response.status()) => return Ok(attempt),
+            Ok(response) => error = format!("Unexpected status {}", response.status()),
+            Err(ureq::Error::Status(status, _)) => error = format!("Unexpected status {}", status),
+            Err(transport) => error = transport.to_string(),
+        }
+        if attempt < policy.attempts {
+            thread::sleep(policy.delay(attempt));
+        }
+    }
+
+    Err(DeliveryError { attempts: policy.attempts, error })
+}
+
+/// A delivery which kept failing, kept to be looked at.
+#[derive(Serialize, JsonSchema)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub error: String,
+    pub failed_at: String,
+}
+
+impl DeadLetter {
+    pub fn new(delivery: &Delivery, error: &DeliveryError) -> DeadLetter {
+        DeadLetter {
+            id: Uuid::new_v4(),
+            webhook_id: delivery.webhook.id,
+            event: delivery.event.name().to_string(),
+            payload: serde_json::from_str(&delivery.body).expect("An error occurred."),
+            attempts: error.attempts as i32,
+            error: error.error.clone(),
+            failed_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn insert_into_db<C: GenericClient>(&self, connection: &mut C) {
+        let stmt = connection
+            .prepare(
+                "INSERT INTO webhook_dead_letters (id, webhook_id, event, payload, attempts, error) VALUES ($1, $2, $3, $4, $5, $6)"
+            ).unwrap();
+        metrics::time_query("insert_dead_letter", || connection.execute(&stmt, &[&self.id, &self.webhook_id, &self.event, &self.payload, &self.attempts, &self.error]))
+            .expect("An error occurred.");
+    }
+
+    pub fn find_by_webhook<C: GenericClient>(webhook_id: Uuid, connection: &mut C) -> Vec<DeadLetter> {
+        let stmt = connection
+            .prepare(
+                "SELECT id, webhook_id, event, payload, attempts, error, created_at FROM webhook_dead_letters WHERE webhook_id = $1 ORDER BY created_at"
+            ).unwrap();
+        match metrics::time_query("find_dead_letters", || connection.query(&stmt, &[&webhook_id])) {
+            Ok(rows) => rows.iter()
+                .map(|row| {
+                    let failed_at: DateTime<Utc> = row.get(6);
+                    DeadLetter {
+                        id: row.get(0),
+                        webhook_id: row.get(1),
+                        event: row.get(2),
+                        payload: row.get(3),
+                        attempts: row.get(4),
+                        error: row.get(5),
+                        failed_at: failed_at.to_rfc3339(),
+                    }
+                })
+                .collect(),
+            Err(_) => Vec::new()
+        }
+    }
+}
+
+/// A delivery waiting in the queue, along with the span it was dispatched in.
+struct Job {
+    delivery: Delivery,
+    policy: RetryPolicy,
+    span: tracing::Span,
+}
+
+/// The queue of the deliveries, started with its workers on the first dispatch.
+fn queue() -> &'static SyncSender<Job> {
+    static QUEUE: OnceLock<SyncSender<Job>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(QUEUE_SIZE);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..WORKERS {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || loop {
+                let job = match receiver.lock().expect("An error occurred.").recv() {
+                    Ok(job) => job,
+                    Err(_) => return
+                };
+                let _span = job.span.entered();
+                if let Err(error) = deliver(&job.delivery, &job.policy) {
+                    bury(&job.delivery, &error);
+                }
+            });
+        }
+        sender
+    })
+}
+
+/// Stores a delivery which failed as a dead letter.
+fn bury(delivery: &Delivery, error: &DeliveryError) {
+    tracing::warn!(webhook_id = %delivery.webhook.id, event = delivery.event.name(), error = %error, "webhook delivery failed");
+    let mut connection = establish_connection();
+    DeadLetter::new(delivery, error).insert_into_db(&mut connection);
+    connection.close().ok();
+}
+
+/// Queues the deliveries, sent in the background by a few workers, the ones which keep failing being stored as dead letters.
+///
+/// When the queue is full the delivery is stored as a dead letter right away, without any attempt.
+/// The deliveries are logged in the current spans, such as the one of the request which triggered them.
+pub fn dispatch(deliveries: Vec<Delivery>, policy: RetryPolicy) {
+    for delivery in deliveries {
+        let job = Job { delivery, policy, span: tracing::Span::current() };
+        if let Err(TrySendError::Full(job) | TrySendError::Disconnected(job)) = queue().try_send(job) {
+            bury(&job.delivery, &DeliveryError { attempts: 0, error: "The delivery queue is full".to_string() });
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::card::{PlayingCard, Suit};
+use crate::trick::{Round, TrickRules};
+
+/// Tricks a team must take before scoring.
+pub const BOOK: usize = 6;
+
+/// Whist for the teams of seats 0 and 2 and of seats 1 and 3, the last card dealt setting the trump.
+pub struct Whist;
+
+impl TrickRules for Whist {
+    fn players(&self) -> usize {
+        4
+    }
+
+    fn hand_size(&self) -> usize {
+        13
+    }
+
+    fn trump(&self, last_dealt: PlayingCard) -> Option<Suit> {
+        last_dealt.suit()
+    }
+
+    /// Each team scores one point per trick over the book, both seats of a team sharing its score.
+    fn scores(&self, round: &Round) -> Vec<i32> {
+        let won = round.tricks_won(self.players());
+        let teams = [won[0] + won[2], won[1] + won[3]];
+        (0..self.players()).map(|seat| teams[seat % 2].saturating_sub(BOOK) as i32).collect()
+    }
+}